@@ -0,0 +1,54 @@
+//! Benchmark di `get_changed_regions` su un buffer in cui cambia una sola cella.
+//!
+//! Esegui con `cargo bench --bench changed_regions`.
+
+use standard_terminal_graphics::{Rect, StyledChar, StyledFrameBuffer};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 2000;
+
+/// Versione cella per cella, equivalente all'implementazione precedente
+fn changed_regions_per_cell(a: &StyledFrameBuffer, b: &StyledFrameBuffer) -> Vec<Rect> {
+    let block_size = 8;
+    let mut regions = Vec::new();
+    
+    for block_y in (0..a.height).step_by(block_size) {
+        for block_x in (0..a.width).step_by(block_size) {
+            let end_x = (block_x + block_size).min(a.width);
+            let end_y = (block_y + block_size).min(a.height);
+            
+            let changed = (block_y..end_y)
+                .any(|y| (block_x..end_x).any(|x| a.is_different_from(b, x, y)));
+            if changed {
+                regions.push(Rect::new(block_x, block_y, end_x - block_x, end_y - block_y));
+            }
+        }
+    }
+    
+    regions
+}
+
+fn main() {
+    let a = StyledFrameBuffer::new(200, 50);
+    let mut b = a.clone();
+    b.set(150, 40, StyledChar::new('x'));
+    
+    assert_eq!(a.get_changed_regions(&b), changed_regions_per_cell(&a, &b));
+    
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(changed_regions_per_cell(black_box(&a), black_box(&b)));
+    }
+    let per_cell = start.elapsed();
+    
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(black_box(&a).get_changed_regions(black_box(&b)));
+    }
+    let chunked = start.elapsed();
+    
+    println!("per-cell: {:?}/iter", per_cell / ITERATIONS);
+    println!("chunked:  {:?}/iter", chunked / ITERATIONS);
+    println!("speedup:  {:.1}x", per_cell.as_secs_f64() / chunked.as_secs_f64());
+}
@@ -9,14 +9,14 @@
 //! - FPS bloccato a 60 con controllo prestazioni
 
 use standard_terminal_graphics::{
-    StyledFrameBuffer, FrameBuffer, Rect, Color, FrameTimer,
+    StyledFrameBuffer, FrameBuffer, Rect, Point, Color, FrameTimer,
     input::{InputManager, InputEvent},
+    ui::{TextArea, Widget},
     renderer::SmartRenderer,
-    StyledChar, image_to_braille_fb,
+    StyledChar, GradientDirection, image_to_braille_fb_boxed, Result,
 };
 use image::DynamicImage;
 use std::time::Duration;
-use std::io::{self};
 use crossterm::{event::KeyCode, event::MouseEventKind};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -43,6 +43,7 @@ struct Window {
     cursor_pos: usize,
     video_frame: usize,
     image_data: Option<FrameBuffer>,
+    editor: TextArea,
     closed: bool,  // Flag per evitare riaperture automatiche
 }
 
@@ -62,8 +63,10 @@ impl Window {
             cursor_pos: 0,
             video_frame: 0,
             image_data: None,
+            editor: TextArea::new(Rect::new(0, 0, 0, 0)),
             closed: false,
         };
+        window.editor.set_text("Text Editor\n\nShift+arrows select, Enter splits lines\n\nPress F2 for new editor");
         window.update_content();
         window
     }
@@ -212,18 +215,15 @@ impl Window {
         }
         
         if let Some(ref image_fb) = self.image_data {
-            // Centra l'immagine con bounds checking rigoroso
+            // L'immagine è già centrata nel box grande quanto l'area contenuto
             let safe_width = image_fb.width.min(content_area.width);
             let safe_height = image_fb.height.min(content_area.height);
             
-            let img_x = content_area.x + (content_area.width.saturating_sub(safe_width)) / 2;
-            let img_y = content_area.y + (content_area.height.saturating_sub(safe_height)) / 2;
-            
             // Disegna l'immagine con controllo rigoroso dei bounds
             for y in 0..safe_height {
                 for x in 0..safe_width {
-                    let dst_x = img_x + x;
-                    let dst_y = img_y + y;
+                    let dst_x = content_area.x + x;
+                    let dst_y = content_area.y + y;
                     
                     if dst_x < self.content.width && dst_y < self.content.height {
                         let ch = image_fb.get(x, y);
@@ -378,45 +378,8 @@ impl Window {
             return;
         }
         
-        // Contenuto editor semplificato
-        let lines = [
-            "Text Editor",
-            "",
-            "Line 1: Hello World",
-            "Line 2: Sample text",
-            "Line 3: More content",
-            "",
-            "Press F2 for new editor",
-        ];
-        
-        for (i, line) in lines.iter().enumerate() {
-            if i >= content_area.height {
-                break;
-            }
-            
-            let display_line = if line.len() > content_area.width {
-                &line[..content_area.width]
-            } else {
-                line
-            };
-            
-            self.content.draw_text(
-                content_area.x,
-                content_area.y + i,
-                display_line,
-                Some(Color::White),
-                None
-            );
-        }
-        
-        // Cursore editor
-        if content_area.height > 2 {
-            self.content.set(
-                content_area.x,
-                content_area.y + 2,
-                StyledChar::new('|').with_fg(Color::Yellow)
-            );
-        }
+        self.editor.set_rect(content_area);
+        self.editor.render(&mut self.content);
     }
 
     fn handle_terminal_input(&mut self, ch: char) {
@@ -461,11 +424,10 @@ impl Window {
                 }
             },
             _ => {
-                if ch.is_ascii_graphic() || ch == ' ' {
-                    if self.current_line.len() < 80 { // Limita lunghezza input
-                        self.current_line.insert(self.cursor_pos, ch);
-                        self.cursor_pos += 1;
-                    }
+                // Limita lunghezza input
+                if (ch.is_ascii_graphic() || ch == ' ') && self.current_line.len() < 80 {
+                    self.current_line.insert(self.cursor_pos, ch);
+                    self.cursor_pos += 1;
                 }
             }
         }
@@ -499,7 +461,7 @@ impl Window {
 
 enum DragMode {
     None,
-    Move { window_id: usize, offset: (isize, isize) },
+    Move { window_id: usize },
     Resize { window_id: usize, anchor: (usize, usize) },
 }
 
@@ -531,9 +493,12 @@ struct DesktopEnvironment {
 }
 
 impl DesktopEnvironment {
-    fn new() -> io::Result<Self> {
+    fn new() -> Result<Self> {
+        InputManager::install_panic_restore();
         let input_manager = InputManager::new()?;
-        let renderer = SmartRenderer::new()?;
+        let mut renderer = SmartRenderer::new()?;
+        // Frame presentati in blocco: niente tearing trascinando le finestre
+        renderer.set_sync_output(true);
         let workspace_size = renderer.get_workspace_size();
         
         let main_fb = StyledFrameBuffer::new(workspace_size.0, workspace_size.1);
@@ -550,7 +515,7 @@ impl DesktopEnvironment {
             mouse_position: (workspace_size.0 / 2, workspace_size.1 / 2),
             last_mouse_position: (workspace_size.0 / 2, workspace_size.1 / 2),
             show_mouse: true,
-            fps_counter: 60.0,
+            fps_counter: 0.0,
             frame_count: 0,
             taskbar_height: 2,
             last_fps_update: std::time::Instant::now(),
@@ -619,11 +584,10 @@ impl DesktopEnvironment {
     fn load_sample_image(&mut self, window_id: usize) {
         // Genera un'immagine di esempio
         let img = self.create_sample_image();
-        if let Ok(braille_fb) = image_to_braille_fb(&img, 30, 15) {
-            if let Some(window) = self.windows.iter_mut().find(|w| w.id == window_id) {
-                window.image_data = Some(braille_fb);
-                window.update_content();
-            }
+        if let Some(window) = self.windows.iter_mut().find(|w| w.id == window_id) {
+            let (w, h) = (window.rect.width.saturating_sub(4), window.rect.height.saturating_sub(5));
+            window.image_data = Some(image_to_braille_fb_boxed(&img, w, h, 128));
+            window.update_content();
         }
     }
 
@@ -632,18 +596,18 @@ impl DesktopEnvironment {
             MouseEventKind::Down(_) => {
                 // Se già in drag, il secondo click termina l'operazione
                 match self.drag_mode {
-                    DragMode::Move { window_id, .. } => {
+                    DragMode::Move { window_id } => {
                         // Secondo click: sposta la finestra
-                        if let Some((start_x, start_y)) = self.drag_start {
-                            let dx = workspace_x as isize - start_x as isize;
-                            let dy = workspace_y as isize - start_y as isize;
+                        if let Some(start) = self.drag_start {
+                            let (dx, dy) = Point::from(start).delta_to(Point::new(workspace_x, workspace_y));
+                            let bounds = Rect::new(
+                                0,
+                                0,
+                                self.main_fb.width,
+                                self.main_fb.height.saturating_sub(self.taskbar_height)
+                            );
                             if let Some(window) = self.windows.iter_mut().find(|w| w.id == window_id) {
-                                let new_x = (window.rect.x as isize + dx).max(0)
-                                    .min(self.main_fb.width.saturating_sub(window.rect.width) as isize) as usize;
-                                let new_y = (window.rect.y as isize + dy).max(0)
-                                    .min(self.main_fb.height.saturating_sub(window.rect.height + self.taskbar_height) as isize) as usize;
-                                window.rect.x = new_x;
-                                window.rect.y = new_y;
+                                window.rect = window.rect.moved_within(dx, dy, bounds);
                                 self.mark_full_refresh();
                             }
                         }
@@ -778,7 +742,7 @@ impl DesktopEnvironment {
                     let on_top = workspace_y == window.rect.y && workspace_x > window.rect.x && workspace_x < window.rect.x + window.rect.width - 1;
                     let on_bottom = workspace_y == window.rect.y + window.rect.height - 1 && workspace_x > window.rect.x && workspace_x < window.rect.x + window.rect.width - 1;
                     if on_left || on_right || on_top || on_bottom {
-                        self.drag_mode = DragMode::Move { window_id: window.id, offset: (workspace_x as isize - window.rect.x as isize, workspace_y as isize - window.rect.y as isize) };
+                        self.drag_mode = DragMode::Move { window_id: window.id };
                         self.drag_start = Some((workspace_x, workspace_y));
                         return;
                     }
@@ -827,12 +791,10 @@ impl DesktopEnvironment {
                     self.need_full_refresh = true;
                 }
             },
-            MouseEventKind::Up(_) => {
+            MouseEventKind::Up(_) if self.dragging_window.is_some() => {
                 // Fine drag
-                if self.dragging_window.is_some() {
-                    self.dragging_window = None;
-                    self.mark_full_refresh();
-                }
+                self.dragging_window = None;
+                self.mark_full_refresh();
             },
             MouseEventKind::Moved => {
                 // Drag finestra
@@ -872,7 +834,7 @@ impl DesktopEnvironment {
         }
     }
 
-    fn render(&mut self) -> io::Result<()> {
+    fn render(&mut self) -> Result<()> {
         // Verifica se serve un refresh
         let mouse_moved = self.mouse_position != self.last_mouse_position;
         let has_dirty_windows = self.windows_dirty.iter().any(|&dirty| dirty);
@@ -917,6 +879,10 @@ impl DesktopEnvironment {
                     self.main_fb.set(x, y, StyledChar::new('░').with_fg(Color::White).with_bg(Color::Cyan));
                 }
             }
+            
+            // Sfondo sfumato dall'alto verso il basso
+            let desktop_rect = Rect::new(0, 0, self.main_fb.width, self.main_fb.height);
+            self.main_fb.fill_gradient(desktop_rect, Color::Cyan, Color::Blue, GradientDirection::Vertical);
         }
         
         // Rendering finestre
@@ -990,11 +956,15 @@ impl DesktopEnvironment {
     }
 
     fn draw_mouse_cursor(&mut self) {
+        if !self.show_mouse {
+            return;
+        }
+        
         let (mouse_x, mouse_y) = self.mouse_position;
-        if (mouse_x as usize) < self.main_fb.width && (mouse_y as usize) < self.main_fb.height {
+        if mouse_x < self.main_fb.width && mouse_y < self.main_fb.height {
             self.main_fb.set(
-                mouse_x as usize, 
-                mouse_y as usize, 
+                mouse_x, 
+                mouse_y, 
                 StyledChar::new('▲').with_fg(Color::Yellow).with_bg(Color::Red)
             );
         }
@@ -1114,8 +1084,19 @@ impl DesktopEnvironment {
         }
     }
 
-    fn handle_input(&mut self) -> io::Result<()> {
+    fn handle_input(&mut self) -> Result<()> {
         if let Some(event) = self.input_manager.poll_event(Duration::from_millis(1))? {
+            // L'editor focalizzato consuma per primo i tasti di testo e navigazione
+            if let Some(focused_id) = self.focused_window {
+                if let Some(window) = self.windows.iter_mut()
+                    .find(|w| w.id == focused_id && w.window_type == WindowType::TextEditor && !w.closed) {
+                    if window.editor.handle_input(&event) {
+                        window.update_content();
+                        return Ok(());
+                    }
+                }
+            }
+            
             match event {
                 InputEvent::Quit => {
                     self.running = false;
@@ -1264,12 +1245,10 @@ impl DesktopEnvironment {
                 let window = &self.windows[idx];
                 (window.rect.width.saturating_sub(4), window.rect.height.saturating_sub(5))
             };
-            if let Ok(fb) = image_to_braille_fb(&img, w, h) {
-                if let Some(window) = self.windows.get_mut(idx) {
-                    window.image_data = Some(fb);
-                    window.update_content();
-                    dirty_ids.push(window.id);
-                }
+            if let Some(window) = self.windows.get_mut(idx) {
+                window.image_data = Some(image_to_braille_fb_boxed(&img, w, h, 128));
+                window.update_content();
+                dirty_ids.push(window.id);
             }
         }
         // Ora marca dirty fuori dal borrow mutabile su self.windows
@@ -1308,7 +1287,7 @@ impl DesktopEnvironment {
         self.last_mouse_position = self.mouse_position;
     }
 
-    fn run(&mut self) -> io::Result<()> {
+    fn run(&mut self) -> Result<()> {
         self.renderer.hide_cursor()?;
         
         while self.running {
@@ -1324,7 +1303,7 @@ impl DesktopEnvironment {
             // Debug performance
             if frame_start.elapsed() > Duration::from_millis(25) {
                 // Frame lento, forza refresh
-                if self.frame_count % 20 == 0 {
+                if self.frame_count.is_multiple_of(20) {
                     self.mark_full_refresh();
                 }
             }
@@ -1335,7 +1314,7 @@ impl DesktopEnvironment {
     }
 }
 
-fn main() -> io::Result<()> {
+fn main() -> Result<()> {
     let mut desktop = DesktopEnvironment::new()?;
     let result = desktop.run();
     
@@ -7,6 +7,38 @@ use std::time::{Duration, Instant};
 pub trait Animation {
     fn update(&mut self, delta_time: Duration) -> bool; // Returns true if animation is finished
     fn apply(&self, buffer: &mut StyledFrameBuffer);
+
+    /// Like `apply`, with the manager's shared clock: override it to stay in phase
+    /// with other animations. The default ignores the clock.
+    fn apply_with_clock(&self, buffer: &mut StyledFrameBuffer, _clock: &AnimationClock) {
+        self.apply(buffer);
+    }
+}
+
+/// Global clock of an `AnimationManager`, shared by all its animations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnimationClock {
+    /// Total time advanced by `AnimationManager::update`
+    pub elapsed: Duration,
+    /// Number of `update` calls
+    pub frame: u64,
+}
+
+impl AnimationClock {
+    /// Position inside a cycle of `period`, from 0 up to (excluding) 1; always 0
+    /// for a zero period
+    pub fn phase(&self, period: Duration) -> f32 {
+        if period.is_zero() {
+            return 0.0;
+        }
+        (self.elapsed.as_nanos() % period.as_nanos()) as f32 / period.as_nanos() as f32
+    }
+
+    /// On during the first half of every `period`: animations asking with the
+    /// same period blink together
+    pub fn blink(&self, period: Duration) -> bool {
+        self.phase(period) < 0.5
+    }
 }
 
 /// Frame sequence animation
@@ -14,6 +46,8 @@ pub struct FrameSequence {
     frames: Vec<FrameBuffer>,
     current_frame: usize,
     frame_duration: Duration,
+    /// Durata specifica di ogni frame (es. ritardi di una GIF), se presente
+    frame_delays: Option<Vec<Duration>>,
     last_frame_time: Instant,
     looping: bool,
     finished: bool,
@@ -25,12 +59,28 @@ impl FrameSequence {
             frames,
             current_frame: 0,
             frame_duration: Duration::from_nanos(1_000_000_000 / fps as u64),
+            frame_delays: None,
             last_frame_time: Instant::now(),
             looping: true,
             finished: false,
         }
     }
 
+    /// Create a sequence where every frame keeps its own duration (e.g. from `load_gif_frames`)
+    pub fn from_timed_frames(timed_frames: Vec<(FrameBuffer, Duration)>) -> Self {
+        let (frames, delays): (Vec<_>, Vec<_>) = timed_frames.into_iter().unzip();
+        let mut sequence = Self::new(frames, 10);
+        sequence.frame_delays = Some(delays);
+        sequence
+    }
+
+    fn current_frame_duration(&self) -> Duration {
+        self.frame_delays
+            .as_ref()
+            .and_then(|delays| delays.get(self.current_frame).copied())
+            .unwrap_or(self.frame_duration)
+    }
+
     pub fn set_looping(&mut self, looping: bool) {
         self.looping = looping;
     }
@@ -42,7 +92,7 @@ impl Animation for FrameSequence {
             return true;
         }
 
-        if self.last_frame_time.elapsed() >= self.frame_duration {
+        if self.last_frame_time.elapsed() >= self.current_frame_duration() {
             self.current_frame += 1;
             self.last_frame_time = Instant::now();
 
@@ -75,12 +125,14 @@ impl Animation for FrameSequence {
 /// Animation manager
 pub struct AnimationManager {
     animations: Vec<Box<dyn Animation>>,
+    clock: AnimationClock,
 }
 
 impl AnimationManager {
     pub fn new() -> Self {
         Self {
             animations: Vec::new(),
+            clock: AnimationClock::default(),
         }
     }
 
@@ -88,13 +140,36 @@ impl AnimationManager {
         self.animations.push(animation);
     }
 
+    /// Advance every animation and the shared clock by `delta_time`
     pub fn update(&mut self, delta_time: Duration) {
+        self.clock.elapsed += delta_time;
+        self.clock.frame += 1;
         self.animations.retain_mut(|anim| !anim.update(delta_time));
     }
 
     pub fn apply_all(&self, buffer: &mut StyledFrameBuffer) {
         for animation in &self.animations {
-            animation.apply(buffer);
+            animation.apply_with_clock(buffer, &self.clock);
         }
     }
+
+    pub fn clock(&self) -> AnimationClock {
+        self.clock
+    }
+
+    /// Total time advanced by `update`
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed
+    }
+
+    /// Number of `update` calls
+    pub fn frame_count(&self) -> u64 {
+        self.clock.frame
+    }
+}
+
+impl Default for AnimationManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,65 @@
+//! Run loop helper: the input, update, render and wait cycle every app repeats
+
+use crate::input::{InputEvent, InputManager};
+use crate::renderer::SmartRenderer;
+use crate::{FrameTimer, Result, StyledFrameBuffer};
+use std::time::{Duration, Instant};
+
+/// Application driven by `run_app`
+pub trait App {
+    /// Advance the state by `dt`, the time since the previous update
+    fn update(&mut self, dt: Duration);
+
+    /// Draw the frame into the renderer's back buffer. It still holds the
+    /// previous frame, so only what changed needs redrawing.
+    fn render(&mut self, buf: &mut StyledFrameBuffer);
+
+    /// Handle an input event; return false to quit. Mouse and scroll positions
+    /// are already in buffer coordinates (events outside the workspace are dropped).
+    fn on_event(&mut self, ev: InputEvent) -> bool;
+}
+
+/// Run `app` until `on_event` returns false: set up the terminal, then every
+/// frame deliver the pending events, call `update` and `render`, present the
+/// frame and wait for the next one (`target_fps` is clamped like `FrameTimer`).
+/// The terminal is restored when the loop ends, on error and on panic.
+pub fn run_app<A: App + ?Sized>(app: &mut A, target_fps: u32) -> Result<()> {
+    InputManager::install_panic_restore();
+    // Dropped in reverse order: the input manager restores the terminal last
+    let mut input = InputManager::new()?;
+    let mut renderer = SmartRenderer::new()?;
+    renderer.set_frame_rate(target_fps);
+    let mut timer = FrameTimer::new(target_fps);
+    let mut last_update = Instant::now();
+
+    loop {
+        while let Some(event) = input.poll_event(Duration::ZERO)? {
+            let event = match event {
+                InputEvent::Resize { width, height } => {
+                    renderer.update_terminal_size((width, height))?;
+                    event
+                }
+                InputEvent::Mouse { x, y, kind } => match renderer.terminal_to_workspace(x, y) {
+                    Some((x, y)) => InputEvent::Mouse { x: x as u16, y: y as u16, kind },
+                    None => continue,
+                },
+                InputEvent::Scroll { x, y, delta } => match renderer.terminal_to_workspace(x, y) {
+                    Some((x, y)) => InputEvent::Scroll { x: x as u16, y: y as u16, delta },
+                    None => continue,
+                },
+                event => event,
+            };
+            if !app.on_event(event) {
+                return Ok(());
+            }
+        }
+
+        let now = Instant::now();
+        app.update(now - last_update);
+        last_update = now;
+
+        app.render(renderer.back_buffer_mut());
+        renderer.present()?;
+        timer.wait_for_next_frame();
+    }
+}
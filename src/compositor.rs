@@ -1,6 +1,69 @@
 //! Compositing system for layered rendering
 
-use crate::{StyledFrameBuffer, Rect};
+use alloc::vec::Vec;
+use crate::{braille_char, Color, StyledChar, StyledFrameBuffer, Rect};
+
+/// How a layer's cells combine with what is already composed below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// The layer replaces the cells below (current behavior)
+    #[default]
+    Normal,
+    /// Darkens: white leaves the color below unchanged, black gives black
+    Multiply,
+    /// Lightens: black leaves the color below unchanged, white gives white
+    Screen,
+    /// Saturating sum, e.g. for additive glows
+    Add,
+    /// Per-channel maximum; overlapping braille chars OR their dots
+    Max,
+}
+
+impl BlendMode {
+    /// Combine two RGB colors channel by channel, `src` over `dst`
+    pub fn blend_rgb(self, src: (u8, u8, u8), dst: (u8, u8, u8)) -> (u8, u8, u8) {
+        let channel = |s: u8, d: u8| -> u8 {
+            let (s16, d16) = (s as u16, d as u16);
+            match self {
+                BlendMode::Normal => s,
+                BlendMode::Multiply => ((s16 * d16 + 127) / 255) as u8,
+                BlendMode::Screen => (255 - ((255 - s16) * (255 - d16) + 127) / 255) as u8,
+                BlendMode::Add => s.saturating_add(d),
+                BlendMode::Max => s.max(d),
+            }
+        };
+        (channel(src.0, dst.0), channel(src.1, dst.1), channel(src.2, dst.2))
+    }
+
+    /// Blend two optional colors; a missing, `Reset` or `Transparent` side keeps the other one
+    fn blend_color(self, src: Option<Color>, dst: Option<Color>) -> Option<Color> {
+        let visible = |color: Option<Color>| color.filter(|&color| color != Color::Transparent);
+        match (visible(src), visible(dst)) {
+            (Some(s), Some(d)) if s != Color::Reset && d != Color::Reset => {
+                let (r, g, b) = self.blend_rgb(s.to_rgb(), d.to_rgb());
+                Some(Color::Rgb(r, g, b))
+            }
+            (src, dst) => src.or(dst),
+        }
+    }
+
+    /// Combine a layer cell with the composed cell below. Colors are blended;
+    /// a blank layer char keeps the char below, so overlays only tint it.
+    pub fn blend_cell(self, src: StyledChar, dst: StyledChar) -> StyledChar {
+        if self == BlendMode::Normal {
+            return src;
+        }
+        
+        let braille = |ch: char| ('\u{2800}'..='\u{28FF}').contains(&ch);
+        let mut cell = if src.ch == ' ' { dst } else { src };
+        if self == BlendMode::Max && braille(src.ch) && braille(dst.ch) {
+            cell.ch = braille_char((src.ch as u32 | dst.ch as u32) as u8);
+        }
+        cell.fg_color = self.blend_color(src.fg_color, dst.fg_color);
+        cell.bg_color = self.blend_color(src.bg_color, dst.bg_color);
+        cell
+    }
+}
 
 /// Layer for compositing
 pub struct Layer {
@@ -8,6 +71,11 @@ pub struct Layer {
     pub position: (usize, usize),
     pub visible: bool,
     pub z_order: i32,
+    /// Blank cells (`StyledChar::default()`) let lower layers show through,
+    /// both when composing and when hit-testing
+    pub blend: bool,
+    /// How cells combine with the layers below in `compose`
+    pub blend_mode: BlendMode,
 }
 
 impl Layer {
@@ -17,13 +85,34 @@ impl Layer {
             position: (x, y),
             visible: true,
             z_order: 0,
+            blend: false,
+            blend_mode: BlendMode::Normal,
         }
     }
+
+    /// Area covered by the layer in compositor coordinates
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.position.0, self.position.1, self.buffer.width, self.buffer.height)
+    }
+
+    /// Whether the layer draws something at (x, y), in compositor coordinates
+    fn covers(&self, x: usize, y: usize) -> bool {
+        self.visible
+            && self.rect().contains(x, y)
+            && (!self.blend
+                || self.buffer.get(x - self.position.0, y - self.position.1) != StyledChar::default())
+    }
 }
 
+/// Stable handle to a layer, unaffected by z-order changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayerId(u32);
+
 /// Compositor for managing multiple layers
 pub struct Compositor {
-    layers: Vec<Layer>,
+    /// Kept sorted by z-order, bottom first
+    layers: Vec<(LayerId, Layer)>,
+    next_id: u32,
     output_buffer: StyledFrameBuffer,
 }
 
@@ -31,28 +120,42 @@ impl Compositor {
     pub fn new(width: usize, height: usize) -> Self {
         Self {
             layers: Vec::new(),
+            next_id: 0,
             output_buffer: StyledFrameBuffer::new(width, height),
         }
     }
 
-    pub fn add_layer(&mut self, layer: Layer) {
-        self.layers.push(layer);
-        self.layers.sort_by_key(|l| l.z_order);
+    pub fn add_layer(&mut self, layer: Layer) -> LayerId {
+        let id = LayerId(self.next_id);
+        self.next_id += 1;
+        self.layers.push((id, layer));
+        self.sort_layers();
+        id
+    }
+
+    /// Remove a layer, returning it
+    pub fn remove_layer(&mut self, id: LayerId) -> Option<Layer> {
+        let index = self.layers.iter().position(|(layer_id, _)| *layer_id == id)?;
+        Some(self.layers.remove(index).1)
+    }
+
+    fn sort_layers(&mut self) {
+        // Stable: layers with equal z keep their insertion order
+        self.layers.sort_by_key(|(_, l)| l.z_order);
     }
 
     pub fn compose(&mut self) -> &StyledFrameBuffer {
         self.output_buffer.clear();
 
-        for layer in &self.layers {
-            if layer.visible {
-                let dst_rect = Rect::new(
-                    layer.position.0,
-                    layer.position.1,
-                    layer.buffer.width,
-                    layer.buffer.height,
-                );
-                
-                let src_rect = Rect::new(0, 0, layer.buffer.width, layer.buffer.height);
+        // Hidden layers are skipped entirely
+        for (_, layer) in self.layers.iter().filter(|(_, layer)| layer.visible) {
+            let dst_rect = layer.rect();
+            let src_rect = Rect::new(0, 0, layer.buffer.width, layer.buffer.height);
+            if layer.blend_mode != BlendMode::Normal {
+                Self::compose_blended(&mut self.output_buffer, layer);
+            } else if layer.blend {
+                self.output_buffer.blit_blend(&layer.buffer, src_rect, dst_rect.x, dst_rect.y);
+            } else {
                 self.output_buffer.blit(&layer.buffer, src_rect, dst_rect.x, dst_rect.y);
             }
         }
@@ -60,7 +163,133 @@ impl Compositor {
         &self.output_buffer
     }
 
+    /// Combine every layer cell with the output below using the layer's `BlendMode`
+    fn compose_blended(output: &mut StyledFrameBuffer, layer: &Layer) {
+        let Some(area) = layer.rect().intersection(&Rect::new(0, 0, output.width, output.height)) else {
+            return;
+        };
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                let src = layer.buffer.get(x - layer.position.0, y - layer.position.1);
+                if src.is_transparent() || (layer.blend && src == StyledChar::default()) {
+                    continue;
+                }
+                let cell = layer.blend_mode.blend_cell(src, output.get(x, y));
+                output.set(x, y, cell);
+            }
+        }
+    }
+
+    /// Layer at `index` in z-order (0 = bottom)
     pub fn get_layer_mut(&mut self, index: usize) -> Option<&mut Layer> {
-        self.layers.get_mut(index)
+        self.layers.get_mut(index).map(|(_, layer)| layer)
+    }
+
+    pub fn layer(&self, id: LayerId) -> Option<&Layer> {
+        self.layers.iter().find(|(layer_id, _)| *layer_id == id).map(|(_, layer)| layer)
+    }
+
+    pub fn layer_mut(&mut self, id: LayerId) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|(layer_id, _)| *layer_id == id).map(|(_, layer)| layer)
+    }
+
+    /// Show or hide a layer, keeping its buffer and z-order.
+    /// Returns false if there's no layer with that id.
+    pub fn set_visible(&mut self, id: LayerId, visible: bool) -> bool {
+        match self.layer_mut(id) {
+            Some(layer) => {
+                layer.visible = visible;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the layer is shown; false for unknown ids
+    pub fn is_visible(&self, id: LayerId) -> bool {
+        self.layer(id).is_some_and(|layer| layer.visible)
+    }
+
+    /// Ids from bottom to top
+    pub fn layer_ids(&self) -> Vec<LayerId> {
+        self.layers.iter().map(|(id, _)| *id).collect()
+    }
+
+    /// Place the layer above every other one
+    pub fn bring_to_front(&mut self, id: LayerId) {
+        let top = self.layers.iter().filter(|(layer_id, _)| *layer_id != id).map(|(_, l)| l.z_order).max();
+        if let (Some(top), Some(layer)) = (top, self.layer_mut(id)) {
+            layer.z_order = layer.z_order.max(top + 1);
+        }
+        self.sort_layers();
+    }
+
+    /// Place the layer below every other one
+    pub fn send_to_back(&mut self, id: LayerId) {
+        let bottom = self.layers.iter().filter(|(layer_id, _)| *layer_id != id).map(|(_, l)| l.z_order).min();
+        if let (Some(bottom), Some(layer)) = (bottom, self.layer_mut(id)) {
+            layer.z_order = layer.z_order.min(bottom - 1);
+        }
+        self.sort_layers();
+    }
+
+    /// Topmost visible layer drawing at (x, y); blank cells of blending layers are skipped
+    pub fn hit_top_layer(&self, x: usize, y: usize) -> Option<LayerId> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|(_, layer)| layer.covers(x, y))
+            .map(|(id, _)| *id)
+    }
+}
+
+/// Window onto a buffer larger than the screen, moved by a camera
+pub struct Viewport {
+    pub source: StyledFrameBuffer,
+    /// Top-left cell of `source` shown at the viewport origin
+    pub camera: (usize, usize),
+    /// Visible size, used to keep the camera inside `source`
+    pub view_size: (usize, usize),
+}
+
+impl Viewport {
+    pub fn new(source: StyledFrameBuffer, view_width: usize, view_height: usize) -> Self {
+        Self {
+            source,
+            camera: (0, 0),
+            view_size: (view_width, view_height),
+        }
+    }
+
+    fn max_camera(&self) -> (usize, usize) {
+        (
+            self.source.width.saturating_sub(self.view_size.0),
+            self.source.height.saturating_sub(self.view_size.1),
+        )
+    }
+
+    /// Move the camera to an absolute position, clamped so the view stays inside the source
+    pub fn set_camera(&mut self, x: usize, y: usize) {
+        let (max_x, max_y) = self.max_camera();
+        self.camera = (x.min(max_x), y.min(max_y));
+    }
+
+    /// Move the camera by a relative amount (clamped)
+    pub fn scroll_by(&mut self, dx: isize, dy: isize) {
+        let x = self.camera.0.saturating_add_signed(dx);
+        let y = self.camera.1.saturating_add_signed(dy);
+        self.set_camera(x, y);
+    }
+
+    /// Update the visible size (e.g. after a terminal resize), re-clamping the camera
+    pub fn set_view_size(&mut self, width: usize, height: usize) {
+        self.view_size = (width, height);
+        self.set_camera(self.camera.0, self.camera.1);
+    }
+
+    /// Blit the visible window into the top-left corner of `dst`
+    pub fn render_into(&self, dst: &mut StyledFrameBuffer) -> Rect {
+        let src_rect = Rect::new(self.camera.0, self.camera.1, self.view_size.0, self.view_size.1);
+        dst.blit(&self.source, src_rect, 0, 0)
     }
 }
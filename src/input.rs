@@ -1,65 +1,466 @@
 //! Input handling module for keyboard and mouse events
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, MouseEvent, MouseEventKind},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, MouseEvent, MouseEventKind},
     terminal::{self, ClearType},
     cursor,
     ExecutableCommand,
 };
-use std::io::{self, stdout};
-use std::time::Duration;
+use crate::Result;
+use std::io::{stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+#[cfg(feature = "async")]
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::thread::JoinHandle;
 
 /// Input event types
 #[derive(Debug, Clone, PartialEq)]
 pub enum InputEvent {
     Key(KeyCode),
+    /// Repeat/release of a key, only reported after `enable_enhanced_keys`
+    /// (presses keep arriving as `Key`)
+    KeyKind { code: KeyCode, kind: KeyEventKind },
+    /// Navigation key (arrows, Home/End, PageUp/PageDown) pressed with Shift,
+    /// e.g. to extend a text selection
+    Shifted(KeyCode),
+    /// Navigation or deletion key (arrows, Home/End, Backspace/Delete) pressed
+    /// with Ctrl, e.g. to move or delete by word
+    Ctrl(KeyCode),
     Mouse { x: u16, y: u16, kind: MouseEventKind },
+    /// Vertical wheel at (x, y): positive = up, negative = down. Notches already
+    /// queued in the same direction are merged, so fast scrolling gives `|delta| > 1`.
+    Scroll { x: u16, y: u16, delta: i32 },
     Resize { width: u16, height: u16 },
+    /// Periodic tick from `InputManager::poll_with_tick`, sent when no other event is pending
+    Tick,
     Quit,
+    /// Any terminal event not modeled above (focus changes, paste, ...), passed through as-is
+    Other(Event),
 }
 
+/// Shortcut table mapping a key plus modifiers to an application action
+#[derive(Debug, Clone)]
+pub struct KeyMap<A> {
+    bindings: std::collections::HashMap<(KeyCode, KeyModifiers), A>,
+}
+
+impl<A> KeyMap<A> {
+    /// Keymap with no bindings
+    pub fn empty() -> Self {
+        Self {
+            bindings: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Bind a key, returning the action it was previously bound to
+    pub fn bind(&mut self, code: KeyCode, modifiers: KeyModifiers, action: A) -> Option<A> {
+        self.bindings.insert((code, modifiers), action)
+    }
+
+    /// Remove a binding, returning its action
+    pub fn unbind(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Option<A> {
+        self.bindings.remove(&(code, modifiers))
+    }
+
+    pub fn get(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<&A> {
+        self.bindings.get(&(code, modifiers))
+    }
+
+    /// Action bound to a key event: `Key` resolves without modifiers, `Shifted`
+    /// with Shift and `Ctrl` with Control. Other events resolve to `None`.
+    pub fn resolve(&self, event: &InputEvent) -> Option<&A> {
+        match event {
+            InputEvent::Key(code) => self.get(*code, KeyModifiers::NONE),
+            InputEvent::Shifted(code) => self.get(*code, KeyModifiers::SHIFT),
+            InputEvent::Ctrl(code) => self.get(*code, KeyModifiers::CONTROL),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+}
+
+impl KeyMap<()> {
+    /// q, Esc, Ctrl+C and Ctrl+D: the keys `InputManager` reports as
+    /// `InputEvent::Quit` unless told otherwise with `set_quit_keys`
+    pub fn default_quit_keys() -> Self {
+        let mut keymap = Self::empty();
+        keymap.bind(KeyCode::Char('q'), KeyModifiers::NONE, ());
+        keymap.bind(KeyCode::Esc, KeyModifiers::NONE, ());
+        keymap.bind(KeyCode::Char('c'), KeyModifiers::CONTROL, ());
+        keymap.bind(KeyCode::Char('d'), KeyModifiers::CONTROL, ());
+        keymap
+    }
+}
+
+/// Actions of the default keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StandardAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Confirm,
+    NextFocus,
+    PrevFocus,
+    Help,
+}
+
+impl Default for KeyMap<StandardAction> {
+    /// Arrows and paging keys, Enter to confirm, Tab/Shift+Tab to move focus, F1 for help
+    fn default() -> Self {
+        let mut keymap = Self::empty();
+        let none = KeyModifiers::NONE;
+        for (code, action) in [
+            (KeyCode::Up, StandardAction::Up),
+            (KeyCode::Down, StandardAction::Down),
+            (KeyCode::Left, StandardAction::Left),
+            (KeyCode::Right, StandardAction::Right),
+            (KeyCode::PageUp, StandardAction::PageUp),
+            (KeyCode::PageDown, StandardAction::PageDown),
+            (KeyCode::Home, StandardAction::Home),
+            (KeyCode::End, StandardAction::End),
+            (KeyCode::Enter, StandardAction::Confirm),
+            (KeyCode::Tab, StandardAction::NextFocus),
+            (KeyCode::BackTab, StandardAction::PrevFocus),
+            (KeyCode::F(1), StandardAction::Help),
+        ] {
+            keymap.bind(code, none, action);
+        }
+        keymap
+    }
+}
+
+/// Whether the terminal can report mouse events, as found by `MouseSupport::probe`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseSupport {
+    Supported,
+    Unsupported,
+}
+
+impl MouseSupport {
+    /// Check that there is a controlling terminal and that `$TERM` isn't a dumb
+    /// one, then (on Unix) enable mouse reporting and ask the terminal whether it
+    /// recognized the mode (DECRQM). Terminals that don't answer within a short
+    /// timeout (up to 200ms) are assumed to support it, as most xterm-compatible
+    /// ones do. Mouse capture is left enabled only when the result is `Supported`.
+    ///
+    /// `out` may be any writer reaching the terminal, so stdout itself may be
+    /// redirected: on Unix the check is that `/dev/tty` can be opened.
+    pub fn probe<W: Write>(out: &mut W) -> MouseSupport {
+        #[cfg(unix)]
+        if std::fs::File::open("/dev/tty").is_err() {
+            return MouseSupport::Unsupported;
+        }
+        #[cfg(not(unix))]
+        {
+            use std::io::IsTerminal;
+            if !std::io::stdin().is_terminal() {
+                return MouseSupport::Unsupported;
+            }
+        }
+        let term = std::env::var("TERM").ok();
+        if Self::from_term(term.as_deref()) == MouseSupport::Unsupported {
+            return MouseSupport::Unsupported;
+        }
+        if crossterm::execute!(out, event::EnableMouseCapture).is_err() {
+            return MouseSupport::Unsupported;
+        }
+        
+        #[cfg(unix)]
+        if query_mode(out, 1000) == Some(false) {
+            let _ = crossterm::execute!(out, event::DisableMouseCapture);
+            return MouseSupport::Unsupported;
+        }
+        MouseSupport::Supported
+    }
+    
+    /// Classify a `$TERM` value: unset, empty and `dumb` terminals have no mouse
+    /// reporting. Anything else is only a candidate, confirmed by `probe`.
+    pub fn from_term(term: Option<&str>) -> MouseSupport {
+        match term.map(str::trim) {
+            #[cfg(unix)]
+            None | Some("") => MouseSupport::Unsupported,
+            Some("dumb") => MouseSupport::Unsupported,
+            _ => MouseSupport::Supported,
+        }
+    }
+}
+
+/// How long `query_mode` waits for the terminal to answer
+#[cfg(unix)]
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Send DECRQM for the private `mode` followed by a device attributes request,
+/// which every VT-compatible terminal answers, and read the replies straight
+/// from the tty. `Some(recognized)` when a mode report came back, `None` on
+/// timeout or when the terminal doesn't implement DECRQM.
+///
+/// Blocks for at most `QUERY_TIMEOUT`. The tty is only read after `poll` says
+/// a reply is waiting, so nothing keeps reading once this returns; keystrokes
+/// read along with the replies are pushed back into the tty input queue.
+#[cfg(unix)]
+pub(crate) fn query_mode<W: Write + ?Sized>(out: &mut W, mode: u16) -> Option<bool> {
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+    
+    let mut tty = std::fs::File::open("/dev/tty").ok()?;
+    write!(out, "\x1b[?{}$p\x1b[c", mode).ok()?;
+    out.flush().ok()?;
+    
+    // The device attributes reply ends the read
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut input = Vec::new();
+    let mut chunk = [0u8; 64];
+    while input.len() < 256 && !scan_query_replies(&input).complete {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut fd = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        // SAFETY: `fd` is a valid pollfd for the open tty and the count is 1
+        let ready = unsafe { libc::poll(&mut fd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 || fd.revents & libc::POLLIN == 0 {
+            break;
+        }
+        match tty.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => input.extend_from_slice(&chunk[..read]),
+        }
+    }
+    
+    let scanned = scan_query_replies(&input);
+    for byte in &scanned.other {
+        // Best effort: kernels that disable TIOCSTI drop the remaining bytes
+        // SAFETY: the tty is open for reading and `byte` points to one byte
+        if unsafe { libc::ioctl(tty.as_raw_fd(), libc::TIOCSTI, byte as *const u8) } < 0 {
+            break;
+        }
+    }
+    parse_mode_report(&scanned.replies, mode)
+}
+
+/// Input read by `query_mode`, split into terminal replies and everything else
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct QueryReplies {
+    /// DECRPM (`ESC [ ? … $ y`) and device attributes (`ESC [ ? … c`) replies
+    pub replies: Vec<u8>,
+    /// Other bytes (keystrokes typed meanwhile, cut-off sequences), in order
+    pub other: Vec<u8>,
+    /// The device attributes reply arrived, so no more replies are coming
+    pub complete: bool,
+}
+
+/// Split `input` into complete `CSI ?` replies and the bytes around them
+pub(crate) fn scan_query_replies(input: &[u8]) -> QueryReplies {
+    let mut scanned = QueryReplies::default();
+    let mut index = 0;
+    while index < input.len() {
+        if input[index..].starts_with(b"\x1b[?") {
+            let params = input[index + 3..]
+                .iter()
+                .take_while(|&&byte| byte.is_ascii_digit() || byte == b';')
+                .count();
+            let end = index + 3 + params;
+            let reply_end = match &input[end..] {
+                [b'$', b'y', ..] => Some(end + 2),
+                [b'c', ..] => {
+                    scanned.complete = true;
+                    Some(end + 1)
+                }
+                _ => None,
+            };
+            if let Some(reply_end) = reply_end {
+                scanned.replies.extend_from_slice(&input[index..reply_end]);
+                index = reply_end;
+                continue;
+            }
+        }
+        scanned.other.push(input[index]);
+        index += 1;
+    }
+    scanned
+}
+
+/// Find the DECRPM reply `ESC [ ? <mode> ; <status> $ y` in `reply`: status 0
+/// means the mode isn't recognized, 1-4 that it is (set, reset or permanent)
+pub(crate) fn parse_mode_report(reply: &[u8], mode: u16) -> Option<bool> {
+    let prefix = format!("\x1b[?{};", mode);
+    let start = reply
+        .windows(prefix.len())
+        .position(|window| window == prefix.as_bytes())?
+        + prefix.len();
+    let rest = &reply[start..];
+    let end = rest.windows(2).position(|window| window == b"$y")?;
+    let status: u8 = std::str::from_utf8(&rest[..end]).ok()?.parse().ok()?;
+    Some(status != 0)
+}
+
+static PANIC_HOOK: Once = Once::new();
+/// Whether keyboard enhancement flags are pushed, so the panic hook can pop them
+pub(crate) static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
 /// Input manager for handling terminal events
 pub struct InputManager {
     mouse_enabled: bool,
+    /// Result of the startup probe: without support mouse capture is never enabled
+    mouse_support: MouseSupport,
     last_terminal_size: (u16, u16),
     mouse_position: (u16, u16),
     mouse_visible: bool,
+    enhanced_keys: bool,
+    /// Event read ahead while merging scroll notches, returned by the next poll
+    pending_event: Option<Event>,
+    /// When `poll_with_tick` is due to return the next `InputEvent::Tick`
+    next_tick: Option<Instant>,
+    /// Keys reported as `InputEvent::Quit` instead of `Key`
+    quit_keys: KeyMap<()>,
     #[allow(dead_code)]
     raw_mode_enabled: bool,
+    out: Box<dyn Write + Send>,
+    /// Background reader started by `event_stream`: stop flag and thread handle
+    #[cfg(feature = "async")]
+    reader: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
 }
 
 impl InputManager {
-    pub fn new() -> io::Result<Self> {
+    /// Enter raw mode and the alternate screen on stdout. Like `with_output`,
+    /// this may block for up to 200ms while the mouse probe waits for the
+    /// terminal to answer.
+    pub fn new() -> Result<Self> {
+        Self::with_output(Box::new(stdout()))
+    }
+
+    /// Create the manager writing its escape sequences to `out` (e.g. an opened
+    /// `/dev/tty`) instead of stdout. The panic hook still restores through stdout.
+    /// Mouse capture is enabled only if `MouseSupport::probe` finds it supported;
+    /// the probe can block startup for up to 200ms on a terminal that doesn't answer.
+    pub fn with_output(mut out: Box<dyn Write + Send>) -> Result<Self> {
         // Inizializzazione silenziosa senza log
         terminal::enable_raw_mode()?;
         crossterm::execute!(
-            stdout(),
+            out,
             terminal::EnterAlternateScreen,
-            cursor::Hide,
-            event::EnableMouseCapture
+            cursor::Hide
         )?;
+        let mouse_support = MouseSupport::probe(&mut out);
         
         let terminal_size = terminal::size().unwrap_or((80, 24));
         
         Ok(Self {
             mouse_enabled: true,
+            mouse_support,
             last_terminal_size: terminal_size,
             mouse_position: (0, 0),
             mouse_visible: true,
+            enhanced_keys: false,
+            pending_event: None,
+            next_tick: None,
+            quit_keys: KeyMap::default_quit_keys(),
             raw_mode_enabled: true,
+            out,
+            #[cfg(feature = "async")]
+            reader: None,
         })
     }
 
+    /// Keys turned into `InputEvent::Quit` (by default `KeyMap::default_quit_keys`).
+    /// Unbind the ones widgets need, e.g. Esc to close a `Menu` or q to type in a
+    /// `TextArea`, or pass `KeyMap::empty()` to get every key as `Key` and decide
+    /// when to quit in the application. Ctrl bindings also match with Shift or Alt
+    /// held, so Ctrl+Shift+C quits like Ctrl+C.
+    pub fn set_quit_keys(&mut self, keys: KeyMap<()>) {
+        self.quit_keys = keys;
+    }
+
+    pub fn quit_keys(&self) -> &KeyMap<()> {
+        &self.quit_keys
+    }
+
+    /// Install a panic hook that restores the terminal (raw mode off, main screen,
+    /// visible cursor, mouse capture and keyboard enhancement off) before the
+    /// previous hook prints the message.
+    /// Opt-in and idempotent: calling it more than once installs the hook only once.
+    pub fn install_panic_restore() {
+        PANIC_HOOK.call_once(|| {
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                restore_terminal(stdout());
+                previous(info);
+            }));
+        });
+    }
+
+    /// Enable the Kitty keyboard protocol so key repeat/release events are reported.
+    /// Returns `Ok(false)` and changes nothing if the terminal doesn't support it.
+    pub fn enable_enhanced_keys(&mut self) -> Result<bool> {
+        if self.enhanced_keys {
+            return Ok(true);
+        }
+        if !terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            return Ok(false);
+        }
+        crossterm::execute!(
+            self.out,
+            event::PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            )
+        )?;
+        self.enhanced_keys = true;
+        KEYBOARD_ENHANCED.store(true, Ordering::SeqCst);
+        Ok(true)
+    }
+
+    pub fn disable_enhanced_keys(&mut self) -> Result<()> {
+        if self.enhanced_keys {
+            crossterm::execute!(self.out, event::PopKeyboardEnhancementFlags)?;
+            self.enhanced_keys = false;
+            KEYBOARD_ENHANCED.store(false, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    pub fn is_enhanced_keys_enabled(&self) -> bool {
+        self.enhanced_keys
+    }
+
     pub fn is_mouse_enabled(&self) -> bool {
         self.mouse_enabled
     }
 
-    pub fn set_mouse_enabled(&mut self, enabled: bool) -> io::Result<()> {
+    /// Result of the mouse probe done when the manager was created
+    pub fn mouse_support(&self) -> MouseSupport {
+        self.mouse_support
+    }
+
+    /// Mouse requested and supported by the terminal: only then are mouse and
+    /// scroll events reported
+    pub fn mouse_actually_enabled(&self) -> bool {
+        self.mouse_enabled && self.mouse_support == MouseSupport::Supported
+    }
+
+    /// Request mouse capture on or off; without terminal support this only records
+    /// the preference and no escape sequence is sent
+    pub fn set_mouse_enabled(&mut self, enabled: bool) -> Result<()> {
         self.mouse_enabled = enabled;
+        if self.mouse_support == MouseSupport::Unsupported {
+            return Ok(());
+        }
         if enabled {
-            crossterm::execute!(stdout(), event::EnableMouseCapture)?;
+            crossterm::execute!(self.out, event::EnableMouseCapture)?;
         } else {
-            crossterm::execute!(stdout(), event::DisableMouseCapture)?;
+            crossterm::execute!(self.out, event::DisableMouseCapture)?;
         }
         Ok(())
     }
@@ -76,7 +477,9 @@ impl InputManager {
         self.mouse_visible
     }
 
-    pub fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>> {
+    /// Next event arriving within `timeout`. `Ok(None)` means the timeout expired:
+    /// events that are dropped (mouse while disabled, key releases) keep waiting.
+    pub fn poll_event(&mut self, timeout: Duration) -> Result<Option<InputEvent>> {
         // Controlla sempre il ridimensionamento prima degli eventi
         let current_size = terminal::size()?;
         if current_size != self.last_terminal_size {
@@ -87,75 +490,140 @@ impl InputManager {
             }));
         }
 
-        if event::poll(timeout)? {
-            match event::read()? {
-                Event::Key(KeyEvent { code, modifiers, .. }) => {
-                    // Gestione Ctrl+C e Ctrl+D per uscita pulita
-                    if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
-                        match code {
-                            KeyCode::Char('c') | KeyCode::Char('d') => {
-                                return Ok(Some(InputEvent::Quit));
-                            },
-                            _ => {}
-                        }
-                    }
-                    
-                    match code {
-                        KeyCode::Char('q') | KeyCode::Esc => Ok(Some(InputEvent::Quit)),
-                        _ => Ok(Some(InputEvent::Key(code))),
+        // Eventi scartati (mouse disattivato, rilasci) non contano come timeout
+        let deadline = Instant::now() + timeout;
+        let mut mapped = loop {
+            let raw = match self.pending_event.take() {
+                Some(raw) => raw,
+                None if event::poll(deadline.saturating_duration_since(Instant::now()))? => event::read()?,
+                None => return Ok(None),
+            };
+            let mapped = translate_event(raw, self.enhanced_keys, &self.quit_keys, &mut self.last_terminal_size, &mut self.mouse_position);
+            if mapped.is_some() && (self.mouse_actually_enabled() || !is_mouse_event(&mapped)) {
+                break mapped;
+            }
+        };
+        
+        // Unisce le tacche della rotella già in coda nella stessa direzione
+        while let Some(InputEvent::Scroll { delta, .. }) = &mut mapped {
+            if !event::poll(Duration::ZERO)? {
+                break;
+            }
+            let next = event::read()?;
+            match scroll_delta(&next) {
+                Some(step) if step.signum() == delta.signum() => *delta += step,
+                _ => {
+                    self.pending_event = Some(next);
+                    break;
+                }
+            }
+        }
+        Ok(mapped)
+    }
+
+    /// Wait for the next event like `poll_event`, but return `InputEvent::Tick`
+    /// every `tick_interval` when nothing else arrived, so a single call can drive
+    /// both input and animation. Ticks keep a steady cadence from the first call;
+    /// while events are queued they're returned first and the tick is delayed
+    /// (a late tick doesn't cause a burst of catch-up ticks). `timeout` caps each
+    /// wait on the terminal, i.e. how often the terminal size is re-checked.
+    pub fn poll_with_tick(&mut self, timeout: Duration, tick_interval: Duration) -> Result<InputEvent> {
+        let deadline = *self.next_tick.get_or_insert_with(|| Instant::now() + tick_interval);
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now()).min(timeout);
+            if let Some(event) = self.poll_event(wait)? {
+                return Ok(event);
+            }
+            
+            let now = Instant::now();
+            if now >= deadline {
+                let next = deadline + tick_interval;
+                self.next_tick = Some(if next > now { next } else { now + tick_interval });
+                return Ok(InputEvent::Tick);
+            }
+        }
+    }
+
+    /// Spawn a reader thread that translates terminal events and sends them on the
+    /// returned channel, so input can be awaited with `tokio::select!` next to other
+    /// futures. Don't mix with `poll_event` while the stream is active: both read the
+    /// same terminal. The thread stops when the receiver is dropped, when this is
+    /// called again, or when the manager is dropped. `get_mouse_position` is not
+    /// updated by the thread.
+    #[cfg(feature = "async")]
+    pub fn event_stream(&mut self) -> tokio::sync::mpsc::UnboundedReceiver<InputEvent> {
+        self.stop_event_stream();
+        
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let enhanced_keys = self.enhanced_keys;
+        let quit_keys = self.quit_keys.clone();
+        let mouse = self.mouse_actually_enabled();
+        let mut terminal_size = self.last_terminal_size;
+        let mut mouse_position = self.mouse_position;
+        
+        let handle = std::thread::spawn(move || {
+            // Short poll timeout so the stop flag is noticed promptly
+            while !thread_stop.load(Ordering::Relaxed) && !sender.is_closed() {
+                match event::poll(Duration::from_millis(50)) {
+                    Ok(true) => {},
+                    Ok(false) => continue,
+                    Err(_) => break,
+                }
+                let Ok(raw) = event::read() else { break };
+                let mapped = translate_event(raw, enhanced_keys, &quit_keys, &mut terminal_size, &mut mouse_position);
+                if !mouse && is_mouse_event(&mapped) {
+                    continue;
+                }
+                if let Some(event) = mapped {
+                    if sender.send(event).is_err() {
+                        break;
                     }
-                },
-                Event::Mouse(MouseEvent { column, row, kind, .. }) => {
-                    // Aggiorna posizione mouse con bounds checking
-                    self.mouse_position = (
-                        column.min(self.last_terminal_size.0.saturating_sub(1)),
-                        row.min(self.last_terminal_size.1.saturating_sub(1))
-                    );
-                    
-                    Ok(Some(InputEvent::Mouse { 
-                        x: self.mouse_position.0, 
-                        y: self.mouse_position.1, 
-                        kind 
-                    }))
-                },
-                Event::Resize(width, height) => {
-                    self.last_terminal_size = (width, height);
-                    Ok(Some(InputEvent::Resize { width, height }))
-                },
-                _ => Ok(None),
+                }
             }
-        } else {
-            Ok(None)
+        });
+        
+        self.reader = Some((stop, handle));
+        receiver
+    }
+
+    /// Stop the reader thread started by `event_stream`, waiting for it to exit
+    #[cfg(feature = "async")]
+    pub fn stop_event_stream(&mut self) {
+        if let Some((stop, handle)) = self.reader.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
         }
     }
 
-    pub fn clear_screen(&self) -> io::Result<()> {
+    pub fn clear_screen(&mut self) -> Result<()> {
         // Pulizia più robusta del terminale
-        stdout().execute(terminal::Clear(terminal::ClearType::All))?;
-        stdout().execute(terminal::Clear(terminal::ClearType::Purge))?; // Purge scrollback
-        stdout().execute(cursor::MoveTo(0, 0))?;
+        self.out.execute(terminal::Clear(terminal::ClearType::All))?;
+        self.out.execute(terminal::Clear(terminal::ClearType::Purge))?; // Purge scrollback
+        self.out.execute(cursor::MoveTo(0, 0))?;
         Ok(())
     }
 
-    pub fn refresh_screen(&self) -> io::Result<()> {
+    pub fn refresh_screen(&mut self) -> Result<()> {
         // Force refresh del terminale
-        stdout().execute(terminal::Clear(ClearType::All))?;
-        stdout().execute(cursor::MoveTo(0, 0))?;
+        self.out.execute(terminal::Clear(ClearType::All))?;
+        self.out.execute(cursor::MoveTo(0, 0))?;
         Ok(())
     }
 
-    pub fn set_cursor_position(&self, x: u16, y: u16) -> io::Result<()> {
-        stdout().execute(cursor::MoveTo(x, y))?;
+    pub fn set_cursor_position(&mut self, x: u16, y: u16) -> Result<()> {
+        self.out.execute(cursor::MoveTo(x, y))?;
         Ok(())
     }
 
-    pub fn show_cursor(&self) -> io::Result<()> {
-        stdout().execute(cursor::Show)?;
+    pub fn show_cursor(&mut self) -> Result<()> {
+        self.out.execute(cursor::Show)?;
         Ok(())
     }
 
-    pub fn hide_cursor(&self) -> io::Result<()> {
-        stdout().execute(cursor::Hide)?;
+    pub fn hide_cursor(&mut self) -> Result<()> {
+        self.out.execute(cursor::Hide)?;
         Ok(())
     }
 
@@ -163,12 +631,12 @@ impl InputManager {
         self.last_terminal_size
     }
 
-    pub fn force_refresh(&mut self) -> io::Result<()> {
+    pub fn force_refresh(&mut self) -> Result<()> {
         // Reset completo del terminale
-        stdout().execute(terminal::Clear(terminal::ClearType::All))?;
-        stdout().execute(terminal::Clear(terminal::ClearType::Purge))?;
-        stdout().execute(cursor::MoveTo(0, 0))?;
-        stdout().execute(crossterm::style::ResetColor)?;
+        self.out.execute(terminal::Clear(terminal::ClearType::All))?;
+        self.out.execute(terminal::Clear(terminal::ClearType::Purge))?;
+        self.out.execute(cursor::MoveTo(0, 0))?;
+        self.out.execute(crossterm::style::ResetColor)?;
         
         let size = terminal::size()?;
         if size != self.last_terminal_size {
@@ -178,15 +646,126 @@ impl InputManager {
     }
 }
 
+/// Mouse and wheel events, dropped while the mouse isn't actually enabled
+fn is_mouse_event(event: &Option<InputEvent>) -> bool {
+    matches!(event, Some(InputEvent::Mouse { .. } | InputEvent::Scroll { .. }))
+}
+
+/// Exact binding, or a Ctrl binding matched while other modifiers are held too
+/// (Ctrl+Shift+C may arrive as an uppercase 'C')
+fn is_quit_key(quit_keys: &KeyMap<()>, code: KeyCode, modifiers: KeyModifiers) -> bool {
+    if quit_keys.get(code, modifiers).is_some() {
+        return true;
+    }
+    if !modifiers.contains(KeyModifiers::CONTROL) {
+        return false;
+    }
+    let code = match code {
+        KeyCode::Char(ch) => KeyCode::Char(ch.to_ascii_lowercase()),
+        other => other,
+    };
+    quit_keys.get(code, KeyModifiers::CONTROL).is_some()
+}
+
+/// Map a crossterm event to an `InputEvent`, tracking the terminal size and the
+/// clamped mouse position. Shared by `poll_event` and the `event_stream` thread.
+pub(crate) fn translate_event(
+    event: Event,
+    enhanced_keys: bool,
+    quit_keys: &KeyMap<()>,
+    terminal_size: &mut (u16, u16),
+    mouse_position: &mut (u16, u16),
+) -> Option<InputEvent> {
+    match event {
+        Event::Key(KeyEvent { code, modifiers, kind, .. }) => {
+            if kind != KeyEventKind::Press {
+                // Senza protocollo avanzato i rilasci (es. Windows) vengono scartati
+                return match (enhanced_keys, kind) {
+                    (true, _) => Some(InputEvent::KeyKind { code, kind }),
+                    (false, KeyEventKind::Repeat) => Some(InputEvent::Key(code)),
+                    (false, _) => None,
+                };
+            }
+            
+            let navigation = matches!(
+                code,
+                KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
+                    | KeyCode::Home | KeyCode::End | KeyCode::PageUp | KeyCode::PageDown
+            );
+            
+            // Tasti di uscita configurabili (q, Esc, Ctrl+C, Ctrl+D di default)
+            if is_quit_key(quit_keys, code, modifiers) {
+                return Some(InputEvent::Quit);
+            }
+            
+            if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
+                match code {
+                    KeyCode::Backspace | KeyCode::Delete => return Some(InputEvent::Ctrl(code)),
+                    _ if navigation => return Some(InputEvent::Ctrl(code)),
+                    _ => {}
+                }
+            }
+            
+            if navigation && modifiers.contains(crossterm::event::KeyModifiers::SHIFT) {
+                Some(InputEvent::Shifted(code))
+            } else {
+                Some(InputEvent::Key(code))
+            }
+        },
+        Event::Mouse(MouseEvent { column, row, kind, .. }) => {
+            // Aggiorna posizione mouse con bounds checking
+            *mouse_position = (
+                column.min(terminal_size.0.saturating_sub(1)),
+                row.min(terminal_size.1.saturating_sub(1))
+            );
+            
+            match kind {
+                MouseEventKind::ScrollUp => Some(InputEvent::Scroll { x: mouse_position.0, y: mouse_position.1, delta: 1 }),
+                MouseEventKind::ScrollDown => Some(InputEvent::Scroll { x: mouse_position.0, y: mouse_position.1, delta: -1 }),
+                _ => Some(InputEvent::Mouse { 
+                    x: mouse_position.0, 
+                    y: mouse_position.1, 
+                    kind 
+                }),
+            }
+        },
+        Event::Resize(width, height) => {
+            *terminal_size = (width, height);
+            Some(InputEvent::Resize { width, height })
+        },
+        other => Some(InputEvent::Other(other)),
+    }
+}
+
+/// Wheel step carried by a raw event, if it is a vertical scroll
+fn scroll_delta(event: &Event) -> Option<i32> {
+    match event {
+        Event::Mouse(MouseEvent { kind: MouseEventKind::ScrollUp, .. }) => Some(1),
+        Event::Mouse(MouseEvent { kind: MouseEventKind::ScrollDown, .. }) => Some(-1),
+        _ => None,
+    }
+}
+
+/// Best-effort terminal restore shared by `Drop` and the panic hook
+pub(crate) fn restore_terminal<W: Write>(mut out: W) {
+    if KEYBOARD_ENHANCED.swap(false, Ordering::SeqCst) {
+        let _ = crossterm::execute!(out, event::PopKeyboardEnhancementFlags);
+    }
+    let _ = crossterm::execute!(
+        out,
+        cursor::Show,
+        event::DisableMouseCapture,
+        terminal::LeaveAlternateScreen
+    );
+    let _ = terminal::disable_raw_mode();
+}
+
 impl Drop for InputManager {
     fn drop(&mut self) {
         // Cleanup silenzioso
-        let _ = crossterm::execute!(
-            stdout(),
-            cursor::Show,
-            event::DisableMouseCapture,
-            terminal::LeaveAlternateScreen
-        );
-        let _ = terminal::disable_raw_mode();
+        #[cfg(feature = "async")]
+        self.stop_event_stream();
+        let _ = self.disable_enhanced_keys();
+        restore_terminal(&mut self.out);
     }
 }
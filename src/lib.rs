@@ -6,18 +6,78 @@
 //! - Sistema di gestione schede e compositing
 //! - Input handling e elementi UI interattivi
 //! - Sistema di animazioni
+//! - Sprite e tile map per giochi
 //! - Rendering intelligente ottimizzato
+//!
+//! Con la feature `std` disattivata (`default-features = false`) restano i tipi di
+//! base (framebuffer, colori, compositing, sprite e conversioni Braille da buffer
+//! luma) che richiedono solo `alloc`, ad esempio per pilotare un display Braille
+//! da un dispositivo embedded.
 
-use image::{DynamicImage, GrayImage};
-use std::time::{Duration, Instant};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::{self, Write as _};
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use image::{DynamicImage, GrayImage, Rgb, RgbImage};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::time::Instant;
+#[cfg(feature = "std")]
 use parking_lot::Mutex;
+#[cfg(feature = "std")]
 use once_cell::sync::Lazy;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+#[cfg(not(feature = "std"))]
+use float::FloatExt as _;
 
+#[cfg(feature = "std")]
 pub mod input;
+#[cfg(feature = "std")]
 pub mod ui;
+#[cfg(feature = "std")]
 pub mod animation;
 pub mod compositor;
+#[cfg(feature = "std")]
 pub mod renderer;
+pub mod sprite;
+#[cfg(feature = "std")]
+pub mod recorder;
+#[cfg(feature = "std")]
+pub mod window_manager;
+#[cfg(feature = "std")]
+pub mod app;
+
+/// Funzioni float che in `core` non esistono, fornite da `libm` senza `std`.
+/// Con `std` valgono i metodi inerenti di `f32`/`f64`.
+#[cfg(not(feature = "std"))]
+mod float {
+    pub(crate) trait FloatExt {
+        fn round(self) -> Self;
+        fn powf(self, n: Self) -> Self;
+    }
+
+    impl FloatExt for f32 {
+        fn round(self) -> f32 { libm::roundf(self) }
+        fn powf(self, n: f32) -> f32 { libm::powf(self, n) }
+    }
+
+    impl FloatExt for f64 {
+        fn round(self) -> f64 { libm::round(self) }
+        fn powf(self, n: f64) -> f64 { libm::pow(self, n) }
+    }
+}
 
 /// FrameBuffer: matrice di caratteri Unicode (es. Braille)
 #[derive(Debug, Clone)]
@@ -67,26 +127,14 @@ impl FrameBuffer {
         self.data.fill(' ');
     }
 
-    /// Ritorna una stringa rappresentante il framebuffer
-    pub fn to_string(&self) -> String {
-        let mut result = String::with_capacity(self.width * self.height + self.height);
-        for y in 0..self.height {
-            for x in 0..self.width {
-                result.push(self.get(x, y));
-            }
-            if y < self.height - 1 {
-                result.push('\n');
-            }
-        }
-        result
-    }
-
+    #[cfg(feature = "std")]
     /// Stampa il framebuffer su terminale
     pub fn print(&self) {
-        print!("{}", self.to_string());
+        print!("{}", self);
     }
 
     /// Copia una porzione di altro framebuffer in questo
+    #[allow(clippy::too_many_arguments)]
     pub fn blit(&mut self, src: &FrameBuffer, src_x: usize, src_y: usize, 
                 dst_x: usize, dst_y: usize, width: usize, height: usize) {
         for y in 0..height {
@@ -99,6 +147,48 @@ impl FrameBuffer {
         }
     }
 
+    /// Sovrappone `src` in (dst_x, dst_y) combinando i punti: dove entrambe le celle
+    /// sono Braille i pattern vengono uniti in OR, altrimenti la cella viene sostituita
+    pub fn or_braille(&mut self, src: &FrameBuffer, dst_x: usize, dst_y: usize) {
+        let is_braille = |ch: char| (0x2800..=0x28FF).contains(&(ch as u32));
+        
+        for y in 0..src.height.min(self.height.saturating_sub(dst_y)) {
+            for x in 0..src.width.min(self.width.saturating_sub(dst_x)) {
+                let top = src.get(x, y);
+                let bottom = self.get(dst_x + x, dst_y + y);
+                let ch = if is_braille(top) && is_braille(bottom) {
+                    braille_char((top as u32 | bottom as u32) as u8)
+                } else {
+                    top
+                };
+                self.set(dst_x + x, dst_y + y, ch);
+            }
+        }
+    }
+
+    /// Celle di `other` diverse da questo buffer, come (x, y, nuovo carattere), in
+    /// ordine di riga. Copre l'area di `other`: fuori da questo buffer si confronta
+    /// con uno spazio. `apply_diff` su questo buffer lo rende uguale a `other`.
+    pub fn diff(&self, other: &FrameBuffer) -> Vec<(usize, usize, char)> {
+        let mut changes = Vec::new();
+        for y in 0..other.height {
+            for x in 0..other.width {
+                let ch = other.get(x, y);
+                if self.get(x, y) != ch {
+                    changes.push((x, y, ch));
+                }
+            }
+        }
+        changes
+    }
+
+    /// Applica un diff prodotto da `diff`; le celle fuori dal buffer vengono ignorate
+    pub fn apply_diff(&mut self, diff: &[(usize, usize, char)]) {
+        for &(x, y, ch) in diff {
+            self.set(x, y, ch);
+        }
+    }
+
     /// Converte in StyledFrameBuffer
     pub fn to_styled(&self) -> StyledFrameBuffer {
         let mut styled = StyledFrameBuffer::new(self.width, self.height);
@@ -110,13 +200,15 @@ impl FrameBuffer {
         styled
     }
 
+    #[cfg(feature = "std")]
     /// Crea un nuovo framebuffer utilizzando il pool di memoria
     pub fn new_pooled(width: usize, height: usize) -> Self {
         let size = width * height;
         
         let data = {
             let mut pool = BUFFER_POOL.lock();
-            if let Some(mut reused_buffer) = pool.pop() {
+            let reused = if pool_max_buffers() > 0 { pool.pop() } else { None };
+            if let Some(mut reused_buffer) = reused {
                 reused_buffer.clear();
                 reused_buffer.resize(size, ' ');
                 reused_buffer
@@ -128,18 +220,21 @@ impl FrameBuffer {
         Self { width, height, data }
     }
 
+    #[cfg(feature = "std")]
     /// Rilascia il buffer al pool per il riutilizzo
     pub fn release_to_pool(mut self) {
-        if self.data.capacity() >= self.data.len() && self.data.capacity() <= 1024 * 1024 {
+        if self.data.capacity() <= pool_max_capacity() {
             let mut pool = BUFFER_POOL.lock();
-            if pool.len() < 16 {
+            if pool.len() < pool_max_buffers() {
                 self.data.clear();
                 pool.push(self.data);
             }
         }
     }
 
+    #[cfg(feature = "std")]
     /// Blit parallelo per buffer grandi
+    #[allow(clippy::too_many_arguments)]
     pub fn blit_parallel(&mut self, src: &FrameBuffer, src_x: usize, src_y: usize, 
                         dst_x: usize, dst_y: usize, width: usize, height: usize) {
         if width * height < 1000 {
@@ -168,6 +263,68 @@ impl FrameBuffer {
     }
 }
 
+/// Rappresentazione testuale del framebuffer, una riga per linea
+impl fmt::Display for FrameBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                f.write_char(self.get(x, y))?;
+            }
+            if y < self.height - 1 {
+                f.write_char('\n')?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Coordinata di cella. Le operazioni con spostamenti con segno sono verificate
+/// (`offset`) o saturanti (`saturating_offset`), così non servono conversioni
+/// manuali a `isize` con clamp sparsi nel codice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Point {
+    pub fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    /// Punto spostato di (dx, dy), `None` se uscirebbe dal range di `usize`
+    pub fn offset(&self, dx: isize, dy: isize) -> Option<Point> {
+        Some(Point::new(self.x.checked_add_signed(dx)?, self.y.checked_add_signed(dy)?))
+    }
+
+    /// Punto spostato di (dx, dy), fermo a 0 (o a `usize::MAX`) invece di andare in overflow
+    pub fn saturating_offset(&self, dx: isize, dy: isize) -> Point {
+        Point::new(self.x.saturating_add_signed(dx), self.y.saturating_add_signed(dy))
+    }
+
+    /// Spostamento con segno da questo punto a `other`
+    pub fn delta_to(&self, other: Point) -> (isize, isize) {
+        (
+            other.x as isize - self.x as isize,
+            other.y as isize - self.y as isize,
+        )
+    }
+
+    /// Punto più vicino dentro `rect` (l'origine se il rect è vuoto)
+    pub fn clamp_to(&self, rect: &Rect) -> Point {
+        Point::new(
+            self.x.clamp(rect.x, rect.x + rect.width.saturating_sub(1)),
+            self.y.clamp(rect.y, rect.y + rect.height.saturating_sub(1)),
+        )
+    }
+}
+
+impl From<(usize, usize)> for Point {
+    fn from((x, y): (usize, usize)) -> Self {
+        Point::new(x, y)
+    }
+}
+
 /// Rappresenta un'area rettangolare
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
@@ -187,16 +344,51 @@ impl Rect {
         y >= self.y && y < self.y + self.height
     }
 
+    /// Angolo in alto a sinistra
+    pub fn origin(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        self.contains(point.x, point.y)
+    }
+
+    /// Rect spostato di (dx, dy) e tenuto dentro `bounds` (es. trascinamento di una
+    /// finestra); se è più grande di `bounds` resta allineato al suo angolo in alto a sinistra
+    pub fn moved_within(&self, dx: isize, dy: isize, bounds: Rect) -> Rect {
+        let origin = self.origin().saturating_offset(dx, dy);
+        let max_x = bounds.x + bounds.width.saturating_sub(self.width);
+        let max_y = bounds.y + bounds.height.saturating_sub(self.height);
+        Rect::new(
+            origin.x.clamp(bounds.x, max_x),
+            origin.y.clamp(bounds.y, max_y),
+            self.width,
+            self.height,
+        )
+    }
+
     pub fn intersects(&self, other: &Rect) -> bool {
         self.x < other.x + other.width &&
         self.x + self.width > other.x &&
         self.y < other.y + other.height &&
         self.y + self.height > other.y
     }
+
+    /// Area comune ai due rettangoli, `None` se non si sovrappongono
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Some(Rect::new(x, y, right - x, bottom - y))
+    }
 }
 
 /// Colore per elementi UI
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Color {
     Black,
     Red,
@@ -207,47 +399,338 @@ pub enum Color {
     Cyan,
     White,
     Gray,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
     Reset,
+    /// Colore truecolor a 24 bit
+    Rgb(u8, u8, u8),
+    /// Nessun colore: come sfondo lascia vedere quello sottostante (layer inferiore
+    /// o contenuto già presente), senza sequenze ANSI in uscita
+    Transparent,
+}
+
+/// Colore da emettere sul terminale: `Transparent` equivale a nessun colore
+fn opaque(color: Option<Color>) -> Option<Color> {
+    color.filter(|&color| color != Color::Transparent)
 }
 
 impl Color {
-    pub fn to_ansi_fg(&self) -> &'static str {
+    pub fn to_ansi_fg(&self) -> Cow<'static, str> {
         match self {
-            Color::Black => "\x1b[30m",
-            Color::Red => "\x1b[31m",
-            Color::Green => "\x1b[32m",
-            Color::Yellow => "\x1b[33m",
-            Color::Blue => "\x1b[34m",
-            Color::Magenta => "\x1b[35m",
-            Color::Cyan => "\x1b[36m",
-            Color::White => "\x1b[37m",
-            Color::Gray => "\x1b[90m",
-            Color::Reset => "\x1b[0m",
+            Color::Black => "\x1b[30m".into(),
+            Color::Red => "\x1b[31m".into(),
+            Color::Green => "\x1b[32m".into(),
+            Color::Yellow => "\x1b[33m".into(),
+            Color::Blue => "\x1b[34m".into(),
+            Color::Magenta => "\x1b[35m".into(),
+            Color::Cyan => "\x1b[36m".into(),
+            Color::White => "\x1b[37m".into(),
+            Color::Gray => "\x1b[90m".into(),
+            Color::BrightRed => "\x1b[91m".into(),
+            Color::BrightGreen => "\x1b[92m".into(),
+            Color::BrightYellow => "\x1b[93m".into(),
+            Color::BrightBlue => "\x1b[94m".into(),
+            Color::BrightMagenta => "\x1b[95m".into(),
+            Color::BrightCyan => "\x1b[96m".into(),
+            Color::BrightWhite => "\x1b[97m".into(),
+            Color::Reset => "\x1b[0m".into(),
+            Color::Rgb(r, g, b) => format!("\x1b[38;2;{};{};{}m", r, g, b).into(),
+            Color::Transparent => "".into(),
         }
     }
 
-    pub fn to_ansi_bg(&self) -> &'static str {
+    pub fn to_ansi_bg(&self) -> Cow<'static, str> {
         match self {
-            Color::Black => "\x1b[40m",
-            Color::Red => "\x1b[41m",
-            Color::Green => "\x1b[42m",
-            Color::Yellow => "\x1b[43m",
-            Color::Blue => "\x1b[44m",
-            Color::Magenta => "\x1b[45m",
-            Color::Cyan => "\x1b[46m",
-            Color::White => "\x1b[47m",
-            Color::Gray => "\x1b[100m",
-            Color::Reset => "\x1b[0m",
+            Color::Black => "\x1b[40m".into(),
+            Color::Red => "\x1b[41m".into(),
+            Color::Green => "\x1b[42m".into(),
+            Color::Yellow => "\x1b[43m".into(),
+            Color::Blue => "\x1b[44m".into(),
+            Color::Magenta => "\x1b[45m".into(),
+            Color::Cyan => "\x1b[46m".into(),
+            Color::White => "\x1b[47m".into(),
+            Color::Gray => "\x1b[100m".into(),
+            Color::BrightRed => "\x1b[101m".into(),
+            Color::BrightGreen => "\x1b[102m".into(),
+            Color::BrightYellow => "\x1b[103m".into(),
+            Color::BrightBlue => "\x1b[104m".into(),
+            Color::BrightMagenta => "\x1b[105m".into(),
+            Color::BrightCyan => "\x1b[106m".into(),
+            Color::BrightWhite => "\x1b[107m".into(),
+            Color::Reset => "\x1b[0m".into(),
+            Color::Rgb(r, g, b) => format!("\x1b[48;2;{};{};{}m", r, g, b).into(),
+            Color::Transparent => "".into(),
+        }
+    }
+
+    /// Componenti RGB del colore (valori di `ANSI_PALETTE` per i colori con nome)
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            named => ANSI_PALETTE
+                .iter()
+                .find(|(color, _)| *color == named)
+                .map(|&(_, rgb)| rgb)
+                .unwrap_or((0, 0, 0)),
+        }
+    }
+
+    /// Colore in formato esadecimale CSS (`#rrggbb`)
+    pub fn to_hex(&self) -> String {
+        let (r, g, b) = self.to_rgb();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
+    /// Mappa un colore RGB arbitrario sul colore con nome più vicino della palette a 16 colori
+    pub fn from_rgb_nearest(r: u8, g: u8, b: u8) -> Color {
+        Self::from_rgb_nearest_in(r, g, b, &ANSI_PALETTE)
+    }
+
+    /// Come `from_rgb_nearest`, ma su una palette personalizzata
+    /// (distanza euclidea in RGB, a parità vince la prima voce)
+    pub fn from_rgb_nearest_in(r: u8, g: u8, b: u8, palette: &[(Color, (u8, u8, u8))]) -> Color {
+        let distance = |(pr, pg, pb): (u8, u8, u8)| {
+            let dr = r as i32 - pr as i32;
+            let dg = g as i32 - pg as i32;
+            let db = b as i32 - pb as i32;
+            dr * dr + dg * dg + db * db
+        };
+        
+        palette
+            .iter()
+            .min_by_key(|&&(_, rgb)| distance(rgb))
+            .map(|&(color, _)| color)
+            .unwrap_or(Color::Reset)
+    }
+
+    /// Interpola linearmente in RGB tra due colori (`t` limitato a 0..=1)
+    pub fn lerp(a: Color, b: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let (ar, ag, ab) = a.to_rgb();
+        let (br, bg, bb) = b.to_rgb();
+        let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+        Color::Rgb(mix(ar, br), mix(ag, bg), mix(ab, bb))
+    }
+
+    /// Come `lerp` ma interpolando in luce lineare: converte da sRGB, interpola e
+    /// riconverte, così i punti intermedi non risultano troppo scuri (il punto medio
+    /// tra nero e bianco è ~188 invece di 128). Da usare per sfumature e dissolvenze.
+    pub fn lerp_srgb(a: Color, b: Color, t: f32) -> Color {
+        fn to_linear(c: u8) -> f32 {
+            let c = c as f32 / 255.0;
+            if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        }
+        fn from_linear(l: f32) -> u8 {
+            let c = if l <= 0.003_130_8 { l * 12.92 } else { 1.055 * l.powf(1.0 / 2.4) - 0.055 };
+            (c.clamp(0.0, 1.0) * 255.0).round() as u8
         }
+        
+        let t = t.clamp(0.0, 1.0);
+        let (ar, ag, ab) = a.to_rgb();
+        let (br, bg, bb) = b.to_rgb();
+        let mix = |from: u8, to: u8| {
+            let from = to_linear(from);
+            from_linear(from + (to_linear(to) - from) * t)
+        };
+        Color::Rgb(mix(ar, br), mix(ag, bg), mix(ab, bb))
     }
 }
 
-/// Carattere con attributi di colore
+/// Palette standard a 16 colori con i valori RGB di xterm
+pub const ANSI_PALETTE: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::White, (229, 229, 229)),
+    (Color::Gray, (127, 127, 127)),
+    (Color::BrightRed, (255, 0, 0)),
+    (Color::BrightGreen, (0, 255, 0)),
+    (Color::BrightYellow, (255, 255, 0)),
+    (Color::BrightBlue, (92, 92, 255)),
+    (Color::BrightMagenta, (255, 0, 255)),
+    (Color::BrightCyan, (0, 255, 255)),
+    (Color::BrightWhite, (255, 255, 255)),
+];
+
+/// Direzione di un riempimento a gradiente
 #[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientDirection {
+    /// Da sinistra verso destra
+    Horizontal,
+    /// Dall'alto verso il basso
+    Vertical,
+    /// Dall'angolo in alto a sinistra a quello in basso a destra
+    Diagonal,
+}
+
+/// Motivo di celle usato da `StyledFrameBuffer::apply_stipple`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StipplePattern {
+    /// Scacchiera: una cella sì e una no, sfalsata a ogni riga
+    Checker,
+    /// Una riga sì e una no
+    HorizontalLines,
+    /// Una colonna sì e una no
+    VerticalLines,
+    /// Una cella ogni due, su righe alterne (trama rada)
+    Dots,
+}
+
+impl StipplePattern {
+    /// Indica se la cella (x, y), relativa all'origine del rect, fa parte del motivo
+    pub fn covers(&self, x: usize, y: usize) -> bool {
+        match self {
+            StipplePattern::Checker => (x + y).is_multiple_of(2),
+            StipplePattern::HorizontalLines => y.is_multiple_of(2),
+            StipplePattern::VerticalLines => x.is_multiple_of(2),
+            StipplePattern::Dots => x.is_multiple_of(2) && y.is_multiple_of(2),
+        }
+    }
+}
+
+/// Stile dei caratteri di un bordo, usato da `StyledFrameBuffer::draw_panel`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BorderStyle {
+    /// `+`, `-`, `|`: compatibile con qualsiasi terminale
+    #[default]
+    Ascii,
+    Single,
+    Double,
+    Rounded,
+    Heavy,
+}
+
+impl BorderStyle {
+    /// Angoli (alto-sx, alto-dx, basso-sx, basso-dx), tratto orizzontale e verticale
+    pub fn chars(&self) -> ([char; 4], char, char) {
+        match self {
+            BorderStyle::Ascii => (['+', '+', '+', '+'], '-', '|'),
+            BorderStyle::Single => (['┌', '┐', '└', '┘'], '─', '│'),
+            BorderStyle::Double => (['╔', '╗', '╚', '╝'], '═', '║'),
+            BorderStyle::Rounded => (['╭', '╮', '╰', '╯'], '─', '│'),
+            BorderStyle::Heavy => (['┏', '┓', '┗', '┛'], '━', '┃'),
+        }
+    }
+}
+
+/// Attributo di riga DEC per testo a doppia larghezza/altezza.
+/// Supportato solo da alcuni terminali: il renderer lo emette solo se abilitato.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RowScale {
+    #[default]
+    Normal,
+    /// Doppia larghezza (`ESC # 6`)
+    DoubleWidth,
+    /// Metà superiore a doppia altezza (`ESC # 3`)
+    DoubleHeightTop,
+    /// Metà inferiore a doppia altezza (`ESC # 4`)
+    DoubleHeightBottom,
+}
+
+impl RowScale {
+    /// Sequenza di escape che applica l'attributo alla riga del cursore
+    pub fn to_escape(&self) -> &'static str {
+        match self {
+            RowScale::Normal => "\x1b#5",
+            RowScale::DoubleWidth => "\x1b#6",
+            RowScale::DoubleHeightTop => "\x1b#3",
+            RowScale::DoubleHeightBottom => "\x1b#4",
+        }
+    }
+}
+
+/// Opzioni per `StyledFrameBuffer::draw_text_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawTextOptions {
+    /// Ampiezza delle tabulazioni; 0 = i tab vengono sanitizzati come gli altri caratteri di controllo
+    pub tab_width: usize,
+}
+
+/// Opzioni per `StyledFrameBuffer::draw_border_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BorderOptions {
+    /// Caratteri del bordo (default ASCII, come `draw_border`)
+    pub style: BorderStyle,
+    /// Colore di primo piano dei quattro angoli; `None` = quello del bordo
+    pub corner_color: Option<Color>,
+}
+
+/// Attributi SGR del testo (grassetto, sottolineato, ...), combinabili con `|`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Attributes(u8);
+
+impl Attributes {
+    pub const BOLD: Attributes = Attributes(1);
+    pub const DIM: Attributes = Attributes(1 << 1);
+    pub const ITALIC: Attributes = Attributes(1 << 2);
+    pub const UNDERLINE: Attributes = Attributes(1 << 3);
+    pub const REVERSE: Attributes = Attributes(1 << 4);
+
+    /// Codici SGR nell'ordine dei bit
+    const SGR_CODES: [(Attributes, &'static str); 5] = [
+        (Attributes::BOLD, "\x1b[1m"),
+        (Attributes::DIM, "\x1b[2m"),
+        (Attributes::ITALIC, "\x1b[3m"),
+        (Attributes::UNDERLINE, "\x1b[4m"),
+        (Attributes::REVERSE, "\x1b[7m"),
+    ];
+
+    pub const fn empty() -> Self {
+        Attributes(0)
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(&self, other: Attributes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Attributes) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Attributes) {
+        self.0 &= !other.0;
+    }
+
+    /// Sequenze di escape che attivano gli attributi (stringa vuota se nessuno)
+    pub fn to_ansi(&self) -> String {
+        Self::SGR_CODES
+            .iter()
+            .filter(|(attr, _)| self.contains(*attr))
+            .map(|(_, code)| *code)
+            .collect()
+    }
+}
+
+impl core::ops::BitOr for Attributes {
+    type Output = Attributes;
+
+    fn bitor(self, rhs: Attributes) -> Attributes {
+        Attributes(self.0 | rhs.0)
+    }
+}
+
+/// Carattere con attributi di colore
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StyledChar {
     pub ch: char,
     pub fg_color: Option<Color>,
     pub bg_color: Option<Color>,
+    /// Segno combinante (es. accento U+0301) mostrato sopra `ch` nella stessa cella
+    pub combining: Option<char>,
+    pub attributes: Attributes,
 }
 
 impl StyledChar {
@@ -256,9 +739,45 @@ impl StyledChar {
             ch,
             fg_color: None,
             bg_color: None,
+            combining: None,
+            attributes: Attributes::empty(),
+        }
+    }
+
+    /// Scorciatoia per un carattere con entrambi i colori
+    pub fn styled(ch: char, fg_color: Color, bg_color: Color) -> Self {
+        Self {
+            fg_color: Some(fg_color),
+            bg_color: Some(bg_color),
+            ..Self::new(ch)
         }
     }
 
+    /// Cella completamente trasparente: uno spazio senza attributi con sfondo
+    /// `Color::Transparent`, che `blit` e il compositor saltano
+    pub fn is_transparent(&self) -> bool {
+        self.ch == ' '
+            && self.combining.is_none()
+            && self.attributes.is_empty()
+            && self.bg_color == Some(Color::Transparent)
+    }
+
+    /// Costruttore a catena: `StyledChar::builder().ch('x').fg(Color::Red).bold().build()`
+    pub fn builder() -> StyledCharBuilder {
+        StyledCharBuilder {
+            cell: StyledChar::default(),
+        }
+    }
+
+    /// Scrive il carattere seguito dall'eventuale segno combinante
+    pub(crate) fn write_glyph<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        out.write_char(self.ch)?;
+        if let Some(mark) = self.combining {
+            out.write_char(mark)?;
+        }
+        Ok(())
+    }
+
     pub fn with_fg(mut self, color: Color) -> Self {
         self.fg_color = Some(color);
         self
@@ -269,31 +788,90 @@ impl StyledChar {
         self
     }
 
-    pub fn to_string(&self) -> String {
+    pub fn with_attributes(mut self, attributes: Attributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+}
+
+/// Builder restituito da `StyledChar::builder`
+#[derive(Debug, Clone, Copy)]
+pub struct StyledCharBuilder {
+    cell: StyledChar,
+}
+
+impl StyledCharBuilder {
+    pub fn ch(mut self, ch: char) -> Self {
+        self.cell.ch = ch;
+        self
+    }
+
+    pub fn fg(mut self, color: Color) -> Self {
+        self.cell.fg_color = Some(color);
+        self
+    }
+
+    pub fn bg(mut self, color: Color) -> Self {
+        self.cell.bg_color = Some(color);
+        self
+    }
+
+    pub fn combining(mut self, mark: char) -> Self {
+        self.cell.combining = Some(mark);
+        self
+    }
+
+    pub fn attributes(mut self, attributes: Attributes) -> Self {
+        self.cell.attributes.insert(attributes);
+        self
+    }
+
+    pub fn bold(self) -> Self {
+        self.attributes(Attributes::BOLD)
+    }
+
+    pub fn dim(self) -> Self {
+        self.attributes(Attributes::DIM)
+    }
+
+    pub fn italic(self) -> Self {
+        self.attributes(Attributes::ITALIC)
+    }
+
+    pub fn underline(self) -> Self {
+        self.attributes(Attributes::UNDERLINE)
+    }
+
+    pub fn reverse(self) -> Self {
+        self.attributes(Attributes::REVERSE)
+    }
+
+    pub fn build(self) -> StyledChar {
+        self.cell
+    }
+}
+
+impl fmt::Display for StyledChar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Rendering ultra-ottimizzato per evitare disallineamenti
-        if self.fg_color.is_none() && self.bg_color.is_none() {
+        if opaque(self.fg_color).is_none() && opaque(self.bg_color).is_none() && self.attributes.is_empty() {
             // Solo carattere per massima performance
-            return self.ch.to_string();
+            return self.write_glyph(f);
         }
         
-        let mut result = String::with_capacity(16);
-        
-        // Applica colori solo se necessario
+        // Applica colori e attributi solo se necessario
+        f.write_str(&self.attributes.to_ansi())?;
         if let Some(fg) = self.fg_color {
-            result.push_str(fg.to_ansi_fg());
+            f.write_str(&fg.to_ansi_fg())?;
         }
         if let Some(bg) = self.bg_color {
-            result.push_str(bg.to_ansi_bg());
+            f.write_str(&bg.to_ansi_bg())?;
         }
         
-        result.push(self.ch);
+        self.write_glyph(f)?;
         
         // Reset pulito per evitare bleeding
-        if self.fg_color.is_some() || self.bg_color.is_some() {
-            result.push_str("\x1b[0m");
-        }
-        
-        result
+        f.write_str("\x1b[0m")
     }
 }
 
@@ -303,6 +881,92 @@ impl Default for StyledChar {
     }
 }
 
+/// Intestazione del formato binario di `StyledFrameBuffer::encode`
+const ENCODING_MAGIC: &[u8; 4] = b"STG1";
+
+/// Numero massimo di celle accettato da `StyledFrameBuffer::decode` (es. 2048x2048):
+/// un input di pochi byte non può chiedere allocazioni arbitrarie
+pub const MAX_DECODED_CELLS: usize = 1 << 22;
+
+/// Scrive `value` come varint LEB128 (7 bit per byte)
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Colore come tag (0 assente, 1-16 palette, 17 reset, 18 RGB seguito da 3 byte,
+/// 19 trasparente)
+fn write_color(out: &mut Vec<u8>, color: Option<Color>) {
+    match color {
+        None => out.push(0),
+        Some(Color::Reset) => out.push(17),
+        Some(Color::Rgb(r, g, b)) => out.extend_from_slice(&[18, r, g, b]),
+        Some(Color::Transparent) => out.push(19),
+        Some(named) => {
+            let index = ANSI_PALETTE.iter().position(|(color, _)| *color == named).unwrap_or(0);
+            out.push(index as u8 + 1);
+        }
+    }
+}
+
+/// Lettore sequenziale per `StyledFrameBuffer::decode`
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl ByteReader<'_> {
+    fn byte(&mut self) -> Result<u8, ConversionError> {
+        let byte = *self.bytes.get(self.pos)
+            .ok_or_else(|| ConversionError::DecodeFailed("dati troncati".to_string()))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, ConversionError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ConversionError::DecodeFailed("varint troppo lungo".to_string()))
+    }
+
+    /// Carattere UTF-8 (1-4 byte)
+    fn char(&mut self) -> Result<char, ConversionError> {
+        let len = match self.bytes.get(self.pos) {
+            Some(&first) if first < 0x80 => 1,
+            Some(&first) if first >= 0xF0 => 4,
+            Some(&first) if first >= 0xE0 => 3,
+            Some(_) => 2,
+            None => 0,
+        };
+        let ch = self.bytes.get(self.pos..self.pos + len)
+            .and_then(|bytes| core::str::from_utf8(bytes).ok())
+            .and_then(|text| text.chars().next())
+            .ok_or_else(|| ConversionError::DecodeFailed("carattere non valido".to_string()))?;
+        self.pos += len;
+        Ok(ch)
+    }
+
+    fn color(&mut self) -> Result<Option<Color>, ConversionError> {
+        Ok(match self.byte()? {
+            0 => None,
+            17 => Some(Color::Reset),
+            18 => Some(Color::Rgb(self.byte()?, self.byte()?, self.byte()?)),
+            19 => Some(Color::Transparent),
+            tag @ 1..=16 => Some(ANSI_PALETTE[tag as usize - 1].0),
+            tag => return Err(ConversionError::DecodeFailed(format!("colore sconosciuto {}", tag))),
+        })
+    }
+}
+
 /// FrameBuffer avanzato con supporto colori e stili
 #[derive(Debug, Clone)]
 pub struct StyledFrameBuffer {
@@ -310,6 +974,12 @@ pub struct StyledFrameBuffer {
     pub height: usize,
     pub data: Vec<StyledChar>,
     dirty_regions: Vec<Rect>,
+    /// Attributi di riga DEC (vuoto = tutte le righe normali)
+    row_scales: Vec<RowScale>,
+    /// Pila dei clip attivi; ogni elemento è già intersecato con quello sotto
+    clip_stack: Vec<Rect>,
+    /// Cella usata da `clear` e per le aree nuove di `resize`
+    default_cell: StyledChar,
 }
 
 impl StyledFrameBuffer {
@@ -319,16 +989,21 @@ impl StyledFrameBuffer {
             height,
             data: vec![StyledChar::default(); width * height],
             dirty_regions: Vec::new(),
+            row_scales: Vec::new(),
+            clip_stack: Vec::new(),
+            default_cell: StyledChar::default(),
         }
     }
 
+    #[cfg(feature = "std")]
     /// Crea un nuovo styled framebuffer utilizzando il pool
     pub fn new_pooled(width: usize, height: usize) -> Self {
         let size = width * height;
         
         let data = {
             let mut pool = STYLED_BUFFER_POOL.lock();
-            if let Some(mut reused_buffer) = pool.pop() {
+            let reused = if pool_max_buffers() > 0 { pool.pop() } else { None };
+            if let Some(mut reused_buffer) = reused {
                 reused_buffer.clear();
                 reused_buffer.resize(size, StyledChar::default());
                 reused_buffer
@@ -341,12 +1016,118 @@ impl StyledFrameBuffer {
             width, 
             height, 
             data,
-            dirty_regions: Vec::with_capacity(8)
+            dirty_regions: Vec::with_capacity(8),
+            row_scales: Vec::new(),
+            clip_stack: Vec::new(),
+            default_cell: StyledChar::default(),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// Rilascia il buffer al pool per il riutilizzo da parte di `new_pooled`
+    pub fn release_to_pool(mut self) {
+        if self.data.capacity() <= pool_max_capacity() {
+            let mut pool = STYLED_BUFFER_POOL.lock();
+            if pool.len() < pool_max_buffers() {
+                self.data.clear();
+                pool.push(self.data);
+            }
+        }
+    }
+
+    /// Serializza le celle in un formato binario compatto: intestazione `STG1`,
+    /// dimensioni come varint e poi coppie (lunghezza run, cella) sulle celle
+    /// consecutive identiche, che negli sfondi sono la norma
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + self.data.len() / 4);
+        out.extend_from_slice(ENCODING_MAGIC);
+        write_varint(&mut out, self.width as u64);
+        write_varint(&mut out, self.height as u64);
+        
+        let mut utf8 = [0u8; 4];
+        for run in self.data.chunk_by(|a, b| a == b) {
+            let cell = run[0];
+            write_varint(&mut out, run.len() as u64);
+            // Bit alto: segue un carattere combinante
+            out.push(cell.attributes.0 | if cell.combining.is_some() { 0x80 } else { 0 });
+            out.extend_from_slice(cell.ch.encode_utf8(&mut utf8).as_bytes());
+            if let Some(mark) = cell.combining {
+                out.extend_from_slice(mark.encode_utf8(&mut utf8).as_bytes());
+            }
+            write_color(&mut out, cell.fg_color);
+            write_color(&mut out, cell.bg_color);
+        }
+        out
+    }
+
+    /// Ricostruisce un buffer prodotto da `encode`. Buffer oltre `MAX_DECODED_CELLS`
+    /// celle, o che non si riescono ad allocare, danno `ConversionError::ImageTooLarge`
+    pub fn decode(bytes: &[u8]) -> Result<StyledFrameBuffer, ConversionError> {
+        let Some(body) = bytes.strip_prefix(ENCODING_MAGIC) else {
+            return Err(ConversionError::DecodeFailed("intestazione mancante".to_string()));
+        };
+        let mut reader = ByteReader { bytes: body, pos: 0 };
+        let width = reader.varint()? as usize;
+        let height = reader.varint()? as usize;
+        let cells = width.checked_mul(height).ok_or(ConversionError::ImageTooLarge)?;
+        if cells > MAX_DECODED_CELLS {
+            return Err(ConversionError::ImageTooLarge);
+        }
+        
+        let mut data = Vec::with_capacity(cells.min(bytes.len().saturating_mul(64)));
+        while data.len() < cells {
+            let run = reader.varint()? as usize;
+            if run == 0 || run > cells - data.len() {
+                return Err(ConversionError::DecodeFailed("run fuori dal buffer".to_string()));
+            }
+            let flags = reader.byte()?;
+            let ch = reader.char()?;
+            let combining = if flags & 0x80 != 0 { Some(reader.char()?) } else { None };
+            let cell = StyledChar {
+                ch,
+                fg_color: reader.color()?,
+                bg_color: reader.color()?,
+                combining,
+                attributes: Attributes(flags & 0x7F),
+            };
+            data.try_reserve(run).map_err(|_| ConversionError::ImageTooLarge)?;
+            data.extend(core::iter::repeat_n(cell, run));
         }
+        if reader.pos != body.len() {
+            return Err(ConversionError::DecodeFailed("dati in eccesso".to_string()));
+        }
+        
+        let mut buffer = StyledFrameBuffer::new(0, 0);
+        buffer.width = width;
+        buffer.height = height;
+        buffer.data = data;
+        Ok(buffer)
+    }
+
+    /// Indica se il contenuto visibile (dimensioni, celle e attributi di riga) è
+    /// identico a quello di `other`; le regioni dirty non contano
+    pub fn same_content(&self, other: &StyledFrameBuffer) -> bool {
+        (self.width, self.height) == (other.width, other.height)
+            && self.data == other.data
+            && self.row_scales == other.row_scales
+    }
+
+    /// Copia contenuto e dimensioni in `dst` riutilizzandone l'allocazione
+    /// (nessuna nuova allocazione se la capacità di `dst` è sufficiente)
+    pub fn clone_into(&self, dst: &mut StyledFrameBuffer) {
+        dst.width = self.width;
+        dst.height = self.height;
+        dst.data.clear();
+        dst.data.extend_from_slice(&self.data);
+        dst.dirty_regions.clear();
+        dst.dirty_regions.extend_from_slice(&self.dirty_regions);
+        dst.row_scales.clear();
+        dst.row_scales.extend_from_slice(&self.row_scales);
+        dst.default_cell = self.default_cell;
     }
 
     pub fn set(&mut self, x: usize, y: usize, styled_char: StyledChar) {
-        if x < self.width && y < self.height {
+        if x < self.width && y < self.height && self.clip_stack.last().is_none_or(|clip| clip.contains(x, y)) {
             let index = y * self.width + x;
             if self.data[index] != styled_char {
                 self.data[index] = styled_char;
@@ -355,6 +1136,26 @@ impl StyledFrameBuffer {
         }
     }
 
+    /// Scrive `len` celle identiche a partire da (x, y), clippando alla riga e al
+    /// clip corrente, con un'unica regione sporca per tutto il tratto
+    pub fn set_span(&mut self, x: usize, y: usize, len: usize, cell: StyledChar) {
+        let span = Rect::new(x, y, len.min(self.width.saturating_sub(x)), 1);
+        let span = match self.clip_stack.last() {
+            Some(clip) => match span.intersection(clip) {
+                Some(span) => span,
+                None => return,
+            },
+            None => span,
+        };
+        if y >= self.height || span.width == 0 {
+            return;
+        }
+        
+        let start = y * self.width + span.x;
+        self.data[start..start + span.width].fill(cell);
+        self.mark_dirty(span);
+    }
+
     pub fn get(&self, x: usize, y: usize) -> StyledChar {
         if x < self.width && y < self.height {
             self.data[y * self.width + x]
@@ -363,21 +1164,50 @@ impl StyledFrameBuffer {
         }
     }
 
-    pub fn clear(&mut self) {
-        self.data.fill(StyledChar::default());
-        self.mark_dirty(Rect::new(0, 0, self.width, self.height));
+    /// Limita le scritture successive a `rect` (intersecato con il clip corrente)
+    /// finché non viene chiamato `pop_clip`
+    pub fn push_clip(&mut self, rect: Rect) {
+        let clip = match self.clip_stack.last() {
+            Some(current) => current.intersection(&rect).unwrap_or(Rect::new(rect.x, rect.y, 0, 0)),
+            None => rect,
+        };
+        self.clip_stack.push(clip);
     }
 
-    pub fn clear_with(&mut self, styled_char: StyledChar) {
-        self.data.fill(styled_char);
-        self.mark_dirty(Rect::new(0, 0, self.width, self.height));
+    /// Rimuove l'ultimo clip inserito, restituendolo
+    pub fn pop_clip(&mut self) -> Option<Rect> {
+        self.clip_stack.pop()
     }
 
-    fn mark_dirty(&mut self, rect: Rect) {
-        self.dirty_regions.push(rect);
+    /// Clip attualmente in vigore, `None` se si può scrivere ovunque
+    pub fn current_clip(&self) -> Option<Rect> {
+        self.clip_stack.last().copied()
     }
 
-    pub fn get_dirty_regions(&self) -> &[Rect] {
+    pub fn clear(&mut self) {
+        self.data.fill(self.default_cell);
+        self.mark_dirty(Rect::new(0, 0, self.width, self.height));
+    }
+
+    /// Imposta la cella di sfondo usata da `clear` e da `resize` (es. colore del tema)
+    pub fn set_default_cell(&mut self, cell: StyledChar) {
+        self.default_cell = cell;
+    }
+
+    pub fn default_cell(&self) -> StyledChar {
+        self.default_cell
+    }
+
+    pub fn clear_with(&mut self, styled_char: StyledChar) {
+        self.data.fill(styled_char);
+        self.mark_dirty(Rect::new(0, 0, self.width, self.height));
+    }
+
+    fn mark_dirty(&mut self, rect: Rect) {
+        self.dirty_regions.push(rect);
+    }
+
+    pub fn get_dirty_regions(&self) -> &[Rect] {
         &self.dirty_regions
     }
 
@@ -385,26 +1215,181 @@ impl StyledFrameBuffer {
         self.dirty_regions.clear();
     }
 
-    /// Copia una porzione di altro framebuffer in questo
-    pub fn blit(&mut self, src: &StyledFrameBuffer, src_rect: Rect, dst_x: usize, dst_y: usize) {
-        for y in 0..src_rect.height {
-            for x in 0..src_rect.width {
-                if src_rect.x + x < src.width && src_rect.y + y < src.height {
-                    let styled_char = src.get(src_rect.x + x, src_rect.y + y);
-                    self.set(dst_x + x, dst_y + y, styled_char);
+    /// Area effettivamente copiabile: `src_rect` clippato a `src`, alla destinazione
+    /// e al clip corrente (in coordinate di destinazione)
+    fn clip_blit(&self, src: &StyledFrameBuffer, src_rect: Rect, dst_x: usize, dst_y: usize) -> Rect {
+        let width = src_rect.width
+            .min(src.width.saturating_sub(src_rect.x))
+            .min(self.width.saturating_sub(dst_x));
+        let height = src_rect.height
+            .min(src.height.saturating_sub(src_rect.y))
+            .min(self.height.saturating_sub(dst_y));
+        let area = Rect::new(dst_x, dst_y, width, height);
+        match self.clip_stack.last() {
+            Some(clip) => area.intersection(clip).unwrap_or(Rect::new(dst_x, dst_y, 0, 0)),
+            None => area,
+        }
+    }
+
+    /// Copia una porzione di altro framebuffer in questo, clippando sorgente e
+    /// destinazione. Restituisce la regione scritta (in coordinate di destinazione).
+    /// Le celle completamente trasparenti (`StyledChar::is_transparent`) vengono
+    /// saltate, e uno sfondo `Color::Transparent` lascia quello di destinazione.
+    pub fn blit(&mut self, src: &StyledFrameBuffer, src_rect: Rect, dst_x: usize, dst_y: usize) -> Rect {
+        let written = self.clip_blit(src, src_rect, dst_x, dst_y);
+        if written.width == 0 || written.height == 0 {
+            return written;
+        }
+        
+        let src_x = src_rect.x + (written.x - dst_x);
+        let src_y = src_rect.y + (written.y - dst_y);
+        for y in 0..written.height {
+            let src_start = (src_y + y) * src.width + src_x;
+            let dst_start = (written.y + y) * self.width + written.x;
+            let src_row = &src.data[src_start..src_start + written.width];
+            let dst_row = &mut self.data[dst_start..dst_start + written.width];
+            
+            // Copia diretta delle righe senza trasparenze
+            if !src_row.iter().any(|cell| cell.bg_color == Some(Color::Transparent)) {
+                dst_row.copy_from_slice(src_row);
+                continue;
+            }
+            for (dst, cell) in dst_row.iter_mut().zip(src_row) {
+                if cell.is_transparent() {
+                    continue;
+                }
+                let bg_color = if cell.bg_color == Some(Color::Transparent) { dst.bg_color } else { cell.bg_color };
+                *dst = StyledChar { bg_color, ..*cell };
+            }
+        }
+        self.mark_dirty(written);
+        written
+    }
+
+    #[cfg(feature = "std")]
+    /// Come `blit`, ma copia le celle così come sono, trasparenti comprese: per
+    /// duplicare un frame (buffer di confronto del renderer, registrazioni)
+    pub(crate) fn copy_rect(&mut self, src: &StyledFrameBuffer, src_rect: Rect, dst_x: usize, dst_y: usize) -> Rect {
+        let written = self.clip_blit(src, src_rect, dst_x, dst_y);
+        if written.width == 0 || written.height == 0 {
+            return written;
+        }
+        
+        let src_x = src_rect.x + (written.x - dst_x);
+        let src_y = src_rect.y + (written.y - dst_y);
+        for y in 0..written.height {
+            let src_start = (src_y + y) * src.width + src_x;
+            let dst_start = (written.y + y) * self.width + written.x;
+            self.data[dst_start..dst_start + written.width]
+                .copy_from_slice(&src.data[src_start..src_start + written.width]);
+        }
+        self.mark_dirty(written);
+        written
+    }
+
+    /// Come `blit`, ma le celle trasparenti della sorgente (spazio senza colori)
+    /// vengono saltate e quelle senza sfondo mantengono lo sfondo di destinazione
+    pub fn blit_blend(&mut self, src: &StyledFrameBuffer, src_rect: Rect, dst_x: usize, dst_y: usize) -> Rect {
+        let written = self.clip_blit(src, src_rect, dst_x, dst_y);
+        let src_x = src_rect.x + (written.x - dst_x);
+        let src_y = src_rect.y + (written.y - dst_y);
+        
+        for y in 0..written.height {
+            for x in 0..written.width {
+                let mut cell = src.get(src_x + x, src_y + y);
+                if cell == StyledChar::default() || cell.is_transparent() {
+                    continue;
+                }
+                if opaque(cell.bg_color).is_none() {
+                    cell.bg_color = self.get(written.x + x, written.y + y).bg_color;
+                }
+                self.set(written.x + x, written.y + y, cell);
+            }
+        }
+        written
+    }
+
+    /// Scrive il buffer con le sequenze ANSI, come `Display`/`to_string`, direttamente
+    /// in `out` (es. una `String` riusata tra i frame) senza allocazioni intermedie
+    pub fn write_to<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        // Rendering ottimizzato senza escape sequences ridondanti
+        let mut current_fg: Option<Color> = None;
+        let mut current_bg: Option<Color> = None;
+        let mut current_attributes = Attributes::empty();
+        
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let styled_char = self.get(x, y);
+                
+                // Gli attributi si disattivano solo con un reset, che azzera anche i colori
+                if styled_char.attributes != current_attributes {
+                    if !current_attributes.is_empty() {
+                        out.write_str("\x1b[0m")?;
+                        current_fg = None;
+                        current_bg = None;
+                    }
+                    current_attributes = styled_char.attributes;
+                    out.write_str(&current_attributes.to_ansi())?;
+                }
+                
+                // Cambia colori solo quando necessario
+                if opaque(styled_char.fg_color) != current_fg {
+                    current_fg = opaque(styled_char.fg_color);
+                    if let Some(fg) = current_fg {
+                        out.write_str(&fg.to_ansi_fg())?;
+                    } else if current_fg.is_none() && (self.has_colors_in_row(y, x) || y == 0) {
+                        out.write_str("\x1b[39m")?; // Reset foreground solo se necessario
+                    }
+                }
+                
+                if opaque(styled_char.bg_color) != current_bg {
+                    current_bg = opaque(styled_char.bg_color);
+                    if let Some(bg) = current_bg {
+                        out.write_str(&bg.to_ansi_bg())?;
+                    } else if current_bg.is_none() && (self.has_colors_in_row(y, x) || y == 0) {
+                        out.write_str("\x1b[49m")?; // Reset background solo se necessario
+                    }
+                }
+                
+                styled_char.write_glyph(out)?;
+            }
+            
+            // Reset colori e newline SOLO se non è l'ultima riga
+            if y < self.height - 1 {
+                // Reset colori solo se erano stati impostati
+                if current_fg.is_some() || current_bg.is_some() || !current_attributes.is_empty() {
+                    out.write_str("\x1b[0m")?;
+                    current_fg = None;
+                    current_bg = None;
+                    current_attributes = Attributes::empty();
                 }
+                out.write_char('\n')?;
             }
         }
+        
+        // Reset finale solo se necessario
+        if current_fg.is_some() || current_bg.is_some() || !current_attributes.is_empty() {
+            out.write_str("\x1b[0m")?;
+        }
+        
+        Ok(())
     }
 
     /// Renderizza solo le righe cambiate con controllo preciso dei caratteri
     pub fn render_partial(&self, last_buffer: &StyledFrameBuffer) -> String {
+        let mut result = String::with_capacity(512);
+        // La scrittura su una String non può fallire
+        let _ = self.write_partial_to(last_buffer, &mut result);
+        result
+    }
+
+    /// Come `render_partial`, ma scrive in `out` (es. una `String` riusata tra i
+    /// frame) invece di allocare. Con dimensioni diverse scrive l'intero buffer.
+    pub fn write_partial_to<W: fmt::Write>(&self, last_buffer: &StyledFrameBuffer, out: &mut W) -> fmt::Result {
         if self.width != last_buffer.width || self.height != last_buffer.height {
-            return self.to_string();
+            return self.write_to(out);
         }
 
-        let mut result = String::with_capacity(512);
-        
         // Confronta e renderizza solo le righe cambiate
         for y in 0..self.height {
             let mut row_changed = false;
@@ -418,100 +1403,177 @@ impl StyledFrameBuffer {
             }
             
             if row_changed {
-                // Posiziona cursore all'inizio della riga
-                result.push_str(&format!("\x1b[{};1H", y + 1));
+                // Posiziona cursore all'inizio della riga e parti da uno stato SGR pulito,
+                // qualunque cosa abbiano lasciato le righe vicine
+                write!(out, "\x1b[{};1H", y + 1)?;
+                out.write_str("\x1b[0m")?;
                 
                 // Renderizza la riga completa con gestione colori ottimizzata
                 let mut current_fg: Option<Color> = None;
                 let mut current_bg: Option<Color> = None;
+                let mut current_attributes = Attributes::empty();
                 
                 // IMPORTANTE: Renderizza SEMPRE tutta la larghezza della riga
                 for x in 0..self.width {
                     let styled_char = self.get(x, y);
                     
+                    // Gli attributi si disattivano solo con un reset, che azzera anche i colori
+                    if styled_char.attributes != current_attributes {
+                        if !current_attributes.is_empty() {
+                            out.write_str("\x1b[0m")?;
+                            current_fg = None;
+                            current_bg = None;
+                        }
+                        current_attributes = styled_char.attributes;
+                        out.write_str(&current_attributes.to_ansi())?;
+                    }
+                    
                     // Cambia colori solo quando necessario
-                    if styled_char.fg_color != current_fg {
-                        current_fg = styled_char.fg_color;
+                    if opaque(styled_char.fg_color) != current_fg {
+                        current_fg = opaque(styled_char.fg_color);
                         if let Some(fg) = current_fg {
-                            result.push_str(fg.to_ansi_fg());
+                            out.write_str(&fg.to_ansi_fg())?;
                         } else {
-                            result.push_str("\x1b[39m");
+                            out.write_str("\x1b[39m")?;
                         }
                     }
                     
-                    if styled_char.bg_color != current_bg {
-                        current_bg = styled_char.bg_color;
+                    if opaque(styled_char.bg_color) != current_bg {
+                        current_bg = opaque(styled_char.bg_color);
                         if let Some(bg) = current_bg {
-                            result.push_str(bg.to_ansi_bg());
+                            out.write_str(&bg.to_ansi_bg())?;
                         } else {
-                            result.push_str("\x1b[49m");
+                            out.write_str("\x1b[49m")?;
                         }
                     }
                     
-                    result.push(styled_char.ch);
+                    styled_char.write_glyph(out)?;
                 }
                 
+                // Reset colori alla fine della riga, prima della pulizia così lo sfondo
+                // dell'ultima cella non si estende al resto della riga
+                out.write_str("\x1b[0m")?;
                 // Pulisci il resto della riga per evitare caratteri fantasma
-                result.push_str("\x1b[0K"); // Clear to end of line
-                // Reset colori alla fine della riga
-                result.push_str("\x1b[0m");
+                out.write_str("\x1b[0K")?; // Clear to end of line
             }
         }
         
-        result
+        Ok(())
     }
 
-    pub fn to_string(&self) -> String {
-        let mut result = String::with_capacity(self.width * self.height * 4);
-        
-        // Rendering ottimizzato senza escape sequences ridondanti
-        let mut current_fg: Option<Color> = None;
-        let mut current_bg: Option<Color> = None;
+    /// Esporta il framebuffer come blocco HTML `<pre>` con uno `<span>` per ogni run di celle con lo stesso stile
+    pub fn to_html(&self) -> String {
+        let mut result = String::with_capacity(self.width * self.height * 2 + 16);
+        result.push_str("<pre>");
         
         for y in 0..self.height {
-            for x in 0..self.width {
-                let styled_char = self.get(x, y);
+            let mut x = 0;
+            while x < self.width {
+                let style = (self.get(x, y).fg_color, self.get(x, y).bg_color, self.get(x, y).attributes);
                 
-                // Cambia colori solo quando necessario
-                if styled_char.fg_color != current_fg {
-                    current_fg = styled_char.fg_color;
-                    if let Some(fg) = current_fg {
-                        result.push_str(fg.to_ansi_fg());
-                    } else if current_fg.is_none() && (self.has_colors_in_row(y, x) || y == 0) {
-                        result.push_str("\x1b[39m"); // Reset foreground solo se necessario
+                // Raccogli il run di celle con lo stesso stile
+                let mut run = String::new();
+                while x < self.width {
+                    let styled_char = self.get(x, y);
+                    if (styled_char.fg_color, styled_char.bg_color, styled_char.attributes) != style {
+                        break;
                     }
-                }
-                
-                if styled_char.bg_color != current_bg {
-                    current_bg = styled_char.bg_color;
-                    if let Some(bg) = current_bg {
-                        result.push_str(bg.to_ansi_bg());
-                    } else if current_bg.is_none() && (self.has_colors_in_row(y, x) || y == 0) {
-                        result.push_str("\x1b[49m"); // Reset background solo se necessario
+                    match styled_char.ch {
+                        '&' => run.push_str("&amp;"),
+                        '<' => run.push_str("&lt;"),
+                        '>' => run.push_str("&gt;"),
+                        '"' => run.push_str("&quot;"),
+                        '\'' => run.push_str("&#39;"),
+                        ch => run.push(ch),
                     }
+                    run.extend(styled_char.combining);
+                    x += 1;
                 }
                 
-                result.push(styled_char.ch);
+                // Color::Reset equivale al colore di default del terminale
+                let css_fg = opaque(style.0).filter(|c| *c != Color::Reset).map(|c| format!("color:{}", c.to_hex()));
+                let css_bg = opaque(style.1).filter(|c| *c != Color::Reset).map(|c| format!("background:{}", c.to_hex()));
+                let css_attributes = [
+                    (Attributes::BOLD, "font-weight:bold"),
+                    (Attributes::ITALIC, "font-style:italic"),
+                    (Attributes::UNDERLINE, "text-decoration:underline"),
+                ]
+                .into_iter()
+                .filter(|(attr, _)| style.2.contains(*attr))
+                .map(|(_, css)| css.to_string());
+                let css: Vec<String> = css_fg.into_iter().chain(css_bg).chain(css_attributes).collect();
+                
+                if css.is_empty() {
+                    result.push_str(&run);
+                } else {
+                    result.push_str(&format!("<span style=\"{}\">{}</span>", css.join(";"), run));
+                }
             }
             
-            // Reset colori e newline SOLO se non è l'ultima riga
             if y < self.height - 1 {
-                // Reset colori solo se erano stati impostati
-                if current_fg.is_some() || current_bg.is_some() {
-                    result.push_str("\x1b[0m");
-                    current_fg = None;
-                    current_bg = None;
-                }
                 result.push('\n');
             }
         }
         
-        // Reset finale solo se necessario
-        if current_fg.is_some() || current_bg.is_some() {
-            result.push_str("\x1b[0m");
+        result.push_str("</pre>");
+        result
+    }
+
+    /// Rasterizza il framebuffer in un'immagine RGB, una cella = blocco `cell_w`x`cell_h`.
+    /// Lo sfondo usa `bg_color` (nero se assente); i caratteri Braille vengono disegnati
+    /// punto per punto in `fg_color` e gli altri glifi visibili come blocco centrato.
+    /// Se le dimensioni in pixel non stanno in `u32` (o il buffer RGB in `usize`)
+    /// restituisce `ConversionError::ImageTooLarge`.
+    #[cfg(feature = "std")]
+    pub fn to_image(&self, cell_w: u32, cell_h: u32) -> Result<RgbImage, ConversionError> {
+        let cell_w = cell_w.max(1);
+        let cell_h = cell_h.max(1);
+        let image_w = u32::try_from(self.width).ok().and_then(|w| w.checked_mul(cell_w));
+        let image_h = u32::try_from(self.height).ok().and_then(|h| h.checked_mul(cell_h));
+        let (image_w, image_h) = image_w.zip(image_h).ok_or(ConversionError::ImageTooLarge)?;
+        (image_w as usize)
+            .checked_mul(image_h as usize)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or(ConversionError::ImageTooLarge)?;
+        let mut img = RgbImage::new(image_w, image_h);
+        // Frazione di una cella, in u64 perché `cell_w * 2` può non stare in u32
+        let part = |size: u32, num: u32, den: u32| (u64::from(size) * u64::from(num) / u64::from(den)) as u32;
+        
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let styled_char = self.get(x, y);
+                let origin_x = x as u32 * cell_w;
+                let origin_y = y as u32 * cell_h;
+                
+                let (r, g, b) = styled_char.bg_color.unwrap_or(Color::Black).to_rgb();
+                fill_image_rect(&mut img, origin_x, origin_y, cell_w, cell_h, Rgb([r, g, b]));
+                
+                let (r, g, b) = styled_char.fg_color.unwrap_or(Color::White).to_rgb();
+                let ink = Rgb([r, g, b]);
+                let code = styled_char.ch as u32;
+                
+                if (0x2800..=0x28FF).contains(&code) {
+                    // Un punto Braille per ogni bit attivo, se la cella ha spazio per la griglia 2x4
+                    if cell_w >= 2 && cell_h >= 4 {
+                        for (bit, &(dot_x, dot_y)) in BRAILLE_DOT_POSITIONS.iter().enumerate() {
+                            if code & (1 << bit) != 0 {
+                                let x0 = part(cell_w, dot_x as u32, 2);
+                                let x1 = part(cell_w, dot_x as u32 + 1, 2);
+                                let y0 = part(cell_h, dot_y as u32, 4);
+                                let y1 = part(cell_h, dot_y as u32 + 1, 4);
+                                fill_image_rect(&mut img, origin_x + x0, origin_y + y0, x1 - x0, y1 - y0, ink);
+                            }
+                        }
+                    }
+                } else if !styled_char.ch.is_whitespace() {
+                    let x0 = cell_w / 4;
+                    let y0 = cell_h / 4;
+                    fill_image_rect(&mut img, origin_x + x0, origin_y + y0, cell_w - 2 * x0, cell_h - 2 * y0, ink);
+                }
+            }
         }
         
-        result
+        Ok(img)
     }
 
     // Helper per verificare se ci sono colori nella riga
@@ -527,6 +1589,11 @@ impl StyledFrameBuffer {
 
     /// Disegna testo con controllo rigoroso delle dimensioni
     pub fn draw_text(&mut self, x: usize, y: usize, text: &str, fg_color: Option<Color>, bg_color: Option<Color>) {
+        self.draw_text_with(x, y, text, fg_color, bg_color, DrawTextOptions::default());
+    }
+
+    /// Come `draw_text`, con opzioni aggiuntive (es. espansione dei tab)
+    pub fn draw_text_with(&mut self, x: usize, y: usize, text: &str, fg_color: Option<Color>, bg_color: Option<Color>, options: DrawTextOptions) {
         if y >= self.height || x >= self.width {
             return;
         }
@@ -535,31 +1602,60 @@ impl StyledFrameBuffer {
         let max_chars = self.width - x;
         let mut char_count = 0;
         
-        for ch in text.chars() {
+        // Un grapheme per cella: i segni combinanti restano attaccati al carattere base
+        for grapheme in text.graphemes(true) {
             if char_count >= max_chars {
                 break;
             }
             
-            let pos_x = x + char_count;
-            if pos_x >= self.width {
-                break;
-            }
-            
-            // Evita caratteri di controllo che possono causare disallineamenti
-            let safe_char = if ch.is_control() || ch as u32 > 127 {
-                '?'
+            // Tab: spazi fino al prossimo multiplo di tab_width (relativo all'inizio del testo)
+            let ((ch, combining), advance) = if grapheme == "\t" && options.tab_width > 0 {
+                ((' ', None), options.tab_width - char_count % options.tab_width)
             } else {
-                ch
+                (grapheme_cell(grapheme), 1)
             };
             
-            let styled_char = StyledChar {
-                ch: safe_char,
-                fg_color,
-                bg_color,
-            };
-            self.set(pos_x, y, styled_char);
-            char_count += 1;
+            for _ in 0..advance.min(max_chars - char_count) {
+                let styled_char = StyledChar {
+                    ch,
+                    fg_color,
+                    bg_color,
+                    combining,
+                    attributes: Attributes::empty(),
+                };
+                self.set(x + char_count, y, styled_char);
+                char_count += 1;
+            }
+        }
+    }
+
+    /// Disegna testo a capo automatico dentro `rect`, spezzando sugli spazi quando possibile.
+    /// Le parole più lunghe della riga vengono spezzate a forza; il testo oltre il fondo
+    /// del rect viene scartato. Ritorna il numero di righe usate.
+    pub fn draw_text_wrapped(&mut self, rect: Rect, text: &str, fg_color: Option<Color>, bg_color: Option<Color>) -> usize {
+        if rect.x >= self.width || rect.y >= self.height {
+            return 0;
+        }
+        
+        let width = rect.width.min(self.width - rect.x);
+        let max_rows = rect.height.min(self.height - rect.y);
+        let lines = wrap_text(text, width);
+        let rows = lines.len().min(max_rows);
+        
+        for (row, line) in lines.iter().take(rows).enumerate() {
+            for (col, grapheme) in line.graphemes(true).enumerate() {
+                let (ch, combining) = grapheme_cell(grapheme);
+                self.set(rect.x + col, rect.y + row, StyledChar {
+                    ch,
+                    fg_color,
+                    bg_color,
+                    combining,
+                    attributes: Attributes::empty(),
+                });
+            }
         }
+        
+        rows
     }
 
     /// Disegna rettangolo con bounds checking rigoroso
@@ -568,23 +1664,177 @@ impl StyledFrameBuffer {
             ch,
             fg_color,
             bg_color,
+            combining: None,
+            attributes: Attributes::empty(),
         };
 
         // Calcola bounds sicuri
+        let start_y = rect.y.min(self.height);
+        let end_y = (rect.y + rect.height).min(self.height);
+        
+        for y in start_y..end_y {
+            self.set_span(rect.x, y, rect.width, styled_char);
+        }
+    }
+
+    /// Riempie lo sfondo di un rettangolo con un gradiente RGB tra due colori
+    pub fn fill_gradient(&mut self, rect: Rect, from: Color, to: Color, direction: GradientDirection) {
         let start_x = rect.x.min(self.width);
         let start_y = rect.y.min(self.height);
         let end_x = (rect.x + rect.width).min(self.width);
         let end_y = (rect.y + rect.height).min(self.height);
         
+        // Il gradiente copre l'intero rect, anche se viene clippato
+        let span_x = rect.width.saturating_sub(1) as f32;
+        let span_y = rect.height.saturating_sub(1) as f32;
+        
         for y in start_y..end_y {
             for x in start_x..end_x {
-                self.set(x, y, styled_char);
+                let dx = (x - rect.x) as f32;
+                let dy = (y - rect.y) as f32;
+                let t = match direction {
+                    GradientDirection::Horizontal => if span_x > 0.0 { dx / span_x } else { 0.0 },
+                    GradientDirection::Vertical => if span_y > 0.0 { dy / span_y } else { 0.0 },
+                    GradientDirection::Diagonal => {
+                        let span = span_x + span_y;
+                        if span > 0.0 { (dx + dy) / span } else { 0.0 }
+                    }
+                };
+                
+                let mut cell = self.get(x, y);
+                cell.bg_color = Some(Color::lerp_srgb(from, to, t));
+                self.set(x, y, cell);
+            }
+        }
+    }
+
+    /// Sovrascrive con `overlay` le celle di `rect` che seguono `pattern`, lasciando
+    /// invariate le altre: trasparenza "a zanzariera" per sfondi modali o finestre inattive
+    pub fn apply_stipple(&mut self, rect: Rect, pattern: StipplePattern, overlay: StyledChar) {
+        let end_x = (rect.x + rect.width).min(self.width);
+        let end_y = (rect.y + rect.height).min(self.height);
+        
+        for y in rect.y.min(end_y)..end_y {
+            for x in rect.x.min(end_x)..end_x {
+                if pattern.covers(x - rect.x, y - rect.y) {
+                    self.set(x, y, overlay);
+                }
+            }
+        }
+    }
+
+    /// Specchia il buffer orizzontalmente (sinistra <-> destra)
+    pub fn flip_horizontal(&mut self) {
+        if self.width == 0 {
+            return;
+        }
+        for row in self.data.chunks_mut(self.width) {
+            row.reverse();
+        }
+        self.mark_dirty(Rect::new(0, 0, self.width, self.height));
+    }
+
+    /// Specchia il buffer verticalmente (alto <-> basso), attributi di riga inclusi
+    pub fn flip_vertical(&mut self) {
+        for y in 0..self.height / 2 {
+            for x in 0..self.width {
+                self.data.swap(y * self.width + x, (self.height - 1 - y) * self.width + x);
+            }
+        }
+        if !self.row_scales.is_empty() {
+            self.row_scales.resize(self.height, RowScale::Normal);
+            self.row_scales.reverse();
+        }
+        self.mark_dirty(Rect::new(0, 0, self.width, self.height));
+    }
+
+    /// Nuovo buffer trasposto (righe <-> colonne): larghezza e altezza si scambiano
+    pub fn transpose(&self) -> StyledFrameBuffer {
+        let mut result = StyledFrameBuffer::new(self.height, self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                result.data[x * self.height + y] = self.data[y * self.width + x];
+            }
+        }
+        result
+    }
+
+    /// Nuovo buffer ruotato di 90° in senso orario; con `remap_glyphs` i caratteri
+    /// direzionali (box-drawing, frecce) vengono ruotati di conseguenza
+    pub fn rotate_cw(&self, remap_glyphs: bool) -> StyledFrameBuffer {
+        self.rotate(true, remap_glyphs)
+    }
+
+    /// Nuovo buffer ruotato di 90° in senso antiorario (vedi `rotate_cw`)
+    pub fn rotate_ccw(&self, remap_glyphs: bool) -> StyledFrameBuffer {
+        self.rotate(false, remap_glyphs)
+    }
+
+    fn rotate(&self, clockwise: bool, remap_glyphs: bool) -> StyledFrameBuffer {
+        let mut result = StyledFrameBuffer::new(self.height, self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (new_x, new_y) = if clockwise {
+                    (self.height - 1 - y, x)
+                } else {
+                    (y, self.width - 1 - x)
+                };
+                let mut cell = self.data[y * self.width + x];
+                if remap_glyphs {
+                    cell.ch = rotate_glyph(cell.ch, clockwise);
+                }
+                result.data[new_y * result.width + new_x] = cell;
+            }
+        }
+        result
+    }
+
+    /// Dissolvenza tra due frame: ogni cella prende il carattere di `a` o `b` secondo
+    /// una soglia ordinata (Bayer 4x4) confrontata con `t`, e interpola i colori
+    /// quando entrambi sono RGB. Lavora sulla sovrapposizione delle tre dimensioni.
+    pub fn lerp_from(&mut self, a: &StyledFrameBuffer, b: &StyledFrameBuffer, t: f32) {
+        const BAYER_4X4: [[u8; 4]; 4] = [
+            [0, 8, 2, 10],
+            [12, 4, 14, 6],
+            [3, 11, 1, 9],
+            [15, 7, 13, 5],
+        ];
+        
+        let t = t.clamp(0.0, 1.0);
+        let width = self.width.min(a.width).min(b.width);
+        let height = self.height.min(a.height).min(b.height);
+        
+        let mix = |from: Option<Color>, to: Option<Color>, picked: Option<Color>| match (from, to) {
+            (Some(from @ Color::Rgb(..)), Some(to @ Color::Rgb(..))) => Some(Color::lerp_srgb(from, to, t)),
+            _ => picked,
+        };
+        
+        for y in 0..height {
+            for x in 0..width {
+                let cell_a = a.get(x, y);
+                let cell_b = b.get(x, y);
+                let threshold = (BAYER_4X4[y % 4][x % 4] as f32 + 0.5) / 16.0;
+                let picked = if t > threshold { cell_b } else { cell_a };
+                
+                self.set(x, y, StyledChar {
+                    ch: picked.ch,
+                    fg_color: mix(cell_a.fg_color, cell_b.fg_color, picked.fg_color),
+                    bg_color: mix(cell_a.bg_color, cell_b.bg_color, picked.bg_color),
+                    combining: picked.combining,
+                    attributes: picked.attributes,
+                });
             }
         }
     }
 
-    /// Disegna bordo con dimensioni verificate
-    pub fn draw_border(&mut self, rect: Rect, fg_color: Option<Color>, _bg_color: Option<Color>) {
+    /// Disegna bordo con dimensioni verificate; `bg_color` viene applicato alle
+    /// celle del bordo
+    pub fn draw_border(&mut self, rect: Rect, fg_color: Option<Color>, bg_color: Option<Color>) {
+        self.draw_border_with(rect, fg_color, bg_color, BorderOptions::default());
+    }
+
+    /// Come `draw_border`, con stile dei caratteri e colore degli angoli a scelta
+    pub fn draw_border_with(&mut self, rect: Rect, fg_color: Option<Color>, bg_color: Option<Color>, options: BorderOptions) {
         if rect.width < 2 || rect.height < 2 || 
            rect.x >= self.width || rect.y >= self.height {
             return;
@@ -601,34 +1851,91 @@ impl StyledFrameBuffer {
             return;
         }
 
-        // Caratteri bordo semplificati per compatibilità
-        let corner = '+';
-        let horizontal = '-';
-        let vertical = '|';
+        let style_cell = StyledChar { fg_color: Some(color), bg_color, ..StyledChar::new(' ') };
+        self.draw_border_chars(rect.x, rect.y, right, bottom, options.style, style_cell);
+        
+        if let Some(corner_color) = options.corner_color {
+            let ([top_left, top_right, bottom_left, bottom_right], _, _) = options.style.chars();
+            let corner = |ch: char| StyledChar { ch, fg_color: Some(corner_color), ..style_cell };
+            self.set(rect.x, rect.y, corner(top_left));
+            self.set(right, rect.y, corner(top_right));
+            self.set(rect.x, bottom, corner(bottom_left));
+            self.set(right, bottom, corner(bottom_right));
+        }
+    }
+
+    /// Traccia il bordo tra gli angoli (left, top) e (right, bottom) già clippati,
+    /// con i colori e gli attributi di `style_cell`
+    fn draw_border_chars(&mut self, left: usize, top: usize, right: usize, bottom: usize, style: BorderStyle, style_cell: StyledChar) {
+        let ([top_left, top_right, bottom_left, bottom_right], horizontal, vertical) = style.chars();
+        let cell = |ch: char| StyledChar { ch, ..style_cell };
 
         // Angoli
-        self.set(rect.x, rect.y, StyledChar::new(corner).with_fg(color));
-        self.set(right, rect.y, StyledChar::new(corner).with_fg(color));
-        self.set(rect.x, bottom, StyledChar::new(corner).with_fg(color));
-        self.set(right, bottom, StyledChar::new(corner).with_fg(color));
+        self.set(left, top, cell(top_left));
+        self.set(right, top, cell(top_right));
+        self.set(left, bottom, cell(bottom_left));
+        self.set(right, bottom, cell(bottom_right));
 
         // Linee orizzontali
-        for x in (rect.x + 1)..right {
+        for x in (left + 1)..right {
             if x < self.width {
-                self.set(x, rect.y, StyledChar::new(horizontal).with_fg(color));
-                self.set(x, bottom, StyledChar::new(horizontal).with_fg(color));
+                self.set(x, top, cell(horizontal));
+                self.set(x, bottom, cell(horizontal));
             }
         }
 
         // Linee verticali
-        for y in (rect.y + 1)..bottom {
+        for y in (top + 1)..bottom {
             if y < self.height {
-                self.set(rect.x, y, StyledChar::new(vertical).with_fg(color));
-                self.set(right, y, StyledChar::new(vertical).with_fg(color));
+                self.set(left, y, cell(vertical));
+                self.set(right, y, cell(vertical));
+            }
+        }
+    }
+
+    /// Ombra finta: scurisce le celle del rect spostato di `offset` che restano
+    /// fuori da `rect` (in basso a destra). Il contenuto resta, vengono solo
+    /// attenuati i colori; lo sfondo predefinito diventa nero.
+    pub fn draw_shadow(&mut self, rect: Rect, offset: (usize, usize)) {
+        let shadow = Rect::new(rect.x + offset.0, rect.y + offset.1, rect.width, rect.height);
+        let end_x = (shadow.x + shadow.width).min(self.width);
+        let end_y = (shadow.y + shadow.height).min(self.height);
+        let dim = |color: Color| Color::lerp(color, Color::Black, 0.5);
+        
+        for y in shadow.y..end_y {
+            for x in shadow.x..end_x {
+                if rect.contains(x, y) {
+                    continue;
+                }
+                let mut cell = self.get(x, y);
+                cell.bg_color = match cell.bg_color {
+                    Some(color) if color != Color::Reset => Some(dim(color)),
+                    _ => Some(Color::Black),
+                };
+                cell.fg_color = cell.fg_color.filter(|&color| color != Color::Reset).map(dim);
+                self.set(x, y, cell);
             }
         }
     }
 
+    /// Pannello: sfondo pieno, bordo nello stile indicato e, se richiesto,
+    /// ombra di due colonne e una riga (le celle sono alte circa il doppio)
+    pub fn draw_panel(&mut self, rect: Rect, style: BorderStyle, fg_color: Color, bg_color: Color, shadow: bool) {
+        if shadow {
+            self.draw_shadow(rect, (2, 1));
+        }
+        self.draw_rect(rect, ' ', Some(fg_color), Some(bg_color));
+        
+        if rect.width < 2 || rect.height < 2 || rect.x >= self.width || rect.y >= self.height {
+            return;
+        }
+        let right = (rect.x + rect.width - 1).min(self.width - 1);
+        let bottom = (rect.y + rect.height - 1).min(self.height - 1);
+        if right > rect.x && bottom > rect.y {
+            self.draw_border_chars(rect.x, rect.y, right, bottom, style, StyledChar::styled(' ', fg_color, bg_color));
+        }
+    }
+
     /// Cursore mouse ottimizzato
     pub fn draw_mouse_cursor(&mut self, x: usize, y: usize, visible: bool) {
         if !visible || x >= self.width || y >= self.height {
@@ -643,6 +1950,23 @@ impl StyledFrameBuffer {
         self.set(x, y, cursor_style);
     }
 
+    /// Imposta l'attributo di riga DEC (doppia larghezza/altezza) per la riga `y`
+    pub fn set_row_scale(&mut self, y: usize, scale: RowScale) {
+        if y >= self.height || self.get_row_scale(y) == scale {
+            return;
+        }
+        if self.row_scales.len() < self.height {
+            self.row_scales.resize(self.height, RowScale::Normal);
+        }
+        self.row_scales[y] = scale;
+        self.mark_dirty(Rect::new(0, y, self.width, 1));
+    }
+
+    /// Attributo di riga DEC della riga `y`
+    pub fn get_row_scale(&self, y: usize) -> RowScale {
+        self.row_scales.get(y).copied().unwrap_or_default()
+    }
+
     /// Forza un refresh completo del framebuffer
     pub fn force_refresh(&mut self) {
         self.mark_dirty(Rect::new(0, 0, self.width, self.height));
@@ -654,7 +1978,7 @@ impl StyledFrameBuffer {
             return;
         }
 
-        let mut new_data = vec![StyledChar::default(); new_width * new_height];
+        let mut new_data = vec![self.default_cell; new_width * new_height];
         
         // Copia i dati esistenti con clipping sicuro
         let copy_width = new_width.min(self.width);
@@ -670,6 +1994,7 @@ impl StyledFrameBuffer {
         self.width = new_width;
         self.height = new_height;
         self.data = new_data;
+        self.row_scales.truncate(new_height);
         self.force_refresh();
     }
     
@@ -683,8 +2008,42 @@ impl StyledFrameBuffer {
         self.get(x, y) != other.get(x, y)
     }
     
+    /// Celle di `other` diverse da questo buffer, come (x, y, nuova cella), in ordine
+    /// di riga: a differenza di `get_changed_regions` elenca solo le celle cambiate,
+    /// adatto a trasmettere i frame (es. su un socket). Fuori da questo buffer si
+    /// confronta con la cella di default.
+    pub fn diff(&self, other: &StyledFrameBuffer) -> Vec<(usize, usize, StyledChar)> {
+        let mut changes = Vec::new();
+        for y in 0..other.height {
+            for x in 0..other.width {
+                let cell = other.get(x, y);
+                if self.get(x, y) != cell {
+                    changes.push((x, y, cell));
+                }
+            }
+        }
+        changes
+    }
+
+    /// Applica un diff prodotto da `diff` rispettando il clip corrente e marcando
+    /// sporche le celle cambiate; quelle fuori dal buffer vengono ignorate
+    pub fn apply_diff(&mut self, diff: &[(usize, usize, StyledChar)]) {
+        for &(x, y, cell) in diff {
+            self.set(x, y, cell);
+        }
+    }
+    
     /// Confronta buffer e restituisce regioni cambiate
     pub fn get_changed_regions(&self, other: &StyledFrameBuffer) -> Vec<Rect> {
+        self.get_changed_regions_with(other, 8)
+    }
+
+    /// Come `get_changed_regions` con blocchi di `block_size` x `block_size` celle.
+    /// Si assume una potenza di due: blocchi piccoli isolano meglio aggiornamenti
+    /// puntuali (es. un orologio), blocchi grandi producono meno regioni.
+    pub fn get_changed_regions_with(&self, other: &StyledFrameBuffer, block_size: usize) -> Vec<Rect> {
+        debug_assert!(block_size.is_power_of_two(), "block_size deve essere una potenza di due");
+        let block_size = block_size.max(1);
         let mut regions = Vec::new();
         
         if self.width != other.width || self.height != other.height {
@@ -693,24 +2052,25 @@ impl StyledFrameBuffer {
         }
         
         // Scansione a blocchi per efficienza
-        let block_size = 8;
+        let width = self.width;
         
         for block_y in (0..self.height).step_by(block_size) {
-            for block_x in (0..self.width).step_by(block_size) {
-                let mut block_changed = false;
-                
-                let end_x = (block_x + block_size).min(self.width);
-                let end_y = (block_y + block_size).min(self.height);
+            let end_y = (block_y + block_size).min(self.height);
+            
+            // Salta l'intera fascia di righe se identica
+            let band = block_y * width..end_y * width;
+            if self.data[band.clone()] == other.data[band] {
+                continue;
+            }
+            
+            for block_x in (0..width).step_by(block_size) {
+                let end_x = (block_x + block_size).min(width);
                 
-                // Verifica se il blocco è cambiato
-                'block_check: for y in block_y..end_y {
-                    for x in block_x..end_x {
-                        if self.is_different_from(other, x, y) {
-                            block_changed = true;
-                            break 'block_check;
-                        }
-                    }
-                }
+                // Confronto a slice riga per riga all'interno del blocco
+                let block_changed = (block_y..end_y).any(|y| {
+                    let row = y * width;
+                    self.data[row + block_x..row + end_x] != other.data[row + block_x..row + end_x]
+                });
                 
                 if block_changed {
                     regions.push(Rect::new(
@@ -727,271 +2087,3362 @@ impl StyledFrameBuffer {
     }
 }
 
-/// Errori che possono verificarsi durante la conversione
-#[derive(Debug)]
-pub enum ConversionError {
-    InvalidDimensions,
-    ImageTooLarge,
+impl fmt::Display for StyledFrameBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
 }
 
-impl std::fmt::Display for ConversionError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ConversionError::InvalidDimensions => write!(f, "Dimensioni non valide"),
-            ConversionError::ImageTooLarge => write!(f, "Immagine troppo grande"),
+/// Cicli di rotazione oraria dei caratteri direzionali (ogni passo = 90°)
+const GLYPH_ROTATIONS: &[&[char]] = &[
+    &['─', '│'], &['-', '|'], &['═', '║'], &['━', '┃'],
+    &['┌', '┐', '┘', '└'], &['╭', '╮', '╯', '╰'], &['╔', '╗', '╝', '╚'],
+    &['├', '┬', '┤', '┴'], &['╠', '╦', '╣', '╩'],
+    &['→', '↓', '←', '↑'], &['↔', '↕'],
+];
+
+/// Ruota di 90° un carattere direzionale; gli altri restano invariati
+fn rotate_glyph(ch: char, clockwise: bool) -> char {
+    for cycle in GLYPH_ROTATIONS {
+        if let Some(i) = cycle.iter().position(|&c| c == ch) {
+            let step = if clockwise { 1 } else { cycle.len() - 1 };
+            return cycle[(i + step) % cycle.len()];
         }
     }
+    ch
 }
 
-impl std::error::Error for ConversionError {}
-
-/// Converte un blocco 2x4 pixel in un carattere Unicode Braille
-fn pixels_to_braille(block: &[u8]) -> char {
-    // Mappa dei punti Braille: [1, 2, 3, 7, 4, 5, 6, 8]
-    let mut code = 0x2800;
-    let mapping = [0, 1, 2, 6, 3, 4, 5, 7];
-    for (i, &px) in block.iter().enumerate() {
-        if px > 128 {
-            code |= 1 << mapping[i];
-        }
+/// Sostituisce caratteri di controllo e non ASCII che possono causare disallineamenti
+fn sanitize_char(ch: char) -> char {
+    if ch.is_control() || ch as u32 > 127 {
+        '?'
+    } else {
+        ch
     }
-    std::char::from_u32(code).unwrap_or(' ')
 }
 
-/// Converte un blocco 2x4 pixel in Braille con soglia personalizzabile
+/// Segni combinanti (larghezza zero) dei blocchi Unicode più comuni
+fn is_combining_mark(ch: char) -> bool {
+    matches!(ch as u32,
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F)
+}
+
+/// Contenuto di una cella per un grapheme cluster: carattere base sanitizzato e
+/// primo segno combinante, se presente. Gli altri caratteri del cluster
+/// (es. sequenze emoji ZWJ) vengono scartati per non rompere l'allineamento.
+fn grapheme_cell(grapheme: &str) -> (char, Option<char>) {
+    let mut chars = grapheme.chars();
+    let base = sanitize_char(chars.next().unwrap_or(' '));
+    (base, chars.find(|&ch| is_combining_mark(ch)))
+}
+
+/// Celle occupate da un grapheme una volta sanitizzato come fa `draw_text`
+fn grapheme_width(grapheme: &str) -> usize {
+    let (base, combining) = grapheme_cell(grapheme);
+    let mut cell = String::with_capacity(8);
+    cell.push(base);
+    cell.extend(combining);
+    cell.width()
+}
+
+/// Numero di celle che `draw_text` occuperebbe scrivendo `text` (tab sanitizzati,
+/// un grapheme per cella). Da usare al posto di `len()` per centrare o allineare testo.
+pub fn text_display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Prefisso più lungo di `text` che entra in `max` celle, senza spezzare grapheme
+pub fn truncate_to_width(text: &str, max: usize) -> &str {
+    let mut width = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        width += grapheme_width(grapheme);
+        if width > max {
+            return &text[..index];
+        }
+    }
+    text
+}
+
+/// Divide il testo in righe larghe al massimo `width` grapheme, andando a capo sugli spazi
+/// e spezzando le parole troppo lunghe. Le sequenze di spazi vengono compattate;
+/// i `\n` espliciti iniziano sempre una nuova riga.
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    if width == 0 || text.is_empty() {
+        return lines;
+    }
+    
+    for paragraph in text.split('\n') {
+        let words: Vec<&str> = paragraph.split_whitespace().collect();
+        let collapsed = words.join(" ");
+        let graphemes: Vec<&str> = collapsed.graphemes(true).collect();
+        // Gli spazi appesi al punto di a capo non servono a testo compattato
+        lines.extend(wrap_graphemes(&graphemes, width).map(|row| row.concat().trim_end().to_string()));
+    }
+    
+    lines
+}
+
+/// Come `wrap_text` (stessi punti di a capo), ma spazi e indentazione restano
+/// come sono: gli spazi al punto di a capo restano in coda alla riga, che può
+/// quindi superare `width` solo di spazi, e riunendo le righe di ogni paragrafo
+/// si riottiene il testo originale.
+pub fn wrap_text_preserving(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    if width == 0 || text.is_empty() {
+        return lines;
+    }
+    
+    for paragraph in text.split('\n') {
+        let graphemes: Vec<&str> = paragraph.graphemes(true).collect();
+        lines.extend(wrap_graphemes(&graphemes, width).map(<[&str]>::concat));
+    }
+    
+    lines
+}
+
+/// Righe di un paragrafo: al massimo `width` grapheme, spezzate dopo l'ultimo
+/// spazio che ci sta (o a forza dentro le parole troppo lunghe), con gli spazi
+/// al punto di a capo appesi alla riga. Un paragrafo vuoto dà una riga vuota.
+fn wrap_graphemes<'a>(graphemes: &'a [&'a str], width: usize) -> impl Iterator<Item = &'a [&'a str]> + 'a {
+    let is_space = |grapheme: &str| grapheme.chars().all(char::is_whitespace);
+    let mut start = 0;
+    let mut empty = graphemes.is_empty();
+    core::iter::from_fn(move || {
+        if core::mem::take(&mut empty) {
+            return Some(&graphemes[..0]);
+        }
+        if start >= graphemes.len() {
+            return None;
+        }
+        let mut end = (start + width).min(graphemes.len());
+        // A metà parola: a capo dopo l'ultimo spazio della riga, se c'è
+        if end < graphemes.len() && !is_space(graphemes[end]) {
+            if let Some(space) = graphemes[start..end].iter().rposition(|g| is_space(g)) {
+                end = start + space + 1;
+            }
+        }
+        while end < graphemes.len() && is_space(graphemes[end]) {
+            end += 1;
+        }
+        let row = &graphemes[start..end];
+        start = end;
+        Some(row)
+    })
+}
+
+/// Errori che possono verificarsi durante la conversione
+#[derive(Debug)]
+pub enum ConversionError {
+    InvalidDimensions,
+    ImageTooLarge,
+    /// Impossibile leggere o decodificare il file sorgente
+    DecodeFailed(String),
+}
+
+impl core::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ConversionError::InvalidDimensions => write!(f, "Dimensioni non valide"),
+            ConversionError::ImageTooLarge => write!(f, "Immagine troppo grande"),
+            ConversionError::DecodeFailed(reason) => write!(f, "Decodifica fallita: {}", reason),
+        }
+    }
+}
+
+impl core::error::Error for ConversionError {}
+
+/// Errore unificato della libreria: permette di usare `?` su rendering, input e
+/// conversione immagini con un solo tipo
+#[derive(Debug)]
+pub enum Error {
+    /// Errore di I/O del terminale (renderer, input)
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Errore di conversione di un'immagine
+    Conversion(ConversionError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => write!(f, "Errore di I/O: {}", err),
+            Error::Conversion(err) => write!(f, "Errore di conversione: {}", err),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => Some(err),
+            Error::Conversion(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<ConversionError> for Error {
+    fn from(err: ConversionError) -> Self {
+        Error::Conversion(err)
+    }
+}
+
+/// Risultato con l'errore della libreria come default
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// Mappatura dei punti Braille (standard Unicode): l'indice `i` corrisponde al punto
+/// `i + 1` e al bit `i` del code point (`0x2800 + bits`); il valore è la posizione
+/// (colonna, riga) del punto nella griglia 2x4 della cella.
+///
+/// ```text
+/// 1 4
+/// 2 5
+/// 3 6
+/// 7 8
+/// ```
+pub const BRAILLE_DOT_POSITIONS: [(usize, usize); 8] = [
+    (0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3),
+];
+
+/// Carattere Braille dai bit dei punti (bit `i` = punto `i + 1`, vedi
+/// `BRAILLE_DOT_POSITIONS`). Ogni valore a 8 bit è un glifo valido: chi calcola i
+/// bit con maschere più larghe deve passare solo il byte basso (`bits as u8`).
+pub const fn braille_char(dots: u8) -> char {
+    match core::char::from_u32(0x2800 | dots as u32) {
+        Some(ch) => ch,
+        // Irraggiungibile: l'intervallo 0x2800..=0x28FF è tutto valido
+        None => '\u{2800}',
+    }
+}
+
+/// Carattere Braille con i punti indicati accesi (`dots[i]` = punto `i + 1`,
+/// vedi `BRAILLE_DOT_POSITIONS`)
+pub fn braille_from_dots(dots: [bool; 8]) -> char {
+    let bits = dots.iter().enumerate()
+        .filter(|(_, &lit)| lit)
+        .fold(0u8, |bits, (i, _)| bits | (1 << i));
+    braille_char(bits)
+}
+
+/// Inverso di `braille_from_dots`: punti accesi di un carattere Braille
+/// (tutti spenti se il carattere non è Braille)
+pub fn braille_to_dots(ch: char) -> [bool; 8] {
+    let code = ch as u32;
+    let mut dots = [false; 8];
+    if (0x2800..=0x28FF).contains(&code) {
+        for (i, dot) in dots.iter_mut().enumerate() {
+            *dot = (code - 0x2800) & (1 << i) != 0;
+        }
+    }
+    dots
+}
+
+/// Bit del code point Braille per ogni punto, indicizzato per [riga][colonna]
+const BRAILLE_DOT_BITS: [[u8; 2]; 4] = [[0, 3], [1, 4], [2, 5], [6, 7]];
+
+#[cfg(feature = "std")]
+/// Riempie un rettangolo di un'immagine RGB con clipping ai bordi
+fn fill_image_rect(img: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    let end_x = (x + width).min(img.width());
+    let end_y = (y + height).min(img.height());
+    for py in y..end_y {
+        for px in x..end_x {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Converte un blocco 2x4 pixel (indice `x + y * 2`) in Braille con soglia personalizzabile
 fn pixels_to_braille_with_threshold(block: &[u8], threshold: u8) -> char {
-    let mut code = 0x2800;
-    let mapping = [0, 1, 2, 6, 3, 4, 5, 7];
-    for (i, &px) in block.iter().enumerate() {
-        if px > threshold {
-            code |= 1 << mapping[i];
+    let mut dots = [false; 8];
+    for (dot, &(x, y)) in dots.iter_mut().zip(BRAILLE_DOT_POSITIONS.iter()) {
+        *dot = block[x + y * 2] > threshold;
+    }
+    braille_from_dots(dots)
+}
+
+/// Impacchetta pixel in scala di grigi (riga per riga, `width * height` byte) in
+/// caratteri Braille (2x4 pixel per cella). Non richiede `std`: i pixel mancanti
+/// (slice troppo corta) valgono 0.
+pub fn pack_braille_luma(pixels: &[u8], width: usize, height: usize, threshold: u8) -> FrameBuffer {
+    let fb_w = width.div_ceil(2);
+    let fb_h = height.div_ceil(4);
+    let mut fb = FrameBuffer::new(fb_w, fb_h);
+
+    for by in 0..fb_h {
+        for bx in 0..fb_w {
+            let mut block = [0u8; 8];
+            for dy in 0..4 {
+                for dx in 0..2 {
+                    let (px, py) = (bx * 2 + dx, by * 4 + dy);
+                    if px < width && py < height {
+                        block[dx + dy * 2] = pixels.get(px + py * width).copied().unwrap_or(0);
+                    }
+                }
+            }
+            let ch = pixels_to_braille_with_threshold(&block, threshold);
+            fb.set(bx, by, ch);
+        }
+    }
+    fb
+}
+
+#[cfg(feature = "std")]
+/// Impacchetta un'immagine in scala di grigi in caratteri Braille (2x4 pixel per cella)
+fn pack_braille(img: &GrayImage, threshold: u8) -> FrameBuffer {
+    let (w, h) = img.dimensions();
+    pack_braille_luma(img.as_raw(), w as usize, h as usize, threshold)
+}
+
+#[cfg(feature = "std")]
+/// Magnitudo del gradiente di Sobel per ogni pixel (bordi replicati, saturata a 255)
+pub fn sobel_magnitude(img: &GrayImage) -> GrayImage {
+    let (w, h) = img.dimensions();
+    let mut out = GrayImage::new(w, h);
+    
+    let sample = |x: i64, y: i64| -> i32 {
+        let x = x.clamp(0, w as i64 - 1) as u32;
+        let y = y.clamp(0, h as i64 - 1) as u32;
+        img.get_pixel(x, y).0[0] as i32
+    };
+    
+    for y in 0..h as i64 {
+        for x in 0..w as i64 {
+            let gx = (sample(x + 1, y - 1) + 2 * sample(x + 1, y) + sample(x + 1, y + 1))
+                - (sample(x - 1, y - 1) + 2 * sample(x - 1, y) + sample(x - 1, y + 1));
+            let gy = (sample(x - 1, y + 1) + 2 * sample(x, y + 1) + sample(x + 1, y + 1))
+                - (sample(x - 1, y - 1) + 2 * sample(x, y - 1) + sample(x + 1, y - 1));
+            let magnitude = ((gx * gx + gy * gy) as f32).sqrt().min(255.0) as u8;
+            out.put_pixel(x as u32, y as u32, image::Luma([magnitude]));
+        }
+    }
+    
+    out
+}
+
+#[cfg(feature = "std")]
+/// Converte un'immagine in Braille accendendo solo i bordi (Sobel + isteresi):
+/// i pixel con gradiente >= `high` sono bordi certi, quelli >= `low` lo diventano
+/// solo se collegati (8-connessi) a un bordo certo.
+pub fn image_to_braille_edges(
+    img: &DynamicImage,
+    max_width: usize,
+    max_height: usize,
+    low: u8,
+    high: u8
+) -> Result<FrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let img = load_and_resize_image(img, (max_width * 2) as u32, (max_height * 4) as u32);
+    let magnitude = sobel_magnitude(&img);
+    let (w, h) = magnitude.dimensions();
+    let high = high.max(low);
+    
+    // Isteresi: propaga dai bordi certi verso i pixel deboli adiacenti
+    let mut edges = GrayImage::new(w, h);
+    let mut stack: Vec<(u32, u32)> = magnitude
+        .enumerate_pixels()
+        .filter(|(_, _, p)| p.0[0] >= high)
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    
+    while let Some((x, y)) = stack.pop() {
+        if edges.get_pixel(x, y).0[0] != 0 {
+            continue;
+        }
+        edges.put_pixel(x, y, image::Luma([255]));
+        
+        for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                if edges.get_pixel(nx, ny).0[0] == 0 && magnitude.get_pixel(nx, ny).0[0] >= low {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+    
+    Ok(pack_braille(&edges, 128))
+}
+
+#[cfg(feature = "std")]
+/// Ridimensiona e converte un'immagine in scala di grigi
+fn load_and_resize_image(img: &DynamicImage, max_width: u32, max_height: u32) -> GrayImage {
+    let img = img.to_luma8();
+    let (w, h) = img.dimensions();
+    
+    if w == 0 || h == 0 {
+        return GrayImage::new(1, 1);
+    }
+    
+    let (new_w, new_h) = scaled_dimensions(w, h, max_width, max_height);
+    image::imageops::resize(&img, new_w, new_h, image::imageops::FilterType::Triangle)
+}
+
+#[cfg(feature = "std")]
+/// Ridimensiona un'immagine mantenendo i colori RGB
+fn load_and_resize_rgb_image(img: &DynamicImage, max_width: u32, max_height: u32) -> RgbImage {
+    let img = img.to_rgb8();
+    let (w, h) = img.dimensions();
+    
+    if w == 0 || h == 0 {
+        return RgbImage::new(1, 1);
+    }
+    
+    let (new_w, new_h) = scaled_dimensions(w, h, max_width, max_height);
+    image::imageops::resize(&img, new_w, new_h, image::imageops::FilterType::Triangle)
+}
+
+#[cfg(feature = "std")]
+/// Dimensioni scalate mantenendo le proporzioni, senza mai ingrandire
+fn scaled_dimensions(w: u32, h: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    let scale_x = max_width as f32 / w as f32;
+    let scale_y = max_height as f32 / h as f32;
+    let scale = scale_x.min(scale_y).min(1.0);
+    let new_w = ((w as f32 * scale) as u32).max(1);
+    let new_h = ((h as f32 * scale) as u32).max(1);
+    (new_w, new_h)
+}
+
+#[cfg(feature = "std")]
+/// Converte un'immagine in un framebuffer Braille
+pub fn image_to_braille_fb(img: &DynamicImage, max_width: usize, max_height: usize) -> Result<FrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    // Ogni carattere Braille rappresenta 2x4 pixel
+    let img = load_and_resize_image(img, (max_width * 2) as u32, (max_height * 4) as u32);
+    Ok(pack_braille(&img, 128))
+}
+
+#[cfg(feature = "std")]
+/// Come `image_to_braille_fb`, per un'immagine già alla risoluzione finale
+/// (`2 * colonne` x `4 * righe` pixel): niente ridimensionamento né copie, i pixel
+/// vengono solo impacchettati. Dimensioni non multiple di 2x4 completano l'ultima
+/// cella con punti spenti.
+pub fn braille_fb_from_luma_exact(img: &GrayImage) -> FrameBuffer {
+    pack_braille(img, 128)
+}
+
+#[cfg(feature = "std")]
+/// Converte un'immagine in framebuffer Braille con soglia personalizzabile
+pub fn image_to_braille_fb_with_threshold(
+    img: &DynamicImage, 
+    max_width: usize, 
+    max_height: usize,
+    threshold: u8
+) -> Result<FrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let img = load_and_resize_image(img, (max_width * 2) as u32, (max_height * 4) as u32);
+    Ok(pack_braille(&img, threshold))
+}
+
+#[cfg(feature = "std")]
+/// Converte un'immagine in un framebuffer Braille di esattamente `cols` x `rows`
+/// celle: l'immagine, scalata mantenendo le proporzioni, è centrata e le celle
+/// rimanenti restano spazi (letterbox). Con `cols` o `rows` a 0 il buffer è vuoto.
+pub fn image_to_braille_fb_boxed(img: &DynamicImage, cols: usize, rows: usize, threshold: u8) -> FrameBuffer {
+    let mut boxed = FrameBuffer::new(cols, rows);
+    if cols == 0 || rows == 0 {
+        return boxed;
+    }
+    
+    let gray = load_and_resize_image(img, (cols * 2) as u32, (rows * 4) as u32);
+    let fb = pack_braille(&gray, threshold);
+    let x = cols.saturating_sub(fb.width) / 2;
+    let y = rows.saturating_sub(fb.height) / 2;
+    boxed.blit(&fb, 0, 0, x, y, fb.width, fb.height);
+    boxed
+}
+
+#[cfg(feature = "std")]
+/// Come `image_to_braille_fb_with_threshold`, ma parte da un buffer luma grezzo
+/// (`src_w * src_h` byte, riga per riga) senza passare da `DynamicImage`:
+/// il ricampionamento legge direttamente `pixels`.
+pub fn luma_to_braille_fb(
+    pixels: &[u8],
+    src_w: usize,
+    src_h: usize,
+    max_width: usize,
+    max_height: usize,
+    threshold: u8
+) -> Result<FrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 || src_w == 0 || src_h == 0 || src_w.checked_mul(src_h) != Some(pixels.len()) {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    let (w, h) = (
+        u32::try_from(src_w).map_err(|_| ConversionError::ImageTooLarge)?,
+        u32::try_from(src_h).map_err(|_| ConversionError::ImageTooLarge)?,
+    );
+    // Limiti in pixel saturati: oltre u32::MAX nessuna immagine va ridotta
+    let max_w = u32::try_from(max_width.saturating_mul(2)).unwrap_or(u32::MAX);
+    let max_h = u32::try_from(max_height.saturating_mul(4)).unwrap_or(u32::MAX);
+    
+    let (new_w, new_h) = scaled_dimensions(w, h, max_w, max_h);
+    if (new_w, new_h) == (w, h) {
+        // Già della misura giusta: i pixel vengono letti senza copie
+        return Ok(pack_braille_luma(pixels, src_w, src_h, threshold));
+    }
+    let src = image::ImageBuffer::<image::Luma<u8>, &[u8]>::from_raw(w, h, pixels)
+        .ok_or(ConversionError::InvalidDimensions)?;
+    let gray = image::imageops::resize(&src, new_w, new_h, image::imageops::FilterType::Triangle);
+    Ok(pack_braille(&gray, threshold))
+}
+
+#[cfg(feature = "std")]
+/// Come `image_to_braille_fb_with_threshold`, ma la scala di grigi usa i pesi RGB
+/// indicati invece di quelli Rec.601 (es. `(1.0, 0.0, 0.0)` per isolare un soggetto rosso).
+/// I pesi negativi valgono 0 e vengono normalizzati a somma 1; se sono tutti nulli
+/// si usano quelli standard.
+pub fn image_to_braille_fb_weighted(
+    img: &DynamicImage,
+    max_width: usize,
+    max_height: usize,
+    weights: (f32, f32, f32),
+    threshold: u8
+) -> Result<FrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let weights = [weights.0, weights.1, weights.2].map(|w| if w.is_finite() { w.max(0.0) } else { 0.0 });
+    let total: f32 = weights.iter().sum();
+    let weights = if total > 0.0 { weights.map(|w| w / total) } else { [0.299, 0.587, 0.114] };
+    
+    let rgb = img.to_rgb8();
+    let gray = GrayImage::from_fn(rgb.width(), rgb.height(), |x, y| {
+        let [r, g, b] = rgb.get_pixel(x, y).0;
+        let luma = r as f32 * weights[0] + g as f32 * weights[1] + b as f32 * weights[2];
+        image::Luma([luma.round().clamp(0.0, 255.0) as u8])
+    });
+    
+    let gray = load_and_resize_image(&DynamicImage::ImageLuma8(gray), (max_width * 2) as u32, (max_height * 4) as u32);
+    Ok(pack_braille(&gray, threshold))
+}
+
+#[cfg(feature = "std")]
+/// Riconverte solo le celle Braille di `prev` che si sovrappongono a `changed`
+/// (in pixel dell'immagine), ad es. per un flusso video dove cambia solo una parte.
+/// L'immagine non viene ridimensionata: deve misurare `prev.width * 2` x `prev.height * 4`
+/// pixel (una cella = 2x4 pixel, soglia 128 come `image_to_braille_fb`).
+/// Restituisce l'area aggiornata, in celle.
+pub fn update_braille_fb(prev: &mut FrameBuffer, new_img: &DynamicImage, changed: Rect) -> Result<Rect, ConversionError> {
+    let (w, h) = (new_img.width() as usize, new_img.height() as usize);
+    if w.div_ceil(2) != prev.width || h.div_ceil(4) != prev.height {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    // Allinea la regione alla griglia delle celle
+    let Some(changed) = changed.intersection(&Rect::new(0, 0, w, h)) else {
+        return Ok(Rect::new(0, 0, 0, 0));
+    };
+    let cells = Rect::new(
+        changed.x / 2,
+        changed.y / 4,
+        (changed.x + changed.width).div_ceil(2) - changed.x / 2,
+        (changed.y + changed.height).div_ceil(4) - changed.y / 4,
+    );
+    
+    let px = Rect::new(cells.x * 2, cells.y * 4, cells.width * 2, cells.height * 4);
+    let crop = new_img
+        .crop_imm(px.x as u32, px.y as u32, px.width.min(w - px.x) as u32, px.height.min(h - px.y) as u32)
+        .to_luma8();
+    let patch = pack_braille(&crop, 128);
+    prev.blit(&patch, 0, 0, cells.x, cells.y, cells.width, cells.height);
+    Ok(cells)
+}
+
+/// Rampa di luminosità predefinita per `image_to_ascii_fb`, dal più scuro al più chiaro
+pub const DEFAULT_ASCII_RAMP: &str = " .:-=+*#%@";
+
+#[cfg(feature = "std")]
+/// Converte un'immagine in ASCII art: ogni cella è un pixel, mappato sulla rampa
+/// in base alla luminosità. Con `invert` la rampa viene percorsa al contrario
+/// (utile su sfondi chiari). Una rampa vuota usa `DEFAULT_ASCII_RAMP`.
+pub fn image_to_ascii_fb(
+    img: &DynamicImage,
+    max_width: usize,
+    max_height: usize,
+    ramp: &str,
+    invert: bool
+) -> Result<FrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let mut ramp: Vec<char> = if ramp.is_empty() { DEFAULT_ASCII_RAMP } else { ramp }.chars().collect();
+    if invert {
+        ramp.reverse();
+    }
+    
+    let img = load_and_resize_image(img, max_width as u32, max_height as u32);
+    let (w, h) = img.dimensions();
+    let mut fb = FrameBuffer::new(w as usize, h as usize);
+    
+    for (x, y, pixel) in img.enumerate_pixels() {
+        let index = (pixel.0[0] as usize * (ramp.len() - 1) + 127) / 255;
+        fb.set(x as usize, y as usize, ramp[index]);
+    }
+    
+    Ok(fb)
+}
+
+/// Blocchi a quadranti indicizzati per bit: 1 = alto-sinistra, 2 = alto-destra,
+/// 4 = basso-sinistra, 8 = basso-destra
+pub const QUADRANT_CHARS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+#[cfg(feature = "std")]
+/// Come `image_to_braille_fb` ma con i blocchi a quadranti (2x2 pixel per cella):
+/// risoluzione minore, ma glifi supportati da molti più font rispetto al Braille
+pub fn image_to_quadrant_fb(img: &DynamicImage, max_width: usize, max_height: usize) -> Result<FrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let img = load_and_resize_image(img, (max_width * 2) as u32, (max_height * 2) as u32);
+    let (w, h) = img.dimensions();
+    let mut fb = FrameBuffer::new((w as usize).div_ceil(2), (h as usize).div_ceil(2));
+    let mut masks = vec![0usize; fb.width * fb.height];
+    
+    for (x, y, pixel) in img.enumerate_pixels() {
+        if pixel.0[0] >= 128 {
+            let cell = (y / 2) as usize * fb.width + (x / 2) as usize;
+            masks[cell] |= 1 << ((x % 2) + (y % 2) * 2);
+        }
+    }
+    for (ch, mask) in fb.data.iter_mut().zip(masks) {
+        *ch = QUADRANT_CHARS[mask];
+    }
+    
+    Ok(fb)
+}
+
+/// Opzioni per la conversione Braille a colori
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrailleOptions {
+    /// Luminanza oltre la quale un punto viene acceso
+    pub threshold: u8,
+    /// Accende i punti scuri invece di quelli chiari
+    pub invert: bool,
+}
+
+impl Default for BrailleOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 128,
+            invert: false,
         }
     }
-    std::char::from_u32(code).unwrap_or(' ')
-}
+}
+
+#[cfg(feature = "std")]
+/// Converte un'immagine in Braille a colori: i punti derivano dalla luminanza con soglia,
+/// il colore di primo piano è la media RGB dei pixel accesi del blocco.
+/// Le celle senza punti accesi restano spazi senza colore.
+pub fn image_to_braille_colored_opts(
+    img: &DynamicImage,
+    max_width: usize,
+    max_height: usize,
+    options: BrailleOptions
+) -> Result<StyledFrameBuffer, ConversionError> {
+    use image::Pixel;
+    
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let img = load_and_resize_rgb_image(img, (max_width * 2) as u32, (max_height * 4) as u32);
+    let (w, h) = img.dimensions();
+    let fb_w = (w as usize).div_ceil(2);
+    let fb_h = (h as usize).div_ceil(4);
+    let mut fb = StyledFrameBuffer::new(fb_w, fb_h);
+
+    for by in 0..fb_h {
+        for bx in 0..fb_w {
+            let mut block = [0u8; 8];
+            let mut sum = [0u32; 3];
+            let mut lit = 0u32;
+            
+            for dy in 0..4 {
+                for dx in 0..2 {
+                    let (px, py) = (bx * 2 + dx, by * 4 + dy);
+                    if px >= w as usize || py >= h as usize {
+                        continue;
+                    }
+                    
+                    let pixel = img.get_pixel(px as u32, py as u32);
+                    let luma = pixel.to_luma().0[0];
+                    let value = if options.invert { 255 - luma } else { luma };
+                    block[dx + dy * 2] = value;
+                    
+                    if value > options.threshold {
+                        for (acc, &channel) in sum.iter_mut().zip(pixel.0.iter()) {
+                            *acc += channel as u32;
+                        }
+                        lit += 1;
+                    }
+                }
+            }
+            
+            // Nessun punto acceso: la cella resta uno spazio senza colore
+            if lit == 0 {
+                continue;
+            }
+            
+            let ch = pixels_to_braille_with_threshold(&block, options.threshold);
+            let color = Color::Rgb((sum[0] / lit) as u8, (sum[1] / lit) as u8, (sum[2] / lit) as u8);
+            fb.set(bx, by, StyledChar::new(ch).with_fg(color));
+        }
+    }
+    Ok(fb)
+}
+
+#[cfg(feature = "std")]
+/// Braille in primo piano su uno sfondo a colori in un solo passaggio: i punti
+/// derivano da `fg_img` con soglia (es. una maschera di sottotitoli o un logo) e
+/// sono bianchi, mentre il `bg_color` di ogni cella, anche senza punti, è il colore
+/// di `bg_img` ricampionato sulla griglia di celle del risultato.
+pub fn image_to_braille_over(
+    fg_img: &DynamicImage,
+    bg_img: &DynamicImage,
+    max_width: usize,
+    max_height: usize,
+    threshold: u8
+) -> Result<StyledFrameBuffer, ConversionError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    if bg_img.width() == 0 || bg_img.height() == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let mask = load_and_resize_image(fg_img, (max_width * 2) as u32, (max_height * 4) as u32);
+    let dots = pack_braille(&mask, threshold);
+    // Un pixel di sfondo per cella
+    let background = image::imageops::resize(
+        &bg_img.to_rgb8(),
+        dots.width as u32,
+        dots.height as u32,
+        image::imageops::FilterType::Triangle,
+    );
+    
+    let mut fb = StyledFrameBuffer::new(dots.width, dots.height);
+    for y in 0..dots.height {
+        for x in 0..dots.width {
+            let [r, g, b] = background.get_pixel(x as u32, y as u32).0;
+            fb.set(x, y, StyledChar::styled(dots.get(x, y), Color::White, Color::Rgb(r, g, b)));
+        }
+    }
+    Ok(fb)
+}
+
+#[cfg(feature = "std")]
+/// Carica una GIF animata convertendo ogni frame in Braille, insieme al ritardo del frame
+pub fn load_gif_frames(path: &str, max_width: usize, max_height: usize) -> Result<Vec<(FrameBuffer, Duration)>, ConversionError> {
+    use image::AnimationDecoder;
+    
+    if max_width == 0 || max_height == 0 {
+        return Err(ConversionError::InvalidDimensions);
+    }
+    
+    let file = std::fs::File::open(path)
+        .map_err(|e| ConversionError::DecodeFailed(e.to_string()))?;
+    let decoder = image::codecs::gif::GifDecoder::new(std::io::BufReader::new(file))
+        .map_err(|e| ConversionError::DecodeFailed(e.to_string()))?;
+    
+    let mut frames = Vec::new();
+    for frame in decoder.into_frames() {
+        let frame = frame.map_err(|e| ConversionError::DecodeFailed(e.to_string()))?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay = Duration::from_micros(numer as u64 * 1000 / denom.max(1) as u64);
+        
+        let img = DynamicImage::ImageRgba8(frame.into_buffer());
+        frames.push((image_to_braille_fb(&img, max_width, max_height)?, delay));
+    }
+    
+    Ok(frames)
+}
+
+/// Tela Braille indirizzabile a livello di singolo punto (2x4 punti per cella)
+#[derive(Debug, Clone)]
+pub struct BrailleCanvas {
+    /// Larghezza in punti
+    pub width: usize,
+    /// Altezza in punti
+    pub height: usize,
+    cells: Vec<u8>,
+}
+
+impl BrailleCanvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width.div_ceil(2) * height.div_ceil(4)],
+        }
+    }
+
+    /// Dimensioni in celle del FrameBuffer risultante
+    pub fn cell_size(&self) -> (usize, usize) {
+        (self.width.div_ceil(2), self.height.div_ceil(4))
+    }
+
+    fn locate(&self, x: usize, y: usize) -> Option<(usize, u8)> {
+        if x < self.width && y < self.height {
+            let index = (y / 4) * self.width.div_ceil(2) + x / 2;
+            Some((index, 1 << BRAILLE_DOT_BITS[y % 4][x % 2]))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_dot(&mut self, x: usize, y: usize) {
+        if let Some((index, mask)) = self.locate(x, y) {
+            self.cells[index] |= mask;
+        }
+    }
+
+    pub fn clear_dot(&mut self, x: usize, y: usize) {
+        if let Some((index, mask)) = self.locate(x, y) {
+            self.cells[index] &= !mask;
+        }
+    }
+
+    pub fn get_dot(&self, x: usize, y: usize) -> bool {
+        self.locate(x, y)
+            .is_some_and(|(index, mask)| self.cells[index] & mask != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(0);
+    }
+
+    /// Traccia un segmento con Bresenham; i punti fuori dalla tela vengono scartati
+    pub fn plot_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+        
+        loop {
+            if x >= 0 && y >= 0 {
+                self.set_dot(x as usize, y as usize);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += step_x;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Disegna una serie di punti collegati, scalata automaticamente per riempire
+    /// `bounds` (in punti). L'asse Y cresce verso l'alto come in un grafico.
+    pub fn plot_series(&mut self, points: &[(f64, f64)], bounds: Rect) {
+        if points.is_empty() || bounds.width == 0 || bounds.height == 0 {
+            return;
+        }
+        
+        let (min_x, max_x) = points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+        let (min_y, max_y) = points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+        
+        // Intervallo nullo: il punto finisce al centro dell'asse
+        let scale = |value: f64, min: f64, max: f64, span: usize| -> f64 {
+            if max > min { (value - min) / (max - min) * span as f64 } else { span as f64 / 2.0 }
+        };
+        
+        let to_dot = |&(px, py): &(f64, f64)| -> (i32, i32) {
+            let x = scale(px, min_x, max_x, bounds.width - 1).round() as i32;
+            let y = scale(py, min_y, max_y, bounds.height - 1).round() as i32;
+            (bounds.x as i32 + x, bounds.y as i32 + (bounds.height - 1) as i32 - y)
+        };
+        
+        let mut previous = to_dot(&points[0]);
+        self.plot_line(previous.0, previous.1, previous.0, previous.1);
+        for point in &points[1..] {
+            let current = to_dot(point);
+            self.plot_line(previous.0, previous.1, current.0, current.1);
+            previous = current;
+        }
+    }
+
+    /// Impacchetta la griglia di punti in caratteri Braille
+    pub fn to_framebuffer(&self) -> FrameBuffer {
+        let (fb_w, fb_h) = self.cell_size();
+        let mut fb = FrameBuffer::new(fb_w, fb_h);
+        for (i, &bits) in self.cells.iter().enumerate() {
+            fb.set(i % fb_w, i / fb_w, braille_char(bits));
+        }
+        fb
+    }
+}
+
+#[cfg(feature = "std")]
+/// Strategia di attesa del `FrameTimer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// Solo `thread::sleep`: nessun consumo di CPU, ma l'OS può sforare di qualche ms
+    #[default]
+    PowerSave,
+    /// Sleep per quasi tutta l'attesa, poi spin-wait sull'ultimo millisecondo
+    HighPrecision,
+}
+
+#[cfg(feature = "std")]
+/// Margine finale gestito con spin-wait in `Precision::HighPrecision`
+const SPIN_MARGIN: Duration = Duration::from_millis(1);
+
+#[cfg(feature = "std")]
+/// Sistema di gestione frame rate semplificato
+pub struct FrameTimer {
+    target_fps: u32,
+    frame_duration: Duration,
+    last_frame: Instant,
+    next_frame: Instant,
+    frame_count: u64,
+    skip_if_behind: bool,
+    dropped_frames: u64,
+    frame_times: VecDeque<Duration>,
+    precision: Precision,
+}
+
+#[cfg(feature = "std")]
+/// Numero di frame usati per la media mobile degli FPS
+const FPS_SAMPLE_WINDOW: usize = 60;
+
+#[cfg(feature = "std")]
+impl FrameTimer {
+    pub fn new(target_fps: u32) -> Self {
+        let target_fps = target_fps.clamp(1, 120); // Clamp tra 1 e 120 FPS
+        let frame_duration = Duration::from_nanos(1_000_000_000 / target_fps as u64);
+        let now = Instant::now();
+        Self {
+            target_fps,
+            frame_duration,
+            last_frame: now,
+            next_frame: now + frame_duration,
+            frame_count: 0,
+            skip_if_behind: false,
+            dropped_frames: 0,
+            frame_times: VecDeque::with_capacity(FPS_SAMPLE_WINDOW),
+            precision: Precision::default(),
+        }
+    }
+
+    pub fn wait_for_next_frame(&mut self) {
+        self.sleep_until(self.last_frame + self.frame_duration);
+        
+        let now = Instant::now();
+        self.record_frame(now - self.last_frame);
+        self.last_frame = now;
+        self.next_frame = self.last_frame + self.frame_duration;
+        self.frame_count += 1;
+    }
+
+    /// Attende il prossimo tick a passo fisso e restituisce quanti frame logici
+    /// sono trascorsi (>= 1). Se il frame precedente è durato troppo, il valore
+    /// include i frame da recuperare, a meno che `skip_if_behind` sia attivo:
+    /// in quel caso i frame persi vengono scartati e la pianificazione riparte da ora.
+    pub fn wait_for_next_tick(&mut self) -> u32 {
+        self.sleep_until(self.next_frame);
+        
+        let now = Instant::now();
+        let behind = now.saturating_duration_since(self.next_frame);
+        let missed = (behind.as_nanos() / self.frame_duration.as_nanos()) as u64;
+        
+        let frames = if self.skip_if_behind && missed > 0 {
+            self.dropped_frames += missed;
+            self.next_frame = now + self.frame_duration;
+            1
+        } else {
+            self.next_frame += self.frame_duration * (missed as u32 + 1);
+            missed as u32 + 1
+        };
+        
+        self.record_frame(now - self.last_frame);
+        self.last_frame = now;
+        self.frame_count += 1;
+        frames
+    }
+
+    /// Cambia il frame rate a runtime (stesso clamp di `new`)
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.target_fps = fps.clamp(1, 120);
+        self.frame_duration = Duration::from_nanos(1_000_000_000 / self.target_fps as u64);
+        self.next_frame = self.last_frame + self.frame_duration;
+    }
+
+    /// Sceglie la strategia di attesa di `wait_for_next_frame` e `wait_for_next_tick`
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Attende fino a `deadline` secondo la `Precision` scelta
+    fn sleep_until(&self, deadline: Instant) {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+        match self.precision {
+            Precision::PowerSave => std::thread::sleep(deadline - now),
+            Precision::HighPrecision => {
+                let remaining = deadline - now;
+                if remaining > SPIN_MARGIN {
+                    std::thread::sleep(remaining - SPIN_MARGIN);
+                }
+                while Instant::now() < deadline {
+                    std::hint::spin_loop();
+                }
+            }
+        }
+    }
+
+    /// Se attivo, `wait_for_next_tick` non accumula ritardo: i frame persi vengono scartati
+    pub fn set_skip_if_behind(&mut self, skip: bool) {
+        self.skip_if_behind = skip;
+    }
+
+    pub fn skip_if_behind(&self) -> bool {
+        self.skip_if_behind
+    }
+
+    /// Numero totale di frame logici scartati con `skip_if_behind`
+    pub fn get_dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    fn record_frame(&mut self, duration: Duration) {
+        if self.frame_times.len() == FPS_SAMPLE_WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(duration);
+    }
+
+    /// FPS reali come media mobile sugli ultimi frame (0 se non ci sono campioni)
+    pub fn get_fps(&self) -> f32 {
+        let total: Duration = self.frame_times.iter().sum();
+        if self.frame_times.is_empty() || total.is_zero() {
+            return 0.0;
+        }
+        self.frame_times.len() as f32 / total.as_secs_f32()
+    }
+
+    pub fn get_target_fps(&self) -> u32 {
+        self.target_fps
+    }
+
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+}
+
+// Global buffer pools for memory reuse
+#[cfg(feature = "std")]
+static BUFFER_POOL: Lazy<Mutex<Vec<Vec<char>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+#[cfg(feature = "std")]
+static STYLED_BUFFER_POOL: Lazy<Mutex<Vec<Vec<StyledChar>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// Limiti dei pool, modificabili a runtime con `set_pool_limits`
+#[cfg(feature = "std")]
+static POOL_MAX_BUFFERS: AtomicUsize = AtomicUsize::new(16);
+#[cfg(feature = "std")]
+static POOL_MAX_CAPACITY: AtomicUsize = AtomicUsize::new(1024 * 1024);
+
+#[cfg(feature = "std")]
+fn pool_max_buffers() -> usize {
+    POOL_MAX_BUFFERS.load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "std")]
+fn pool_max_capacity() -> usize {
+    POOL_MAX_CAPACITY.load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "std")]
+/// Imposta il numero massimo di buffer conservati per ciascun pool e la capacità
+/// massima (in celle) di un buffer riciclabile. `max_buffers == 0` disabilita il pooling.
+/// I buffer già in eccesso rispetto ai nuovi limiti vengono rilasciati.
+pub fn set_pool_limits(max_buffers: usize, max_capacity: usize) {
+    POOL_MAX_BUFFERS.store(max_buffers, Ordering::Relaxed);
+    POOL_MAX_CAPACITY.store(max_capacity, Ordering::Relaxed);
+    
+    let mut pool = BUFFER_POOL.lock();
+    pool.retain(|buffer| buffer.capacity() <= max_capacity);
+    pool.truncate(max_buffers);
+    drop(pool);
+    
+    let mut styled_pool = STYLED_BUFFER_POOL.lock();
+    styled_pool.retain(|buffer| buffer.capacity() <= max_capacity);
+    styled_pool.truncate(max_buffers);
+}
+
+#[cfg(feature = "std")]
+/// Svuota entrambi i pool liberando la memoria
+pub fn clear_pools() {
+    BUFFER_POOL.lock().clear();
+    STYLED_BUFFER_POOL.lock().clear();
+}
+
+#[cfg(feature = "std")]
+/// StyledFrameBuffer preso dal pool che vi ritorna automaticamente quando esce
+/// dallo scope. Si usa come un normale buffer grazie a `Deref`.
+pub struct PooledBuffer {
+    buffer: Option<StyledFrameBuffer>,
+}
+
+#[cfg(feature = "std")]
+impl PooledBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            buffer: Some(StyledFrameBuffer::new_pooled(width, height)),
+        }
+    }
+
+    /// Estrae il buffer, che non verrà più riciclato automaticamente
+    pub fn into_inner(mut self) -> StyledFrameBuffer {
+        self.buffer.take().expect("buffer presente fino al drop")
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::ops::Deref for PooledBuffer {
+    type Target = StyledFrameBuffer;
+
+    fn deref(&self) -> &StyledFrameBuffer {
+        self.buffer.as_ref().expect("buffer presente fino al drop")
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::ops::DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut StyledFrameBuffer {
+        self.buffer.as_mut().expect("buffer presente fino al drop")
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            buffer.release_to_pool();
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Statistiche dei pool: (buffer conservati, capacità totale in celle), sommando entrambi i pool
+pub fn pool_stats() -> (usize, usize) {
+    let pool = BUFFER_POOL.lock();
+    let styled_pool = STYLED_BUFFER_POOL.lock();
+    let buffers = pool.len() + styled_pool.len();
+    let capacity = pool.iter().map(|b| b.capacity()).sum::<usize>()
+        + styled_pool.iter().map(|b| b.capacity()).sum::<usize>();
+    (buffers, capacity)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use image::DynamicImage;
+
+    #[test]
+    fn test_framebuffer_set_get() {
+        let mut fb = FrameBuffer::new(4, 2);
+        fb.set(1, 1, 'A');
+        assert_eq!(fb.get(1, 1), 'A');
+        assert_eq!(fb.get(0, 0), ' ');
+    }
+
+    #[test]
+    fn test_framebuffer_clear() {
+        let mut fb = FrameBuffer::new(2, 2);
+        fb.set(0, 0, 'X');
+        fb.clear();
+        assert_eq!(fb.get(0, 0), ' ');
+    }
+
+    #[test]
+    fn test_framebuffer_to_string() {
+        let mut fb = FrameBuffer::new(2, 2);
+        fb.set(0, 0, 'A');
+        fb.set(1, 1, 'B');
+        let result = fb.to_string();
+        assert_eq!(result, "A \n B");
+    }
+
+    #[test]
+    fn test_image_to_braille_fb() {
+        let img = DynamicImage::new_luma8(4, 8);
+        let fb = image_to_braille_fb(&img, 2, 2).unwrap();
+        assert_eq!(fb.width, 2);
+        assert_eq!(fb.height, 2);
+    }
+
+    #[test]
+    fn test_invalid_dimensions() {
+        let img = DynamicImage::new_luma8(4, 8);
+        assert!(image_to_braille_fb(&img, 0, 2).is_err());
+    }
+
+    #[test]
+    fn test_blit() {
+        let mut src = FrameBuffer::new(2, 2);
+        src.set(0, 0, 'A');
+        src.set(1, 1, 'B');
+        
+        let mut dst = FrameBuffer::new(4, 4);
+        dst.blit(&src, 0, 0, 1, 1, 2, 2);
+        
+        assert_eq!(dst.get(1, 1), 'A');
+        assert_eq!(dst.get(2, 2), 'B');
+    }
+
+    #[test]
+    fn test_styled_char() {
+        let styled = StyledChar::new('A').with_fg(Color::Red).with_bg(Color::Blue);
+        let output = styled.to_string();
+        assert!(output.contains('A'));
+        assert!(output.contains("\x1b[31m")); // Red foreground
+        assert!(output.contains("\x1b[44m")); // Blue background
+    }
+
+    #[test]
+    fn test_rect_contains() {
+        let rect = Rect::new(5, 5, 10, 10);
+        assert!(rect.contains(10, 10));
+        assert!(!rect.contains(0, 0));
+        assert!(!rect.contains(20, 20));
+    }
+
+    #[test]
+    fn test_styled_framebuffer() {
+        let mut fb = StyledFrameBuffer::new(10, 10);
+        let styled_char = StyledChar::new('X').with_fg(Color::Red);
+        fb.set(5, 5, styled_char);
+        assert_eq!(fb.get(5, 5).ch, 'X');
+        assert_eq!(fb.get(5, 5).fg_color, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_fill_gradient() {
+        let mut fb = StyledFrameBuffer::new(5, 2);
+        fb.set(2, 0, StyledChar::new('X').with_fg(Color::Red));
+        fb.fill_gradient(Rect::new(0, 0, 5, 2), Color::Rgb(0, 0, 0), Color::Rgb(200, 100, 0), GradientDirection::Horizontal);
+        assert_eq!(fb.get(0, 1).bg_color, Some(Color::Rgb(0, 0, 0)));
+        // Interpolazione in luce lineare
+        assert_eq!(fb.get(2, 0).bg_color, Some(Color::Rgb(146, 71, 0)));
+        assert_eq!(fb.get(4, 1).bg_color, Some(Color::Rgb(200, 100, 0)));
+        // Carattere e colore di primo piano restano invariati
+        assert_eq!(fb.get(2, 0).ch, 'X');
+        assert_eq!(fb.get(2, 0).fg_color, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_from_rgb_nearest() {
+        assert_eq!(Color::from_rgb_nearest(0, 0, 0), Color::Black);
+        assert_eq!(Color::from_rgb_nearest(250, 10, 10), Color::BrightRed);
+        assert_eq!(Color::from_rgb_nearest(190, 20, 10), Color::Red);
+        assert_eq!(Color::from_rgb_nearest(120, 130, 125), Color::Gray);
+        
+        // Ogni voce della palette mappa su se stessa
+        for &(color, (r, g, b)) in ANSI_PALETTE.iter() {
+            assert_eq!(Color::from_rgb_nearest(r, g, b), color);
+        }
+    }
+
+    #[test]
+    fn test_to_html() {
+        let mut fb = StyledFrameBuffer::new(4, 2);
+        fb.draw_text(0, 0, "<a", Some(Color::Red), None);
+        fb.set(2, 0, StyledChar::new('&').with_bg(Color::Rgb(1, 2, 3)));
+        let html = fb.to_html();
+        assert_eq!(
+            html,
+            "<pre><span style=\"color:#cd0000\">&lt;a</span><span style=\"background:#010203\">&amp;</span> \n    </pre>"
+        );
+    }
+
+    #[test]
+    fn test_to_image() {
+        let mut fb = StyledFrameBuffer::new(2, 1);
+        fb.set(0, 0, StyledChar::new(' ').with_bg(Color::Rgb(10, 20, 30)));
+        fb.set(1, 0, StyledChar::new('\u{2801}').with_fg(Color::Rgb(255, 0, 0)));
+        
+        let thumb = fb.to_image(1, 1).unwrap();
+        assert_eq!(thumb.dimensions(), (2, 1));
+        assert_eq!(thumb.get_pixel(0, 0).0, [10, 20, 30]);
+        
+        // Il punto 1 del Braille occupa il quarto in alto a sinistra della cella
+        let img = fb.to_image(2, 4).unwrap();
+        assert_eq!(img.dimensions(), (4, 4));
+        assert_eq!(img.get_pixel(2, 0).0, [255, 0, 0]);
+        assert_eq!(img.get_pixel(3, 0).0, [0, 0, 0]);
+        assert_eq!(img.get_pixel(2, 1).0, [0, 0, 0]);
+        
+        // Dimensioni in pixel oltre u32: errore invece di overflow
+        assert!(matches!(fb.to_image(u32::MAX, 1), Err(ConversionError::ImageTooLarge)));
+        assert!(matches!(StyledFrameBuffer::new(1, 3).to_image(1, u32::MAX / 2), Err(ConversionError::ImageTooLarge)));
+    }
+
+    #[test]
+    fn test_load_gif_frames() {
+        use image::{codecs::gif::GifEncoder, Delay, Frame, RgbaImage};
+        
+        let path = std::env::temp_dir().join(format!("stg_test_{}.gif", std::process::id()));
+        {
+            let file = std::fs::File::create(&path).unwrap();
+            let mut encoder = GifEncoder::new(file);
+            let frames = [0u8, 255].iter().map(|&v| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(4, 8, image::Rgba([v, v, v, 255])),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(50, 1),
+                )
+            });
+            encoder.encode_frames(frames).unwrap();
+        }
+        
+        let frames = load_gif_frames(path.to_str().unwrap(), 2, 2).unwrap();
+        std::fs::remove_file(&path).ok();
+        
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].1, Duration::from_millis(50));
+        assert_eq!(frames[0].0.get(0, 0), '\u{2800}');
+        assert_eq!(frames[1].0.get(0, 0), '\u{28FF}');
+        assert!(load_gif_frames("/nonexistent.gif", 2, 2).is_err());
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(wrap_text("hello big world", 9), vec!["hello big", "world"]);
+        assert_eq!(wrap_text("abcdefgh ij", 3), vec!["abc", "def", "gh", "ij"]);
+        assert_eq!(wrap_text("a\n\nb", 5), vec!["a", "", "b"]);
+        // I grapheme composti non vengono mai spezzati
+        assert_eq!(wrap_text("e\u{301}e\u{301}e\u{301}", 2), vec!["e\u{301}e\u{301}", "e\u{301}"]);
+
+        // Parola lunga esattamente quanto la riga: nessuna riga vuota in coda
+        assert_eq!(wrap_text("abc def", 3), vec!["abc", "def"]);
+        assert_eq!(wrap_text("abc", 3), vec!["abc"]);
+        // Parola spezzata a forza su più righe piene
+        assert_eq!(wrap_text("abcdef", 3), vec!["abc", "def"]);
+        assert_eq!(wrap_text("abcdefg", 3), vec!["abc", "def", "g"]);
+        // Spazi multipli compattati
+        assert_eq!(wrap_text("a   b    c", 5), vec!["a b c"]);
+        assert_eq!(wrap_text("  ", 5), vec![""]);
+    }
+
+    #[test]
+    fn test_wrap_text_preserving_matches_wrap_text() {
+        // Stessi punti di a capo: con spazi singoli differiscono solo gli spazi in coda
+        let samples = ["hello big world", "abcdefgh ij", "uno due tre quattro\n\ncinque", "abc def", "e\u{301}e\u{301}e\u{301} x"];
+        for text in samples {
+            for width in 1..12 {
+                let trimmed: Vec<String> = wrap_text_preserving(text, width).iter().map(|row| row.trim_end().to_string()).collect();
+                assert_eq!(trimmed, wrap_text(text, width), "{text:?} a larghezza {width}");
+            }
+        }
+        
+        // Spazi e indentazione restano, riunendo le righe si riottiene il testo
+        assert_eq!(wrap_text_preserving("  a   b c", 4), vec!["  a   ", "b c"]);
+        assert_eq!(wrap_text_preserving("  a   b c", 4).concat(), "  a   b c");
+        assert_eq!(wrap_text("  a   b c", 4), vec!["a b", "c"]);
+        
+        // TextBuffer usa lo stesso algoritmo
+        let mut text = crate::ui::TextBuffer::new(7);
+        text.set_text("  uno  due tre\n\nquattrocento");
+        assert_eq!(text.wrapped_lines(), wrap_text_preserving("  uno  due tre\n\nquattrocento", 7).as_slice());
+    }
+
+    #[test]
+    fn test_draw_text_wrapped() {
+        let mut fb = StyledFrameBuffer::new(10, 3);
+        let rows = fb.draw_text_wrapped(Rect::new(1, 0, 7, 2), "one two three four", Some(Color::Green), None);
+        assert_eq!(rows, 2);
+        assert_eq!(fb.get(1, 0).ch, 'o');
+        assert_eq!(fb.get(5, 0).ch, 't');
+        assert_eq!(fb.get(1, 1).ch, 't');
+        assert_eq!(fb.get(1, 1).fg_color, Some(Color::Green));
+        // La terza riga ("four") viene clippata dal fondo del rect
+        assert_eq!(fb.get(1, 2).ch, ' ');
+    }
+
+    #[test]
+    fn test_clone_into_reuses_allocation() {
+        let mut src = StyledFrameBuffer::new(20, 10);
+        src.set(3, 4, StyledChar::new('Z').with_fg(Color::Cyan));
+        
+        let mut dst = StyledFrameBuffer::new(30, 10);
+        let ptr_before = dst.data.as_ptr();
+        src.clone_into(&mut dst);
+        
+        assert_eq!(dst.data.as_ptr(), ptr_before);
+        assert_eq!((dst.width, dst.height), (20, 10));
+        assert_eq!(dst.get(3, 4), src.get(3, 4));
+        assert_eq!(dst.data, src.data);
+    }
+
+    #[test]
+    fn test_get_changed_regions() {
+        let a = StyledFrameBuffer::new(20, 10);
+        let mut b = a.clone();
+        assert!(a.get_changed_regions(&b).is_empty());
+        
+        b.set(9, 9, StyledChar::new('x'));
+        assert_eq!(a.get_changed_regions(&b), vec![Rect::new(8, 8, 8, 2)]);
+        
+        b.set(19, 0, StyledChar::new('y'));
+        assert_eq!(a.get_changed_regions(&b), vec![Rect::new(16, 0, 4, 8), Rect::new(8, 8, 8, 2)]);
+    }
+
+    #[test]
+    fn test_pool_limits() {
+        // Unico test che modifica i pool globali, per evitare interferenze tra test paralleli
+        clear_pools();
+        assert_eq!(pool_stats(), (0, 0));
+        
+        FrameBuffer::new_pooled(10, 10).release_to_pool();
+        let (buffers, capacity) = pool_stats();
+        assert_eq!(buffers, 1);
+        assert!(capacity >= 100);
+        
+        // Buffer oltre la capacità massima non vengono conservati
+        set_pool_limits(4, 50);
+        assert_eq!(pool_stats(), (0, 0));
+        FrameBuffer::new_pooled(10, 10).release_to_pool();
+        assert_eq!(pool_stats().0, 0);
+        
+        // Pooling disabilitato
+        set_pool_limits(0, 1024);
+        FrameBuffer::new_pooled(2, 2).release_to_pool();
+        assert_eq!(pool_stats().0, 0);
+        
+        // Buffer stilizzati: rilascio esplicito e automatico con PooledBuffer
+        set_pool_limits(16, 1024 * 1024);
+        clear_pools();
+        StyledFrameBuffer::new_pooled(4, 4).release_to_pool();
+        assert_eq!(pool_stats().0, 1);
+        let mut pooled = PooledBuffer::new(4, 4);
+        assert_eq!(pool_stats().0, 0);
+        pooled.set(1, 1, StyledChar::new('x'));
+        assert_eq!(pooled.get(1, 1).ch, 'x');
+        drop(pooled);
+        assert_eq!(pool_stats().0, 1);
+        let reused = StyledFrameBuffer::new_pooled(2, 2);
+        assert_eq!(reused.get(1, 1).ch, ' ');
+        assert_eq!(pool_stats().0, 0);
+        
+        let kept = PooledBuffer::new(2, 2).into_inner();
+        assert_eq!(pool_stats().0, 0);
+        drop(kept);
+        assert_eq!(pool_stats().0, 0);
+        
+        clear_pools();
+    }
+
+    #[test]
+    fn test_image_to_braille_colored() {
+        // Metà sinistra rossa brillante, metà destra nera
+        let img = image::RgbImage::from_fn(4, 4, |x, _| {
+            if x < 2 { image::Rgb([255, 40, 40]) } else { image::Rgb([0, 0, 0]) }
+        });
+        let fb = image_to_braille_colored_opts(&DynamicImage::ImageRgb8(img), 2, 1, BrailleOptions { threshold: 50, invert: false }).unwrap();
+        
+        assert_eq!((fb.width, fb.height), (2, 1));
+        assert_ne!(fb.get(0, 0).ch, ' ');
+        assert_eq!(fb.get(0, 0).fg_color, Some(Color::Rgb(255, 40, 40)));
+        assert_eq!(fb.get(1, 0), StyledChar::default());
+    }
+
+    #[test]
+    fn test_row_scale() {
+        let mut fb = StyledFrameBuffer::new(4, 3);
+        assert_eq!(fb.get_row_scale(1), RowScale::Normal);
+        
+        fb.set_row_scale(1, RowScale::DoubleWidth);
+        fb.set_row_scale(5, RowScale::DoubleWidth); // fuori dai limiti: ignorato
+        assert_eq!(fb.get_row_scale(1), RowScale::DoubleWidth);
+        assert_eq!(fb.get_row_scale(2), RowScale::Normal);
+        
+        fb.resize(4, 1);
+        assert_eq!(fb.get_row_scale(1), RowScale::Normal);
+    }
+
+    #[test]
+    fn test_image_to_braille_edges() {
+        // Quadrato pieno al centro: solo il contorno deve accendersi
+        let img = GrayImage::from_fn(16, 16, |x, y| {
+            if (2..14).contains(&x) && (2..14).contains(&y) { image::Luma([255]) } else { image::Luma([0]) }
+        });
+        let magnitude = sobel_magnitude(&img);
+        assert_eq!(magnitude.get_pixel(8, 8).0[0], 0);
+        assert_eq!(magnitude.get_pixel(2, 8).0[0], 255);
+        
+        let fb = image_to_braille_edges(&DynamicImage::ImageLuma8(img), 8, 4, 50, 200).unwrap();
+        assert_eq!((fb.width, fb.height), (8, 4));
+        // Interno del quadrato: nessun bordo
+        assert_eq!(fb.get(3, 1), '\u{2800}');
+        assert_eq!(fb.get(4, 2), '\u{2800}');
+        assert_ne!(fb.get(0, 1), '\u{2800}');
+        assert_ne!(fb.get(7, 2), '\u{2800}');
+    }
+
+    #[test]
+    fn test_sprite_and_tilemap() {
+        use crate::sprite::{Sprite, TileMap};
+
+        let sprite = Sprite::from_lines(&[".@.", "@@@"]).with_transparent('.');
+        let mut fb = StyledFrameBuffer::new(4, 3);
+        fb.clear_with(StyledChar::new('#'));
+        
+        // Parzialmente fuori a sinistra: la colonna 0 dello sprite viene tagliata
+        fb.draw_sprite(&sprite, -1, 1);
+        assert_eq!(fb.get(0, 1).ch, '@');
+        assert_eq!(fb.get(1, 1).ch, '#'); // trasparente
+        assert_eq!(fb.get(0, 2).ch, '@');
+        assert_eq!(fb.get(1, 2).ch, '@');
+        assert_eq!(fb.get(2, 2).ch, '#');
+        
+        let palette = vec![StyledChar::new('.'), StyledChar::new('#').with_fg(Color::Yellow)];
+        let mut map = TileMap::new(10, 10, palette);
+        map.set_tile(5, 5, 1);
+        let mut view = StyledFrameBuffer::new(3, 3);
+        map.render(&mut view, Rect::new(0, 0, 3, 3), (4, 4));
+        assert_eq!(view.get(1, 1), StyledChar::new('#').with_fg(Color::Yellow));
+        assert_eq!(view.get(0, 0).ch, '.');
+        
+        // Oltre il bordo della mappa le celle restano invariate
+        let mut edge = StyledFrameBuffer::new(3, 3);
+        map.render(&mut edge, Rect::new(0, 0, 3, 3), (9, 9));
+        assert_eq!(edge.get(0, 0).ch, '.');
+        assert_eq!(edge.get(1, 1).ch, ' ');
+    }
+
+    #[test]
+    fn test_hit_map() {
+        use crate::ui::HitMap;
+
+        let mut hits = HitMap::new();
+        hits.add(Rect::new(0, 0, 10, 10), "window");
+        hits.add(Rect::new(8, 0, 1, 1), "close");
+        hits.add_with_z(Rect::new(5, 5, 10, 10), "popup", 1);
+        
+        assert_eq!(hits.hit(8, 0), Some("close"));
+        assert_eq!(hits.hit(1, 1), Some("window"));
+        // z più alto vince anche se inserito prima
+        hits.add(Rect::new(6, 6, 2, 2), "late");
+        assert_eq!(hits.hit(6, 6), Some("popup"));
+        assert_eq!(hits.hit(20, 20), None);
+        
+        hits.clear();
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_scrollbar() {
+        use crate::input::InputEvent;
+        use crate::ui::{Orientation, Scrollbar, Widget};
+        use crossterm::event::{MouseButton, MouseEventKind};
+
+        let mut bar = Scrollbar::new(Rect::new(9, 0, 1, 10), Orientation::Vertical);
+        bar.set_content(100, 20);
+        assert_eq!(bar.thumb(), (0, 2));
+        bar.set_offset(500);
+        assert_eq!(bar.offset(), 80);
+        assert_eq!(bar.thumb(), (8, 2));
+        
+        let click = |y| InputEvent::Mouse { x: 9, y, kind: MouseEventKind::Down(MouseButton::Left) };
+        assert_eq!(bar.handle_event(&click(0)), Some(0));
+        assert_eq!(bar.handle_event(&click(0)), None);
+        let drag = InputEvent::Mouse { x: 0, y: 9, kind: MouseEventKind::Drag(MouseButton::Left) };
+        assert_eq!(bar.handle_event(&drag), Some(80));
+        // Click fuori dalla barra ignorato
+        assert_eq!(bar.handle_event(&InputEvent::Mouse { x: 3, y: 0, kind: MouseEventKind::Down(MouseButton::Left) }), None);
+        assert_eq!(bar.handle_event(&InputEvent::Scroll { x: 9, y: 4, delta: 3 }), Some(77));
+        assert_eq!(bar.handle_event(&InputEvent::Scroll { x: 9, y: 4, delta: -10 }), Some(80));
+        assert_eq!(bar.handle_event(&InputEvent::Scroll { x: 2, y: 4, delta: 3 }), None);
+        
+        let mut fb = StyledFrameBuffer::new(10, 10);
+        bar.render(&mut fb);
+        assert_eq!(fb.get(9, 9).ch, '█');
+        assert_eq!(fb.get(9, 0).ch, '░');
+    }
+
+    #[test]
+    fn test_menu() {
+        use crate::input::InputEvent;
+        use crate::ui::{Menu, MenuAction, Widget};
+        use crossterm::event::KeyCode;
+
+        let items = vec!["Open".to_string(), "Save as".to_string(), "Quit".to_string()];
+        let mut menu = Menu::new(items, (2, 1));
+        assert_eq!(menu.handle_event(&InputEvent::Key(KeyCode::Up)), None);
+        assert_eq!(menu.highlighted(), 2);
+        menu.handle_event(&InputEvent::Key(KeyCode::Down));
+        menu.handle_event(&InputEvent::Key(KeyCode::Down));
+        assert_eq!(menu.handle_event(&InputEvent::Key(KeyCode::Enter)), Some(MenuAction::Selected(1)));
+        assert_eq!(menu.handle_event(&InputEvent::Key(KeyCode::Esc)), Some(MenuAction::Cancelled));
+        
+        // Dimensionato sulla voce più lunga
+        assert_eq!(menu.popup_rect(40, 20), Rect::new(2, 1, 11, 5));
+        // Ribaltato verso l'alto se sborda in basso
+        menu.set_anchor(2, 18);
+        assert_eq!(menu.popup_rect(40, 20), Rect::new(2, 13, 11, 5));
+        
+        let mut fb = StyledFrameBuffer::new(40, 20);
+        menu.render(&mut fb);
+        assert_eq!(fb.get(2, 13).ch, '+');
+        assert_eq!(fb.get(4, 14).ch, 'O');
+        assert_eq!(fb.get(4, 15).bg_color, Some(Color::White));
+        // get_rect restituisce il rettangolo effettivamente disegnato
+        assert_eq!(menu.get_rect(), Rect::new(2, 13, 11, 5));
+    }
+
+    #[test]
+    fn test_quit_keys() {
+        use crate::input::{translate_event, InputEvent, KeyMap};
+        use crate::ui::{Menu, MenuAction};
+        use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+        
+        let translate = |quit_keys: &KeyMap<()>, code: KeyCode, modifiers: KeyModifiers| {
+            translate_event(Event::Key(KeyEvent::new(code, modifiers)), false, quit_keys, &mut (80, 24), &mut (0, 0))
+        };
+        
+        // Di default q, Esc, Ctrl+C e Ctrl+D chiudono l'applicazione
+        let mut quit_keys = KeyMap::default_quit_keys();
+        assert_eq!(translate(&quit_keys, KeyCode::Esc, KeyModifiers::NONE), Some(InputEvent::Quit));
+        assert_eq!(translate(&quit_keys, KeyCode::Char('q'), KeyModifiers::NONE), Some(InputEvent::Quit));
+        assert_eq!(translate(&quit_keys, KeyCode::Char('c'), KeyModifiers::CONTROL), Some(InputEvent::Quit));
+        assert_eq!(translate(&quit_keys, KeyCode::Char('c'), KeyModifiers::NONE), Some(InputEvent::Key(KeyCode::Char('c'))));
+        // Ctrl+C esce anche con Shift o Alt premuti
+        assert_eq!(translate(&quit_keys, KeyCode::Char('c'), KeyModifiers::CONTROL | KeyModifiers::SHIFT), Some(InputEvent::Quit));
+        assert_eq!(translate(&quit_keys, KeyCode::Char('C'), KeyModifiers::CONTROL | KeyModifiers::SHIFT), Some(InputEvent::Quit));
+        assert_eq!(translate(&quit_keys, KeyCode::Char('c'), KeyModifiers::CONTROL | KeyModifiers::ALT), Some(InputEvent::Quit));
+        assert_eq!(translate(&quit_keys, KeyCode::Char('c'), KeyModifiers::ALT), Some(InputEvent::Key(KeyCode::Char('c'))));
+        
+        // Senza Esc tra i tasti di uscita il tasto arriva al menu
+        quit_keys.unbind(KeyCode::Esc, KeyModifiers::NONE);
+        let event = translate(&quit_keys, KeyCode::Esc, KeyModifiers::NONE).unwrap();
+        assert_eq!(event, InputEvent::Key(KeyCode::Esc));
+        let mut menu = Menu::new(vec!["Open".to_string()], (0, 0));
+        assert_eq!(menu.handle_event(&event), Some(MenuAction::Cancelled));
+    }
+
+    #[test]
+    fn test_restore_terminal_pops_keyboard_flags() {
+        use crate::input::{restore_terminal, KEYBOARD_ENHANCED};
+        use std::sync::atomic::Ordering;
+        
+        let pop = "\x1b[<1u";
+        let restored = |out: &mut Vec<u8>| {
+            out.clear();
+            restore_terminal(&mut *out);
+            String::from_utf8_lossy(out).into_owned()
+        };
+        let mut out = Vec::new();
+        
+        // Anche dall'hook di panic i flag della tastiera vengono tolti, una sola volta
+        KEYBOARD_ENHANCED.store(true, Ordering::SeqCst);
+        assert!(restored(&mut out).starts_with(pop));
+        assert!(!restored(&mut out).contains(pop));
+    }
+
+    #[test]
+    fn test_table() {
+        use crate::input::InputEvent;
+        use crate::ui::{Table, Widget};
+        use crossterm::event::KeyCode;
+
+        let columns = vec![("PID".to_string(), 3), ("Name".to_string(), 5)];
+        let rows = (0..10).map(|i| vec![i.to_string(), format!("process{}", i)]).collect();
+        let mut table = Table::new(Rect::new(0, 0, 9, 5), columns, rows);
+        
+        let mut fb = StyledFrameBuffer::new(9, 5);
+        table.render(&mut fb);
+        let line = |fb: &StyledFrameBuffer, y| (0..9).map(|x| fb.get(x, y).ch).collect::<String>();
+        assert_eq!(line(&fb, 0), "PID|Name ");
+        assert_eq!(line(&fb, 1), "---------");
+        // Celle troncate alla larghezza della colonna
+        assert_eq!(line(&fb, 2), "0  |proce");
+        assert_eq!(fb.get(0, 2).bg_color, Some(Color::White));
+        
+        // Scorrimento per mantenere visibile la selezione
+        for _ in 0..4 {
+            table.handle_input(&InputEvent::Key(KeyCode::Down));
+        }
+        assert_eq!(table.selected(), Some(4));
+        assert_eq!(table.scroll_offset(), 2);
+        table.handle_input(&InputEvent::Key(KeyCode::PageDown));
+        assert_eq!(table.selected(), Some(7));
+        table.handle_input(&InputEvent::Key(KeyCode::End));
+        assert_eq!(table.selected(), Some(9));
+        assert_eq!(table.scroll_offset(), 7);
+    }
+
+    #[test]
+    fn test_braille_canvas() {
+        let mut canvas = BrailleCanvas::new(5, 6);
+        assert_eq!(canvas.cell_size(), (3, 2));
+        
+        canvas.set_dot(0, 0);
+        assert!(canvas.get_dot(0, 0));
+        assert_eq!(canvas.to_framebuffer().get(0, 0), '\u{2801}');
+        
+        // Punti 7 e 8 (ultima riga della cella) e fuori dai limiti ignorati
+        canvas.set_dot(0, 3);
+        canvas.set_dot(1, 3);
+        canvas.set_dot(5, 0);
+        assert_eq!(canvas.to_framebuffer().get(0, 0), '\u{28C1}');
+        
+        canvas.set_dot(4, 5);
+        assert_eq!(canvas.to_framebuffer().get(2, 1), '\u{2802}');
+        
+        canvas.clear_dot(0, 0);
+        assert!(!canvas.get_dot(0, 0));
+        assert_eq!(canvas.to_framebuffer().get(0, 0), '\u{28C0}');
+    }
+
+    #[test]
+    fn test_braille_plot() {
+        let mut canvas = BrailleCanvas::new(8, 8);
+        canvas.plot_line(0, 0, 3, 3);
+        let lit: Vec<(usize, usize)> = (0..8)
+            .flat_map(|y| (0..8).map(move |x| (x, y)))
+            .filter(|&(x, y)| canvas.get_dot(x, y))
+            .collect();
+        assert_eq!(lit, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+        
+        // Serie crescente: y scalata verso l'alto, estremi agli angoli dei bounds
+        let mut chart = BrailleCanvas::new(8, 8);
+        chart.plot_series(&[(0.0, 0.0), (10.0, 10.0)], Rect::new(0, 0, 8, 8));
+        assert!(chart.get_dot(0, 7));
+        assert!(chart.get_dot(7, 0));
+        assert!(chart.get_dot(4, 3));
+        assert!(!chart.get_dot(0, 0));
+    }
+
+    #[test]
+    fn test_cursor_style_escape() {
+        use crate::renderer::CursorStyle;
+
+        assert_eq!(CursorStyle::default(), CursorStyle::Block);
+        assert_eq!(CursorStyle::Block.to_escape(), "\x1b[1 q");
+        assert_eq!(CursorStyle::Underline.to_escape(), "\x1b[3 q");
+        assert_eq!(CursorStyle::Bar.to_escape(), "\x1b[5 q");
+    }
+
+    #[test]
+    fn test_layout_mode() {
+        use crate::renderer::LayoutMode;
+
+        // Comportamento storico: 4 celle di margine per asse, centrato
+        assert_eq!(LayoutMode::default().workspace_layout((84, 34)), ((80, 30), (2, 2)));
+        // Dimensione minima anche su terminali piccoli, senza underflow
+        assert_eq!(LayoutMode::default().workspace_layout((30, 10)), ((40, 20), (0, 0)));
+        assert_eq!(LayoutMode::Fullscreen.workspace_layout((84, 34)), ((84, 34), (0, 0)));
+        
+        // FitExact: nessuna riga/colonna persa, anche con dimensioni dispari
+        assert_eq!(LayoutMode::FitExact.workspace_layout((83, 29)), ((82, 28), (1, 1)));
+        assert_eq!(LayoutMode::FitExact.workspace_layout((1, 1)), ((1, 1), (0, 0)));
+        for w in 0..=97u16 {
+            for h in [0, 1, 2, 29, 31] {
+                let ((ws_w, ws_h), (off_x, off_y)) = LayoutMode::FitExact.workspace_layout((w, h));
+                assert_eq!((ws_w + off_x, ws_h + off_y), (w as usize, h as usize));
+                assert!(off_x <= 1 && off_y <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lerp_from() {
+        let mut a = StyledFrameBuffer::new(4, 4);
+        a.clear_with(StyledChar::new('a').with_fg(Color::Rgb(0, 0, 0)).with_bg(Color::Red));
+        let mut b = StyledFrameBuffer::new(6, 4);
+        b.clear_with(StyledChar::new('b').with_fg(Color::Rgb(200, 100, 0)).with_bg(Color::Blue));
+        
+        let mut out = StyledFrameBuffer::new(4, 4);
+        out.lerp_from(&a, &b, 0.0);
+        assert_eq!(out.data, a.data);
+        out.lerp_from(&a, &b, 1.0);
+        assert!(out.data.iter().all(|&cell| cell == b.get(0, 0)));
+        
+        // A metà: metà delle celle per ciascun frame, colori RGB interpolati in luce lineare
+        out.lerp_from(&a, &b, 0.5);
+        let from_b = out.data.iter().filter(|cell| cell.ch == 'b').count();
+        assert_eq!(from_b, 8);
+        assert!(out.data.iter().all(|cell| cell.fg_color == Some(Color::Rgb(146, 71, 0))));
+        assert!(out.data.iter().all(|cell| cell.bg_color == Some(if cell.ch == 'b' { Color::Blue } else { Color::Red })));
+    }
+
+    #[test]
+    fn test_flip_and_transpose() {
+        // Pattern 3x2:  ab.
+        //               cd.
+        let mut fb = StyledFrameBuffer::new(3, 2);
+        for (i, ch) in "ab.cd.".chars().enumerate() {
+            fb.data[i] = StyledChar::new(ch).with_fg(Color::Green);
+        }
+        let chars = |fb: &StyledFrameBuffer| fb.data.iter().map(|cell| cell.ch).collect::<String>();
+        
+        let mut mirrored = fb.clone();
+        mirrored.flip_horizontal();
+        assert_eq!(chars(&mirrored), ".ba.dc");
+        assert_eq!(mirrored.get(0, 0).fg_color, Some(Color::Green));
+        
+        let mut flipped = fb.clone();
+        flipped.set_row_scale(0, RowScale::DoubleWidth);
+        flipped.flip_vertical();
+        assert_eq!(chars(&flipped), "cd.ab.");
+        assert_eq!(flipped.get_row_scale(1), RowScale::DoubleWidth);
+        
+        let transposed = fb.transpose();
+        assert_eq!((transposed.width, transposed.height), (2, 3));
+        assert_eq!(chars(&transposed), "acbd..");
+        assert_eq!(transposed.transpose().data, fb.data);
+    }
+
+    #[test]
+    fn test_rotate() {
+        // Pattern 3x2:  ┌─→
+        //               │ab
+        let mut fb = StyledFrameBuffer::new(3, 2);
+        for (i, ch) in "┌─→│ab".chars().enumerate() {
+            fb.data[i] = StyledChar::new(ch);
+        }
+        let chars = |fb: &StyledFrameBuffer| fb.data.iter().map(|cell| cell.ch).collect::<String>();
+        
+        let cw = fb.rotate_cw(false);
+        assert_eq!((cw.width, cw.height), (2, 3));
+        assert_eq!(chars(&cw), "│┌a─b→");
+        assert_eq!(chars(&fb.rotate_cw(true)), "─┐a│b↓");
+        
+        let ccw = fb.rotate_ccw(true);
+        assert_eq!(chars(&ccw), "↑b│a└─");
+        assert_eq!(ccw.rotate_cw(true).data, fb.data);
+    }
+
+    #[test]
+    fn test_image_to_ascii_fb() {
+        let img = GrayImage::from_fn(4, 1, |x, _| image::Luma([(x * 85) as u8]));
+        let img = DynamicImage::ImageLuma8(img);
+        
+        let fb = image_to_ascii_fb(&img, 4, 4, "", false).unwrap();
+        assert_eq!((fb.width, fb.height), (4, 1));
+        assert_eq!(fb.to_string(), " -*@");
+        
+        let fb = image_to_ascii_fb(&img, 4, 4, "#.", true).unwrap();
+        assert_eq!(fb.to_string(), "..##");
+        assert!(image_to_ascii_fb(&img, 0, 4, "", false).is_err());
+    }
+
+    #[test]
+    fn test_styled_blit_clipping() {
+        let mut src = StyledFrameBuffer::new(3, 3);
+        src.clear_with(StyledChar::new('s').with_fg(Color::Red));
+        src.set(1, 1, StyledChar::default());
+        src.set(2, 2, StyledChar::new('n'));
+        
+        let mut dst = StyledFrameBuffer::new(4, 4);
+        dst.clear_with(StyledChar::new('.').with_bg(Color::Blue));
+        dst.clear_dirty();
+        
+        // src_rect oltre la sorgente e destinazione oltre il bordo: clip su entrambi
+        let written = dst.blit(&src, Rect::new(1, 1, 5, 5), 3, 2);
+        assert_eq!(written, Rect::new(3, 2, 1, 2));
+        assert_eq!(dst.get(3, 2), StyledChar::default());
+        assert_eq!(dst.get(3, 3).ch, 's');
+        assert_eq!(dst.get_dirty_regions(), &[written]);
+        assert_eq!(dst.blit(&src, Rect::new(3, 0, 2, 2), 0, 0), Rect::new(0, 0, 0, 2));
+        
+        let written = dst.blit_blend(&src, Rect::new(0, 0, 3, 3), 0, 0);
+        assert_eq!(written, Rect::new(0, 0, 3, 3));
+        assert_eq!(dst.get(1, 1).ch, '.'); // trasparente
+        assert_eq!(dst.get(0, 0), StyledChar::new('s').with_fg(Color::Red).with_bg(Color::Blue));
+        assert_eq!(dst.get(2, 2).bg_color, Some(Color::Blue));
+    }
+
+    #[test]
+    fn test_braille_dots() {
+        let mut dots = [false; 8];
+        dots[0] = true;
+        assert_eq!(braille_from_dots(dots), '\u{2801}');
+        dots[7] = true;
+        assert_eq!(braille_from_dots(dots), '\u{2881}');
+        assert_eq!(braille_to_dots('\u{2881}'), dots);
+        assert_eq!(braille_to_dots('a'), [false; 8]);
+        
+        // Punto 4 = colonna destra, riga in alto
+        assert_eq!(BRAILLE_DOT_POSITIONS[3], (1, 0));
+        let mut block = [0u8; 8];
+        block[1] = 255;
+        assert_eq!(pixels_to_braille_with_threshold(&block, 128), '\u{2808}');
+        
+        for code in 0x2800..=0x28FFu32 {
+            let ch = char::from_u32(code).unwrap();
+            assert_eq!(braille_from_dots(braille_to_dots(ch)), ch);
+        }
+    }
+
+    #[test]
+    fn test_or_braille() {
+        let mut grid = FrameBuffer::filled(3, 1, '\u{2801}');
+        grid.set(2, 0, '+');
+        let mut series = FrameBuffer::filled(3, 1, '\u{2880}');
+        series.set(1, 0, 'x');
+        
+        grid.or_braille(&series, 0, 0);
+        assert_eq!(grid.get(0, 0), '\u{2881}');
+        assert_eq!(grid.get(1, 0), 'x');
+        assert_eq!(grid.get(2, 0), '\u{2880}');
+        
+        // Clipping al bordo destro
+        grid.or_braille(&FrameBuffer::filled(3, 1, '\u{2802}'), 2, 0);
+        assert_eq!(grid.get(2, 0), '\u{2882}');
+    }
+
+    #[test]
+    fn test_draw_text_tabs() {
+        let mut fb = StyledFrameBuffer::new(12, 1);
+        fb.draw_text(0, 0, "a\tb", None, None);
+        assert_eq!(fb.get(1, 0).ch, '?');
+        
+        let options = DrawTextOptions { tab_width: 4 };
+        fb.draw_text_with(0, 0, "ab\tc\t\td", Some(Color::Red), Some(Color::Blue), options);
+        let line: String = (0..12).map(|x| fb.get(x, 0).ch).collect();
+        assert_eq!(line, "ab  c       ");
+        assert_eq!(fb.get(3, 0), StyledChar::new(' ').with_fg(Color::Red).with_bg(Color::Blue));
+    }
+
+    #[test]
+    fn test_clip_stack() {
+        let mut fb = StyledFrameBuffer::new(10, 4);
+        fb.push_clip(Rect::new(2, 0, 5, 4));
+        fb.push_clip(Rect::new(0, 1, 4, 2));
+        assert_eq!(fb.current_clip(), Some(Rect::new(2, 1, 2, 2)));
+        
+        fb.draw_rect(Rect::new(0, 0, 10, 4), '#', None, None);
+        let count = fb.data.iter().filter(|cell| cell.ch == '#').count();
+        assert_eq!(count, 4);
+        assert_eq!(fb.get(1, 1).ch, ' ');
+        assert_eq!(fb.get(2, 1).ch, '#');
+        
+        assert_eq!(fb.pop_clip(), Some(Rect::new(2, 1, 2, 2)));
+        fb.draw_text(0, 3, "abcdefgh", None, None);
+        let line: String = (0..10).map(|x| fb.get(x, 3).ch).collect();
+        assert_eq!(line, "  cdefg   ");
+        
+        // Blit clippato: restituisce solo l'area visibile
+        let src = StyledFrameBuffer::new(10, 4);
+        assert_eq!(fb.blit(&src, Rect::new(0, 0, 10, 4), 0, 0), Rect::new(2, 0, 5, 4));
+        
+        fb.pop_clip();
+        assert_eq!(fb.current_clip(), None);
+        fb.set(0, 0, StyledChar::new('x'));
+        assert_eq!(fb.get(0, 0).ch, 'x');
+    }
+
+    #[test]
+    fn test_combining_marks() {
+        let mut fb = StyledFrameBuffer::new(6, 1);
+        // "e" + acuto combinante occupa una sola cella
+        fb.draw_text(0, 0, "cafe\u{301}!", None, None);
+        assert_eq!(fb.get(3, 0).ch, 'e');
+        assert_eq!(fb.get(3, 0).combining, Some('\u{301}'));
+        assert_eq!(fb.get(4, 0).ch, '!');
+        assert_eq!(fb.get(3, 0).to_string(), "e\u{301}");
+        assert_eq!(fb.to_string(), "cafe\u{301}! ");
+        
+        fb.draw_text_wrapped(Rect::new(0, 0, 6, 1), "a\u{300}b", None, None);
+        assert_eq!(fb.get(0, 0).combining, Some('\u{300}'));
+        assert_eq!(fb.get(1, 0).ch, 'b');
+    }
+
+    #[test]
+    fn test_render_partial_resets_row_state() {
+        let mut last = StyledFrameBuffer::new(3, 2);
+        for x in 0..3 {
+            last.set(x, 0, StyledChar::new('#').with_bg(Color::Red));
+        }
+        let mut current = last.clone();
+        current.set(0, 1, StyledChar::new('a'));
+        
+        let output = current.render_partial(&last);
+        // Solo la riga 1 viene ridisegnata, partendo da uno stato pulito
+        assert!(output.starts_with("\x1b[2;1H\x1b[0m"));
+        assert!(!output.contains("\x1b[41m"));
+        assert!(output.ends_with("\x1b[0m\x1b[0K"));
+        
+        // Riga colorata cambiata: il reset precede la pulizia di fine riga
+        current.set(1, 0, StyledChar::new('!').with_bg(Color::Red));
+        let output = current.render_partial(&last);
+        assert!(output.starts_with("\x1b[1;1H\x1b[0m\x1b[41m#!#\x1b[0m\x1b[0K"));
+    }
+
+    #[test]
+    fn test_default_cell() {
+        let theme = StyledChar::new('.').with_bg(Color::Blue);
+        let mut fb = StyledFrameBuffer::new(2, 2);
+        fb.set(0, 0, StyledChar::new('x'));
+        fb.set_default_cell(theme);
+        
+        fb.resize(3, 2);
+        assert_eq!(fb.get(0, 0).ch, 'x');
+        assert_eq!(fb.get(2, 1), theme);
+        
+        fb.clear();
+        assert!(fb.data.iter().all(|&cell| cell == theme));
+    }
+
+    #[test]
+    fn test_viewport() {
+        use crate::compositor::Viewport;
+
+        let mut world = StyledFrameBuffer::new(20, 10);
+        world.set(15, 8, StyledChar::new('@'));
+        let mut viewport = Viewport::new(world, 8, 4);
+        
+        viewport.scroll_by(100, 100);
+        assert_eq!(viewport.camera, (12, 6));
+        viewport.scroll_by(-3, -100);
+        assert_eq!(viewport.camera, (9, 0));
+        
+        viewport.set_camera(10, 6);
+        let mut screen = StyledFrameBuffer::new(8, 4);
+        assert_eq!(viewport.render_into(&mut screen), Rect::new(0, 0, 8, 4));
+        assert_eq!(screen.get(5, 2).ch, '@');
+    }
+
+    #[test]
+    fn test_apply_stipple() {
+        let mut buffer = StyledFrameBuffer::new(6, 4);
+        buffer.clear_with(StyledChar::new('x'));
+        let shade = StyledChar::new('░').with_fg(Color::Gray);
+        
+        buffer.apply_stipple(Rect::new(1, 1, 4, 2), StipplePattern::Checker, shade);
+        assert_eq!(buffer.get(1, 1), shade);
+        assert_eq!(buffer.get(2, 1).ch, 'x');
+        assert_eq!(buffer.get(2, 2), shade);
+        assert_eq!(buffer.get(0, 0).ch, 'x');
+        assert_eq!(buffer.get(5, 1).ch, 'x');
+        
+        buffer.apply_stipple(Rect::new(0, 0, 10, 10), StipplePattern::HorizontalLines, shade);
+        assert!((0..6).all(|x| buffer.get(x, 2) == shade));
+        assert_eq!(buffer.get(0, 3).ch, 'x');
+    }
+
+    #[test]
+    fn test_set_span() {
+        let mut buffer = StyledFrameBuffer::new(8, 2);
+        buffer.clear_dirty();
+        let cell = StyledChar::new('=').with_fg(Color::Green);
+        
+        buffer.set_span(5, 1, 10, cell);
+        assert_eq!(buffer.get_dirty_regions(), &[Rect::new(5, 1, 3, 1)]);
+        assert!((5..8).all(|x| buffer.get(x, 1) == cell));
+        assert_eq!(buffer.get(4, 1).ch, ' ');
+        
+        buffer.push_clip(Rect::new(0, 0, 2, 2));
+        buffer.set_span(1, 0, 4, cell);
+        assert_eq!(buffer.get(1, 0), cell);
+        assert_eq!(buffer.get(2, 0).ch, ' ');
+        buffer.set_span(3, 0, 4, cell);
+        assert_eq!(buffer.get(3, 0).ch, ' ');
+    }
+
+    #[test]
+    fn test_changed_regions_block_size() {
+        let a = StyledFrameBuffer::new(16, 8);
+        let mut b = a.clone();
+        b.set(5, 6, StyledChar::new('!'));
+        
+        assert_eq!(a.get_changed_regions(&b), vec![Rect::new(0, 0, 8, 8)]);
+        assert_eq!(a.get_changed_regions_with(&b, 2), vec![Rect::new(4, 6, 2, 2)]);
+        assert_eq!(a.get_changed_regions_with(&b, 32), vec![Rect::new(0, 0, 16, 8)]);
+    }
+
+    #[test]
+    fn test_toast() {
+        use crate::animation::{Animation, AnimationManager};
+        use crate::ui::{show_toast, Toast, ToastLevel, ToastPosition};
+
+        let mut buffer = StyledFrameBuffer::new(12, 5);
+        show_toast(&mut buffer, "Saved", ToastLevel::Warn, ToastPosition::Bottom);
+        assert_eq!(buffer.get(1, 4).ch, 'S');
+        assert_eq!(buffer.get(11, 4).bg_color, Some(Color::Yellow));
+        assert_eq!(buffer.get(1, 4).fg_color, Some(Color::Black));
+        assert_eq!(buffer.get(0, 3).bg_color, None);
+        
+        let mut toast = Toast::new("Oops".to_string(), ToastLevel::Error, ToastPosition::Top, Duration::from_secs(2));
+        assert!(!toast.update(Duration::from_secs(1)));
+        assert!(toast.update(Duration::from_secs(1)));
+        
+        let mut manager = AnimationManager::new();
+        manager.add_animation(Box::new(Toast::new("Hi".to_string(), ToastLevel::Info, ToastPosition::Top, Duration::from_millis(500))));
+        manager.apply_all(&mut buffer);
+        assert_eq!(buffer.get(0, 0).bg_color, Some(Color::Blue));
+        manager.update(Duration::from_secs(1));
+        let mut fresh = StyledFrameBuffer::new(12, 5);
+        manager.apply_all(&mut fresh);
+        assert_eq!(fresh.get(0, 0).bg_color, None);
+    }
+
+    #[test]
+    fn test_animation_clock() {
+        use crate::animation::{Animation, AnimationClock, AnimationManager};
+        
+        // Cursore che lampeggia seguendo l'orologio comune invece di un timer proprio
+        struct Blink(usize);
+        impl Animation for Blink {
+            fn update(&mut self, _delta_time: Duration) -> bool {
+                false
+            }
+            fn apply(&self, _buffer: &mut StyledFrameBuffer) {}
+            fn apply_with_clock(&self, buffer: &mut StyledFrameBuffer, clock: &AnimationClock) {
+                let ch = if clock.blink(Duration::from_millis(500)) { '#' } else { ' ' };
+                buffer.set(self.0, 0, StyledChar::new(ch));
+            }
+        }
+        
+        let mut manager = AnimationManager::new();
+        manager.add_animation(Box::new(Blink(0)));
+        manager.update(Duration::from_millis(100));
+        // Aggiunta più tardi, resta comunque in fase con la prima
+        manager.add_animation(Box::new(Blink(1)));
+        manager.update(Duration::from_millis(100));
+        assert_eq!(manager.elapsed(), Duration::from_millis(200));
+        assert_eq!(manager.frame_count(), 2);
+        
+        let mut buffer = StyledFrameBuffer::new(2, 1);
+        manager.apply_all(&mut buffer);
+        assert_eq!((buffer.get(0, 0).ch, buffer.get(1, 0).ch), ('#', '#'));
+        manager.update(Duration::from_millis(100));
+        manager.apply_all(&mut buffer);
+        assert_eq!((buffer.get(0, 0).ch, buffer.get(1, 0).ch), (' ', ' '));
+        
+        let clock = manager.clock();
+        assert!((clock.phase(Duration::from_millis(400)) - 0.75).abs() < 1e-6);
+        assert_eq!(clock.phase(Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn test_text_display_width() {
+        assert_eq!(text_display_width("hello"), 5);
+        assert_eq!(text_display_width("e\u{301}a\t"), 3);
+        assert_eq!(text_display_width("👨‍👩‍👧"), 1);
+        assert_eq!(text_display_width(""), 0);
+        
+        assert_eq!(truncate_to_width("e\u{301}tude", 2), "e\u{301}t");
+        assert_eq!(truncate_to_width("abc", 10), "abc");
+        assert_eq!(truncate_to_width("abc", 0), "");
+        
+        // Coerente con quanto scrive draw_text
+        let mut buffer = StyledFrameBuffer::new(10, 1);
+        let text = "né€x";
+        buffer.draw_text(0, 0, text, None, None);
+        let width = text_display_width(text);
+        assert_ne!(buffer.get(width - 1, 0).ch, ' ');
+        assert_eq!(buffer.get(width, 0).ch, ' ');
+    }
+
+    #[test]
+    fn test_button_label_centering() {
+        use crate::ui::{Button, Widget};
+
+        let mut buffer = StyledFrameBuffer::new(12, 3);
+        Button::new(Rect::new(0, 0, 1, 1), "🚀 Go".to_string()).render(&mut buffer);
+        Button::new(Rect::new(2, 0, 2, 3), "🚀".to_string()).render(&mut buffer);
+        
+        // Etichetta non ASCII centrata per larghezza in celle, non in byte
+        Button::new(Rect::new(0, 0, 12, 3), "né".to_string()).render(&mut buffer);
+        assert_eq!(buffer.get(5, 1).ch, 'n');
+        assert_eq!(buffer.get(7, 1).ch, ' ');
+        
+        // Etichetta troppo lunga troncata senza coprire il bordo
+        Button::new(Rect::new(0, 0, 5, 3), "abcdef".to_string()).render(&mut buffer);
+        assert_eq!(buffer.get(3, 1).ch, 'c');
+        assert_ne!(buffer.get(4, 1).ch, 'd');
+    }
+
+    #[test]
+    fn test_unified_error() {
+        fn convert() -> Result<FrameBuffer> {
+            let img = DynamicImage::new_rgb8(4, 4);
+            Ok(image_to_braille_fb(&img, 0, 0)?)
+        }
+        fn io() -> Result<()> {
+            Err(std::io::Error::other("tty"))?
+        }
+        
+        assert!(matches!(convert(), Err(Error::Conversion(ConversionError::InvalidDimensions))));
+        let err = io().unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_styled_char_builder() {
+        let cell = StyledChar::builder().ch('x').fg(Color::Red).bold().underline().build();
+        assert_eq!(cell.ch, 'x');
+        assert_eq!(cell.fg_color, Some(Color::Red));
+        assert_eq!(cell.bg_color, None);
+        assert_eq!(cell.attributes, Attributes::BOLD | Attributes::UNDERLINE);
+        assert_eq!(cell.to_string(), "\x1b[1m\x1b[4m\x1b[31mx\x1b[0m");
+        
+        let shorthand = StyledChar::styled('y', Color::Green, Color::Black);
+        assert_eq!(shorthand, StyledChar::new('y').with_fg(Color::Green).with_bg(Color::Black));
+        
+        // Un attributo che si spegne richiede un reset prima della cella successiva
+        let mut buffer = StyledFrameBuffer::new(2, 1);
+        buffer.set(0, 0, StyledChar::builder().ch('a').bold().build());
+        buffer.set(1, 0, StyledChar::new('b'));
+        assert_eq!(buffer.to_string(), "\x1b[1ma\x1b[0mb");
+    }
+
+    #[test]
+    fn test_image_to_quadrant_fb() {
+        // Colonna sinistra accesa, riga in basso accesa nel secondo blocco
+        let img = GrayImage::from_fn(4, 2, |x, y| image::Luma([if x == 0 || (x >= 2 && y == 1) { 255 } else { 0 }]));
+        let img = DynamicImage::ImageLuma8(img);
+        
+        let fb = image_to_quadrant_fb(&img, 2, 1).unwrap();
+        assert_eq!((fb.width, fb.height), (2, 1));
+        assert_eq!(fb.to_string(), "▌▄");
+        assert!(image_to_quadrant_fb(&img, 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_point() {
+        let p = Point::new(2, 3);
+        assert_eq!(p.offset(-2, 1), Some(Point::new(0, 4)));
+        assert_eq!(p.offset(-3, 0), None);
+        assert_eq!(p.saturating_offset(-10, -10), Point::new(0, 0));
+        assert_eq!(p.delta_to(Point::new(0, 5)), (-2, 2));
+        
+        let area = Rect::new(1, 1, 4, 2);
+        assert_eq!(Point::new(9, 0).clamp_to(&area), Point::new(4, 1));
+        assert!(area.contains_point(area.origin()));
+        
+        let bounds = Rect::new(0, 0, 20, 10);
+        let window = Rect::new(5, 5, 8, 4);
+        assert_eq!(window.moved_within(-10, 2, bounds), Rect::new(0, 6, 8, 4));
+        assert_eq!(window.moved_within(100, -100, bounds), Rect::new(12, 0, 8, 4));
+        assert_eq!(Rect::new(0, 0, 30, 4).moved_within(3, 0, bounds).x, 0);
+    }
+
+    #[test]
+    fn test_update_braille_fb() {
+        let dark = DynamicImage::ImageLuma8(GrayImage::new(8, 8));
+        let mut fb = image_to_braille_fb(&dark, 4, 2).unwrap();
+        assert_eq!((fb.width, fb.height), (4, 2));
+        
+        // Accende due pixel: solo la cella che li contiene viene riconvertita
+        let mut lit = GrayImage::new(8, 8);
+        lit.put_pixel(5, 6, image::Luma([255]));
+        lit.put_pixel(0, 0, image::Luma([255]));
+        let lit = DynamicImage::ImageLuma8(lit);
+        
+        let updated = update_braille_fb(&mut fb, &lit, Rect::new(5, 6, 1, 1)).unwrap();
+        assert_eq!(updated, Rect::new(2, 1, 1, 1));
+        assert_eq!(fb.get(2, 1), image_to_braille_fb(&lit, 4, 2).unwrap().get(2, 1));
+        assert_ne!(fb.get(2, 1), '\u{2800}');
+        // Fuori dalla regione dichiarata nulla cambia
+        assert_eq!(fb.get(0, 0), '\u{2800}');
+        
+        let wrong = DynamicImage::ImageLuma8(GrayImage::new(16, 8));
+        assert!(update_braille_fb(&mut fb, &wrong, Rect::new(0, 0, 1, 1)).is_err());
+    }
+
+    #[test]
+    fn test_compositor_z_order() {
+        use crate::compositor::{Compositor, Layer};
+
+        let mut compositor = Compositor::new(10, 5);
+        let mut background = Layer::new(10, 5, 0, 0);
+        background.buffer.clear_with(StyledChar::new('.'));
+        let background = compositor.add_layer(background);
+        let mut window = Layer::new(4, 3, 2, 1);
+        window.buffer.clear_with(StyledChar::new('w'));
+        let window = compositor.add_layer(window);
+        let mut overlay = Layer::new(4, 3, 4, 1);
+        overlay.blend = true;
+        overlay.buffer.set(3, 0, StyledChar::new('o'));
+        let overlay = compositor.add_layer(overlay);
+        
+        // Le celle vuote dell'overlay lasciano passare il livello sotto
+        assert_eq!(compositor.hit_top_layer(4, 1), Some(window));
+        assert_eq!(compositor.hit_top_layer(7, 1), Some(overlay));
+        assert_eq!(compositor.hit_top_layer(0, 0), Some(background));
+        assert_eq!(compositor.compose().get(4, 1).ch, 'w');
+        
+        compositor.send_to_back(window);
+        assert_eq!(compositor.hit_top_layer(4, 1), Some(background));
+        compositor.bring_to_front(window);
+        assert_eq!(compositor.layer_ids(), vec![background, overlay, window]);
+        assert_eq!(compositor.compose().get(2, 1).ch, 'w');
+    }
+
+    #[test]
+    fn test_image_to_braille_fb_weighted() {
+        // Rosso puro: scuro con i pesi standard, pieno isolando il canale rosso
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 4, Rgb([255, 0, 0])));
+        
+        let standard = image_to_braille_fb_weighted(&img, 1, 1, (0.299, 0.587, 0.114), 128).unwrap();
+        assert_eq!(standard.get(0, 0), '\u{2800}');
+        let red = image_to_braille_fb_weighted(&img, 1, 1, (2.0, 0.0, 0.0), 128).unwrap();
+        assert_eq!(red.get(0, 0), '\u{28FF}');
+        
+        // Pesi nulli: ripiego sui pesi standard
+        let fallback = image_to_braille_fb_weighted(&img, 1, 1, (0.0, -1.0, 0.0), 128).unwrap();
+        assert_eq!(fallback.get(0, 0), standard.get(0, 0));
+        assert!(image_to_braille_fb_weighted(&img, 0, 1, (1.0, 1.0, 1.0), 128).is_err());
+    }
+
+    #[test]
+    fn test_text_area() {
+        use crate::input::InputEvent;
+        use crate::ui::{TextArea, Widget};
+        use crossterm::event::KeyCode;
+        
+        let mut area = TextArea::new(Rect::new(0, 0, 6, 2));
+        area.set_text("ab\ncd");
+        area.handle_input(&InputEvent::Key(KeyCode::End));
+        area.handle_input(&InputEvent::Key(KeyCode::Enter));
+        area.handle_input(&InputEvent::Key(KeyCode::Char('x')));
+        assert_eq!(area.text(), "ab\nx\ncd");
+        assert_eq!(area.cursor(), (1, 1));
+        
+        // Backspace a inizio riga unisce con la precedente
+        area.handle_input(&InputEvent::Key(KeyCode::Down));
+        area.handle_input(&InputEvent::Key(KeyCode::Home));
+        area.handle_input(&InputEvent::Key(KeyCode::Backspace));
+        assert_eq!(area.text(), "ab\nxcd");
+        assert_eq!(area.cursor(), (1, 1));
+        
+        // Selezione con shift+frecce attraverso le righe
+        area.set_cursor(0, 1);
+        area.handle_input(&InputEvent::Shifted(KeyCode::Down));
+        area.handle_input(&InputEvent::Shifted(KeyCode::Right));
+        assert_eq!(area.selection(), Some(((0, 1), (1, 2))));
+        assert_eq!(area.selected_text().as_deref(), Some("b\nxc"));
+        
+        let mut fb = StyledFrameBuffer::new(6, 2);
+        area.render(&mut fb);
+        assert_eq!(fb.get(0, 0).bg_color, Some(Color::Black));
+        assert_eq!(fb.get(1, 0).bg_color, Some(Color::White));
+        assert_eq!(fb.get(1, 1).bg_color, Some(Color::White));
+        assert_eq!(fb.get(2, 1).bg_color, Some(Color::Yellow));
+        
+        // Il testo digitato sostituisce la selezione
+        area.handle_input(&InputEvent::Key(KeyCode::Char('Z')));
+        assert_eq!(area.text(), "aZd");
+        assert_eq!(area.selection(), None);
+        assert_eq!(area.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn test_text_area_types_quit_letter() {
+        use crate::input::{translate_event, KeyMap};
+        use crate::ui::{TextArea, Widget};
+        use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+        
+        // Togliendo q dai tasti di uscita la lettera arriva all'editor
+        let mut quit_keys = KeyMap::default_quit_keys();
+        quit_keys.unbind(KeyCode::Char('q'), KeyModifiers::NONE);
+        let mut area = TextArea::new(Rect::new(0, 0, 10, 2));
+        for ch in "quiq".chars() {
+            let raw = Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+            let event = translate_event(raw, false, &quit_keys, &mut (80, 24), &mut (0, 0)).unwrap();
+            assert!(area.handle_input(&event));
+        }
+        assert_eq!(area.text(), "quiq");
+    }
+
+    #[test]
+    fn test_next_word_boundary() {
+        use crate::input::InputEvent;
+        use crate::ui::{next_word_boundary, TextArea, Widget, WordDirection::*};
+        use crossterm::event::KeyCode;
+        
+        // Spazi multipli saltati, punteggiatura come parola a sé
+        let text = "foo,  bar.baz   qux";
+        assert_eq!(next_word_boundary(text, 0, Forward), 3);
+        assert_eq!(next_word_boundary(text, 3, Forward), 4);
+        assert_eq!(next_word_boundary(text, 4, Forward), 9);
+        assert_eq!(next_word_boundary(text, 9, Forward), 10);
+        assert_eq!(next_word_boundary(text, 14, Forward), 19);
+        assert_eq!(next_word_boundary(text, 19, Forward), 19);
+        assert_eq!(next_word_boundary(text, 19, Backward), 16);
+        assert_eq!(next_word_boundary(text, 16, Backward), 10);
+        assert_eq!(next_word_boundary(text, 6, Backward), 3);
+        assert_eq!(next_word_boundary(text, 2, Backward), 0);
+        // Indici in caratteri, lettere non ASCII comprese
+        assert_eq!(next_word_boundary("città è", 0, Forward), 5);
+        assert_eq!(next_word_boundary("città è", 7, Backward), 6);
+        
+        let mut area = TextArea::new(Rect::new(0, 0, 20, 2));
+        area.set_text("one  two!\nthree");
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Right));
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Right));
+        assert_eq!(area.cursor(), (0, 8));
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Right));
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Right));
+        assert_eq!(area.cursor(), (1, 0));
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Left));
+        assert_eq!(area.cursor(), (0, 9));
+        
+        // Cancellazione per parola
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Backspace));
+        assert_eq!(area.text(), "one  two\nthree");
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Backspace));
+        assert_eq!(area.text(), "one  \nthree");
+        area.set_cursor(0, 0);
+        area.handle_input(&InputEvent::Ctrl(KeyCode::Delete));
+        assert_eq!(area.text(), "  \nthree");
+    }
+
+    #[test]
+    fn test_same_content() {
+        let mut fb = StyledFrameBuffer::new(4, 2);
+        let blank = fb.clone();
+        assert!(fb.same_content(&blank));
+        
+        // Cambiano colore, attributi di riga e dimensioni
+        fb.set(1, 1, StyledChar::new(' ').with_bg(Color::Red));
+        assert!(!fb.same_content(&blank));
+        let colored = fb.clone();
+        fb.set_row_scale(0, RowScale::DoubleWidth);
+        assert!(!fb.same_content(&colored));
+        assert!(!StyledFrameBuffer::new(2, 4).same_content(&blank));
+    }
+
+    #[test]
+    fn test_mouse_support_detection() {
+        use crate::input::{parse_mode_report, scan_query_replies, MouseSupport};
+        
+        // Risposta DECRPM seguita dagli attributi del dispositivo
+        assert_eq!(parse_mode_report(b"\x1b[?1000;1$y\x1b[?62;22c", 1000), Some(true));
+        assert_eq!(parse_mode_report(b"\x1b[?1000;0$y\x1b[?1;2c", 1000), Some(false));
+        // Solo DA1: DECRQM non implementato
+        assert_eq!(parse_mode_report(b"\x1b[?1;2c", 1000), None);
+        assert_eq!(parse_mode_report(b"\x1b[?1006;2$y", 1000), None);
+        
+        // I tasti premuti durante l'attesa vengono separati dalle risposte, anche se
+        // contengono una 'c' o arrivano in mezzo
+        let scanned = scan_query_replies(b"ac\x1b[?1000;1$yx\x1b[?62;22cq");
+        assert_eq!(scanned.replies, b"\x1b[?1000;1$y\x1b[?62;22c");
+        assert_eq!(scanned.other, b"acxq");
+        assert!(scanned.complete);
+        // Risposta DA1 non ancora arrivata per intero
+        let scanned = scan_query_replies(b"\x1b[?1000;1$y\x1b[?62;2");
+        assert!(!scanned.complete);
+        assert_eq!(scanned.other, b"\x1b[?62;2");
+        assert_eq!(scan_query_replies(b"\x1b[A").other, b"\x1b[A");
+        
+        assert_eq!(MouseSupport::from_term(Some("dumb")), MouseSupport::Unsupported);
+        assert_eq!(MouseSupport::from_term(Some("xterm-256color")), MouseSupport::Supported);
+    }
+
+    #[test]
+    fn test_luma_to_braille_fb() {
+        // Stesso risultato del percorso via DynamicImage
+        let pixels: Vec<u8> = (0..16 * 8).map(|i| if (i % 16) < 8 { 255 } else { 0 }).collect();
+        let raw = luma_to_braille_fb(&pixels, 16, 8, 4, 1, 128).unwrap();
+        let img = DynamicImage::ImageLuma8(GrayImage::from_raw(16, 8, pixels.clone()).unwrap());
+        let via_image = image_to_braille_fb_with_threshold(&img, 4, 1, 128).unwrap();
+        assert_eq!((raw.width, raw.height), (via_image.width, via_image.height));
+        assert_eq!(raw.data, via_image.data);
+        
+        // Senza ridimensionamento: una cella per blocco 2x4
+        let full = luma_to_braille_fb(&pixels, 16, 8, 8, 2, 128).unwrap();
+        assert_eq!((full.width, full.height), (8, 2));
+        assert_eq!(full.get(0, 0), '\u{28FF}');
+        assert_eq!(full.get(7, 1), '\u{2800}');
+        // Limiti enormi: nessun overflow nella conversione in pixel
+        assert_eq!(luma_to_braille_fb(&pixels, 16, 8, usize::MAX, usize::MAX, 128).unwrap().data, full.data);
+        
+        assert!(matches!(luma_to_braille_fb(&pixels, 16, 7, 8, 2, 128), Err(ConversionError::InvalidDimensions)));
+        assert!(luma_to_braille_fb(&pixels, 16, 8, 0, 2, 128).is_err());
+        assert!(luma_to_braille_fb(&pixels, usize::MAX, 2, 8, 2, 128).is_err());
+    }
+
+    #[test]
+    fn test_image_to_braille_fb_boxed() {
+        // Immagine larga 4:1 in un box 4x4: centrata verticalmente
+        let img = DynamicImage::ImageLuma8(GrayImage::from_pixel(8, 4, image::Luma([255])));
+        let fb = image_to_braille_fb_boxed(&img, 4, 4, 128);
+        assert_eq!((fb.width, fb.height), (4, 4));
+        for x in 0..4 {
+            assert_eq!(fb.get(x, 0), ' ');
+            assert_eq!(fb.get(x, 1), '\u{28FF}');
+            assert_eq!(fb.get(x, 2), ' ');
+        }
+        
+        // Immagine alta: centrata orizzontalmente
+        let tall = DynamicImage::ImageLuma8(GrayImage::from_pixel(2, 8, image::Luma([255])));
+        let fb = image_to_braille_fb_boxed(&tall, 5, 2, 128);
+        assert_eq!(fb.data.iter().collect::<String>(), "  \u{28FF}    \u{28FF}  ");
+        assert_eq!(image_to_braille_fb_boxed(&tall, 0, 2, 128).data.len(), 0);
+    }
+
+    #[test]
+    fn test_compositor_blend_modes() {
+        use crate::compositor::{BlendMode, Compositor, Layer};
+        
+        // Multiply: bianco sopra rosso resta rosso, bianco sopra nero resta nero
+        let white = Color::BrightWhite;
+        assert_eq!(BlendMode::Multiply.blend_rgb(white.to_rgb(), Color::Red.to_rgb()), Color::Red.to_rgb());
+        assert_eq!(BlendMode::Multiply.blend_rgb(white.to_rgb(), Color::Black.to_rgb()), (0, 0, 0));
+        assert_eq!(BlendMode::Screen.blend_rgb((0, 0, 0), (10, 20, 30)), (10, 20, 30));
+        assert_eq!(BlendMode::Add.blend_rgb((200, 10, 0), (100, 10, 0)), (255, 20, 0));
+        
+        let mut compositor = Compositor::new(2, 1);
+        let mut base = Layer::new(2, 1, 0, 0);
+        base.buffer.set(0, 0, StyledChar::styled('a', Color::Red, Color::Red));
+        base.buffer.set(1, 0, StyledChar::styled('\u{2801}', Color::Black, Color::Black));
+        compositor.add_layer(base);
+        let mut overlay = Layer::new(2, 1, 0, 0);
+        overlay.z_order = 1;
+        overlay.blend_mode = BlendMode::Multiply;
+        overlay.buffer.set(0, 0, StyledChar::styled(' ', white, white));
+        overlay.buffer.set(1, 0, StyledChar::styled('\u{2802}', white, white));
+        let id = compositor.add_layer(overlay);
+        
+        // Il carattere vuoto lascia visibile quello sotto
+        let out = compositor.compose();
+        assert_eq!(out.get(0, 0).ch, 'a');
+        assert_eq!(out.get(0, 0).bg_color.map(|c| c.to_rgb()), Some(Color::Red.to_rgb()));
+        assert_eq!(out.get(1, 0).bg_color, Some(Color::Rgb(0, 0, 0)));
+        assert_eq!(out.get(1, 0).ch, '\u{2802}');
+        
+        // Max unisce i punti Braille
+        compositor.layer_mut(id).unwrap().blend_mode = BlendMode::Max;
+        assert_eq!(compositor.compose().get(1, 0).ch, '\u{2803}');
+    }
+
+    #[test]
+    fn test_keymap() {
+        use crate::input::{InputEvent, KeyMap, StandardAction};
+        use crossterm::event::{KeyCode, KeyModifiers};
+        
+        let mut keymap = KeyMap::empty();
+        assert_eq!(keymap.bind(KeyCode::F(2), KeyModifiers::NONE, "new"), None);
+        keymap.bind(KeyCode::Right, KeyModifiers::CONTROL, "word");
+        assert_eq!(keymap.resolve(&InputEvent::Key(KeyCode::F(2))), Some(&"new"));
+        assert_eq!(keymap.resolve(&InputEvent::Ctrl(KeyCode::Right)), Some(&"word"));
+        // Stesso tasto, modificatori diversi
+        assert_eq!(keymap.resolve(&InputEvent::Key(KeyCode::Right)), None);
+        assert_eq!(keymap.resolve(&InputEvent::Quit), None);
+        assert_eq!(keymap.bind(KeyCode::F(2), KeyModifiers::NONE, "open"), Some("new"));
+        assert_eq!(keymap.unbind(KeyCode::F(2), KeyModifiers::NONE), Some("open"));
+        assert_eq!(keymap.len(), 1);
+        
+        let standard = KeyMap::<StandardAction>::default();
+        assert_eq!(standard.resolve(&InputEvent::Key(KeyCode::Enter)), Some(&StandardAction::Confirm));
+        assert_eq!(standard.resolve(&InputEvent::Key(KeyCode::BackTab)), Some(&StandardAction::PrevFocus));
+    }
+
+    #[test]
+    fn test_draw_panel_shadow() {
+        let mut fb = StyledFrameBuffer::new(8, 5);
+        fb.draw_rect(Rect::new(0, 0, 8, 5), '.', Some(Color::White), Some(Color::Rgb(200, 100, 0)));
+        fb.draw_panel(Rect::new(1, 1, 4, 3), BorderStyle::Rounded, Color::Yellow, Color::Blue, true);
+        
+        assert_eq!(fb.get(1, 1).ch, '╭');
+        assert_eq!(fb.get(4, 3).ch, '╯');
+        assert_eq!(fb.get(2, 1).ch, '─');
+        assert_eq!(fb.get(1, 1).bg_color, Some(Color::Blue));
+        assert_eq!(fb.get(2, 2), StyledChar::styled(' ', Color::Yellow, Color::Blue));
+        
+        // Ombra: contenuto invariato, colori attenuati, solo fuori dal pannello
+        let shadow = fb.get(6, 2);
+        assert_eq!(shadow.ch, '.');
+        assert_eq!(shadow.bg_color, Some(Color::Rgb(100, 50, 0)));
+        assert_eq!(fb.get(3, 4).bg_color, Some(Color::Rgb(100, 50, 0)));
+        assert_eq!(fb.get(2, 4).bg_color, Some(Color::Rgb(200, 100, 0)));
+        assert_eq!(fb.get(6, 0).bg_color, Some(Color::Rgb(200, 100, 0)));
+        
+        // Sfondo predefinito: diventa nero
+        let mut plain = StyledFrameBuffer::new(3, 3);
+        plain.draw_shadow(Rect::new(0, 0, 2, 2), (1, 1));
+        assert_eq!(plain.get(2, 2).bg_color, Some(Color::Black));
+        assert_eq!(plain.get(1, 1).bg_color, None);
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let mut fb = StyledFrameBuffer::new(40, 10);
+        fb.draw_rect(Rect::new(0, 0, 40, 10), ' ', None, Some(Color::Blue));
+        fb.draw_text(2, 1, "città ⠿", Some(Color::Rgb(1, 2, 3)), Some(Color::Reset));
+        fb.set(5, 5, StyledChar::builder().ch('e').combining('\u{301}').bold().reverse().build());
+        fb.set(39, 9, StyledChar::default());
+        
+        let bytes = fb.encode();
+        // Lo sfondo ripetuto occupa pochi run
+        assert!(bytes.len() < 100, "{} byte", bytes.len());
+        let decoded = StyledFrameBuffer::decode(&bytes).unwrap();
+        assert_eq!((decoded.width, decoded.height), (40, 10));
+        assert_eq!(decoded.data, fb.data);
+        
+        let empty = StyledFrameBuffer::new(0, 3);
+        assert_eq!(StyledFrameBuffer::decode(&empty.encode()).unwrap().height, 3);
+        
+        // Dati troncati o corrotti
+        assert!(StyledFrameBuffer::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(StyledFrameBuffer::decode(b"XXXX").is_err());
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(StyledFrameBuffer::decode(&extra).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_huge_sizes() {
+        // Intestazione, dimensioni e un run della stessa lunghezza di tutte le celle
+        let payload = |width: u64, height: u64, run: u64| {
+            let mut bytes = ENCODING_MAGIC.to_vec();
+            write_varint(&mut bytes, width);
+            write_varint(&mut bytes, height);
+            write_varint(&mut bytes, run);
+            bytes.extend_from_slice(&[0, b'x', 0, 0]);
+            bytes
+        };
+        
+        // Pochi byte che chiedono miliardi di celle: errore, non un'allocazione
+        let huge = payload(1 << 31, 1 << 31, 1 << 62);
+        assert!(huge.len() < 32);
+        assert!(matches!(StyledFrameBuffer::decode(&huge), Err(ConversionError::ImageTooLarge)));
+        let overflow = payload(u64::MAX, 2, u64::MAX);
+        assert!(matches!(StyledFrameBuffer::decode(&overflow), Err(ConversionError::ImageTooLarge)));
+        let over_cap = payload(MAX_DECODED_CELLS as u64 + 1, 1, MAX_DECODED_CELLS as u64 + 1);
+        assert!(matches!(StyledFrameBuffer::decode(&over_cap), Err(ConversionError::ImageTooLarge)));
+        
+        // Run più lungo del buffer dichiarato
+        let long_run = payload(4, 4, 1 << 40);
+        assert!(matches!(StyledFrameBuffer::decode(&long_run), Err(ConversionError::DecodeFailed(_))));
+        
+        // Un run entro il limite resta valido
+        let filled = StyledFrameBuffer::decode(&payload(8, 2, 16)).unwrap();
+        assert_eq!(filled.get(7, 1).ch, 'x');
+    }
+
+    #[test]
+    fn test_recorder_player() {
+        use crate::recorder::{Player, Recorder};
+        
+        let path = std::env::temp_dir().join(format!("stg_recording_{}.bin", std::process::id()));
+        let mut recorder = Recorder::new(&path);
+        let mut fb = StyledFrameBuffer::new(30, 10);
+        fb.fill_gradient(Rect::new(0, 0, 30, 10), Color::Black, Color::Blue, GradientDirection::Horizontal);
+        recorder.record(&fb, Duration::ZERO);
+        let keyframe_len = recorder.as_bytes().len();
+        
+        // Un solo carattere cambiato: il diff è molto più piccolo del keyframe
+        fb.set(12, 4, StyledChar::styled('x', Color::Yellow, Color::Red));
+        recorder.record(&fb, Duration::from_millis(16));
+        assert!(recorder.as_bytes().len() - keyframe_len < keyframe_len);
+        let second = fb.clone();
+        fb.resize(10, 3);
+        recorder.record(&fb, Duration::from_millis(33));
+        assert_eq!(recorder.frame_count(), 3);
+        recorder.finish().unwrap();
+        
+        let frames: Vec<_> = Player::open(&path).unwrap().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].0, Duration::from_millis(16));
+        assert_eq!(frames[1].1.data, second.data);
+        assert_eq!((frames[2].1.width, frames[2].1.height), (10, 3));
+        assert_eq!(frames[2].1.data, fb.data);
+        
+        assert!(Player::from_bytes(b"nope".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_window_manager() {
+        use crate::window_manager::{Window, WindowEvent, WindowManager};
+        use crossterm::event::{MouseButton, MouseEventKind};
+        
+        let down = MouseEventKind::Down(MouseButton::Left);
+        let drag = MouseEventKind::Drag(MouseButton::Left);
+        let up = MouseEventKind::Up(MouseButton::Left);
+        
+        let mut wm = WindowManager::new(40, 20);
+        let back = wm.open(Window::new("Back"), Rect::new(0, 0, 12, 6));
+        let front = wm.open(Window::new("Front").with_min_size(8, 4).with_max_size(20, 10), Rect::new(5, 2, 10, 5));
+        assert_eq!(wm.focused(), Some(front));
+        assert_eq!(wm.window_at(6, 3), Some(front));
+        
+        // Click sulla parte visibile di quella dietro: focus e primo piano
+        assert_eq!(wm.handle_mouse(1, 1, down), Some(WindowEvent::Focused(back)));
+        assert_eq!(wm.window_at(6, 3), Some(back));
+        wm.handle_mouse(1, 1, up);
+        
+        // Trascinamento della barra del titolo, limitato ai bounds
+        assert_eq!(wm.handle_mouse(13, 2, down), Some(WindowEvent::Focused(front)));
+        assert_eq!(wm.handle_mouse(16, 4, drag), Some(WindowEvent::Moved(front)));
+        assert_eq!(wm.rect(front), Some(Rect::new(8, 4, 10, 5)));
+        wm.handle_mouse(100, 100, drag);
+        assert_eq!(wm.rect(front), Some(Rect::new(30, 15, 10, 5)));
+        wm.handle_mouse(0, 0, up);
+        
+        // Ridimensionamento dall'angolo in basso a destra con limiti min/max
+        wm.handle_mouse(39, 19, down);
+        assert_eq!(wm.handle_mouse(32, 16, drag), Some(WindowEvent::Resized(front)));
+        assert_eq!(wm.rect(front), Some(Rect::new(30, 15, 8, 4)));
+        wm.handle_mouse(0, 0, up);
+        wm.set_rect(front, Rect::new(0, 0, 30, 30));
+        assert_eq!(wm.rect(front), Some(Rect::new(0, 0, 20, 10)));
+        
+        // Decorazioni e pulsante di chiusura
+        let mut fb = StyledFrameBuffer::new(40, 20);
+        wm.render_into(&mut fb);
+        assert_eq!(fb.get(0, 0).ch, '┌');
+        assert_eq!((2..7).map(|x| fb.get(x, 0).ch).collect::<String>(), "Front");
+        assert_eq!(fb.get(17, 0).ch, 'x');
+        assert_eq!(wm.handle_mouse(17, 0, down), Some(WindowEvent::CloseRequested(front)));
+        assert!(wm.close(front).is_some());
+        assert_eq!(wm.focused(), Some(back));
+        assert_eq!(wm.window_ids(), vec![back]);
+    }
 
-/// Ridimensiona e converte un'immagine in scala di grigi
-fn load_and_resize_image(img: &DynamicImage, max_width: u32, max_height: u32) -> GrayImage {
-    let img = img.to_luma8();
-    let (w, h) = img.dimensions();
-    
-    if w == 0 || h == 0 {
-        return GrayImage::new(1, 1);
+    #[test]
+    fn test_window_manager_focus_cycle() {
+        use crate::window_manager::{Window, WindowManager};
+        
+        let mut wm = WindowManager::new(40, 20);
+        let a = wm.open(Window::new("A"), Rect::new(0, 0, 10, 5));
+        let b = wm.open(Window::new("B"), Rect::new(5, 2, 10, 5));
+        let c = wm.open(Window::new("C"), Rect::new(10, 4, 10, 5));
+        assert_eq!(wm.focused(), Some(c));
+        
+        // Avanti: tutte e tre in ordine, poi si ricomincia
+        assert_eq!(wm.focus_next(), Some(a));
+        assert_eq!(wm.window_ids().last(), Some(&a));
+        assert_eq!(wm.focus_next(), Some(b));
+        assert_eq!(wm.focus_next(), Some(c));
+        assert_eq!(wm.focus_next(), Some(a));
+        
+        // Indietro ripercorre lo stesso giro al contrario
+        assert_eq!(wm.focus_prev(), Some(c));
+        assert_eq!(wm.focus_prev(), Some(b));
+        assert_eq!(wm.focus_prev(), Some(a));
+        assert_eq!(wm.focus_prev(), Some(c));
+        assert_eq!(wm.window_ids().last(), Some(&c));
+        
+        // Le finestre minimizzate vengono saltate
+        assert!(wm.set_minimized(b, true));
+        assert!(wm.is_minimized(b));
+        assert_eq!(wm.focus_next(), Some(a));
+        assert_eq!(wm.focus_next(), Some(c));
+        assert_eq!(wm.focus_next(), Some(a));
+        assert!(wm.set_minimized(a, true));
+        assert_eq!(wm.focused(), Some(c));
+        assert_eq!(wm.focus_next(), Some(c));
+        assert!(wm.set_minimized(b, false));
+        assert_eq!(wm.focused(), Some(b));
     }
-    
-    let scale_x = max_width as f32 / w as f32;
-    let scale_y = max_height as f32 / h as f32;
-    let scale = scale_x.min(scale_y).min(1.0);
-    let new_w = ((w as f32 * scale) as u32).max(1);
-    let new_h = ((h as f32 * scale) as u32).max(1);
-    
-    image::imageops::resize(&img, new_w, new_h, image::imageops::FilterType::Triangle)
-}
 
-/// Converte un'immagine in un framebuffer Braille
-pub fn image_to_braille_fb(img: &DynamicImage, max_width: usize, max_height: usize) -> Result<FrameBuffer, ConversionError> {
-    if max_width == 0 || max_height == 0 {
-        return Err(ConversionError::InvalidDimensions);
+    #[test]
+    fn test_sync_output_mode_report() {
+        use crate::input::parse_mode_report;
+        
+        // Risposte DECRPM al probe del modo 2026, seguite dalla risposta DA1
+        assert_eq!(parse_mode_report(b"\x1b[?2026;2$y\x1b[?62;22c", 2026), Some(true));
+        assert_eq!(parse_mode_report(b"\x1b[?2026;0$y\x1b[?62;22c", 2026), Some(false));
+        assert_eq!(parse_mode_report(b"\x1b[?62;22c", 2026), None);
+        assert_eq!(parse_mode_report(b"\x1b[?1000;1$y", 2026), None);
     }
-    
-    // Ogni carattere Braille rappresenta 2x4 pixel
-    let img = load_and_resize_image(img, (max_width * 2) as u32, (max_height * 4) as u32);
-    let (w, h) = img.dimensions();
-    let fb_w = (w as usize + 1) / 2;
-    let fb_h = (h as usize + 3) / 4;
-    let mut fb = FrameBuffer::new(fb_w, fb_h);
 
-    for by in 0..fb_h {
-        for bx in 0..fb_w {
-            let mut block = [0u8; 8];
-            for dy in 0..4 {
-                for dx in 0..2 {
-                    let px = if (bx * 2 + dx) < w as usize && (by * 4 + dy) < h as usize {
-                        img.get_pixel((bx * 2 + dx) as u32, (by * 4 + dy) as u32).0[0]
-                    } else {
-                        0
-                    };
-                    block[dx + dy * 2] = px;
-                }
-            }
-            let ch = pixels_to_braille(&block);
-            fb.set(bx, by, ch);
-        }
+    #[test]
+    fn test_frame_buffer_diff() {
+        let mut a = FrameBuffer::new(4, 2);
+        let mut b = a.clone();
+        b.set(1, 0, '⣿');
+        b.set(3, 1, '#');
+        
+        let diff = a.diff(&b);
+        assert_eq!(diff, vec![(1, 0, '⣿'), (3, 1, '#')]);
+        a.apply_diff(&diff);
+        assert_eq!(a.data, b.data);
+        assert!(a.diff(&b).is_empty());
+        
+        let mut sa = StyledFrameBuffer::new(4, 2);
+        let mut sb = sa.clone();
+        let cell = StyledChar::styled('x', Color::Red, Color::Blue);
+        sb.set(2, 1, cell);
+        assert_eq!(sa.diff(&sb), vec![(2, 1, cell)]);
+        sa.clear_dirty();
+        sa.apply_diff(&sa.diff(&sb));
+        assert_eq!(sa.get(2, 1), cell);
+        assert_eq!(sa.get_dirty_regions(), &[Rect::new(2, 1, 1, 1)]);
     }
-    Ok(fb)
-}
 
-/// Converte un'immagine in framebuffer Braille con soglia personalizzabile
-pub fn image_to_braille_fb_with_threshold(
-    img: &DynamicImage, 
-    max_width: usize, 
-    max_height: usize,
-    threshold: u8
-) -> Result<FrameBuffer, ConversionError> {
-    if max_width == 0 || max_height == 0 {
-        return Err(ConversionError::InvalidDimensions);
+    #[test]
+    fn test_lerp_srgb() {
+        // Il punto medio percepito tra nero e bianco è ~188, non 128
+        assert_eq!(Color::lerp_srgb(Color::Rgb(0, 0, 0), Color::Rgb(255, 255, 255), 0.5), Color::Rgb(188, 188, 188));
+        assert_eq!(Color::lerp(Color::Rgb(0, 0, 0), Color::Rgb(255, 255, 255), 0.5), Color::Rgb(128, 128, 128));
+        
+        // Estremi esatti, anche con colori della palette
+        assert_eq!(Color::lerp_srgb(Color::Red, Color::Blue, 0.0).to_rgb(), Color::Red.to_rgb());
+        assert_eq!(Color::lerp_srgb(Color::Red, Color::Blue, 1.0).to_rgb(), Color::Blue.to_rgb());
+        assert_eq!(Color::lerp_srgb(Color::Black, Color::White, 2.0).to_rgb(), Color::White.to_rgb());
     }
-    
-    let img = load_and_resize_image(img, (max_width * 2) as u32, (max_height * 4) as u32);
-    let (w, h) = img.dimensions();
-    let fb_w = (w as usize + 1) / 2;
-    let fb_h = (h as usize + 3) / 4;
-    let mut fb = FrameBuffer::new(fb_w, fb_h);
 
-    for by in 0..fb_h {
-        for bx in 0..fb_w {
-            let mut block = [0u8; 8];
-            for dy in 0..4 {
-                for dx in 0..2 {
-                    let px = if (bx * 2 + dx) < w as usize && (by * 4 + dy) < h as usize {
-                        img.get_pixel((bx * 2 + dx) as u32, (by * 4 + dy) as u32).0[0]
-                    } else {
-                        0
-                    };
-                    block[dx + dy * 2] = px;
-                }
-            }
-            let ch = pixels_to_braille_with_threshold(&block, threshold);
-            fb.set(bx, by, ch);
-        }
+    #[test]
+    fn test_image_to_braille_over() {
+        // Maschera 4x8: colonna di sinistra accesa in entrambe le celle
+        let mask = DynamicImage::ImageLuma8(GrayImage::from_fn(4, 8, |x, _| {
+            image::Luma([if x == 0 { 255 } else { 0 }])
+        }));
+        let scene = DynamicImage::ImageRgb8(RgbImage::from_fn(2, 2, |x, _| {
+            if x == 0 { image::Rgb([200, 0, 0]) } else { image::Rgb([0, 0, 200]) }
+        }));
+        
+        let fb = image_to_braille_over(&mask, &scene, 2, 2, 128).unwrap();
+        assert_eq!((fb.width, fb.height), (2, 2));
+        assert_eq!(fb.get(0, 0).ch, '⡇');
+        assert_eq!(fb.get(0, 0).fg_color, Some(Color::White));
+        assert_eq!(fb.get(0, 1).bg_color, Some(Color::Rgb(200, 0, 0)));
+        // Celle senza punti: Braille vuoto, ma con lo sfondo della scena
+        assert_eq!(fb.get(1, 0).ch, '\u{2800}');
+        assert_eq!(fb.get(1, 0).bg_color, Some(Color::Rgb(0, 0, 200)));
+        
+        assert!(matches!(image_to_braille_over(&mask, &scene, 0, 2, 128), Err(ConversionError::InvalidDimensions)));
     }
-    Ok(fb)
-}
 
-/// Sistema di gestione frame rate semplificato
-pub struct FrameTimer {
-    target_fps: u32,
-    frame_duration: Duration,
-    last_frame: Instant,
-    frame_count: u64,
-}
+    #[test]
+    fn test_compositor_layer_visibility() {
+        use crate::compositor::{Compositor, Layer};
+        
+        let mut compositor = Compositor::new(4, 2);
+        let mut panel = Layer::new(2, 1, 1, 0);
+        panel.buffer.clear_with(StyledChar::styled('#', Color::Red, Color::Blue));
+        let panel = compositor.add_layer(panel);
+        let removed = compositor.add_layer(Layer::new(1, 1, 3, 1));
+        compositor.remove_layer(removed);
+        
+        assert!(compositor.is_visible(panel));
+        assert!(compositor.set_visible(panel, false));
+        assert!(!compositor.is_visible(panel));
+        // Un layer nascosto non contribuisce alla composizione né all'hit test
+        assert!(compositor.compose().data.iter().all(|&cell| cell == StyledChar::default()));
+        assert_eq!(compositor.hit_top_layer(1, 0), None);
+        
+        assert!(compositor.set_visible(panel, true));
+        assert_eq!(compositor.compose().get(2, 0).ch, '#');
+        assert!(!compositor.set_visible(removed, true));
+        assert!(!compositor.is_visible(removed));
+    }
 
-impl FrameTimer {
-    pub fn new(target_fps: u32) -> Self {
-        let target_fps = target_fps.max(1).min(120); // Clamp tra 1 e 120 FPS
-        Self {
-            target_fps,
-            frame_duration: Duration::from_nanos(1_000_000_000 / target_fps as u64),
-            last_frame: Instant::now(),
-            frame_count: 0,
-        }
+    #[test]
+    fn test_write_to_reused_string() {
+        let mut fb = StyledFrameBuffer::new(4, 2);
+        fb.draw_text(0, 0, "ab", Some(Color::Red), Some(Color::Blue));
+        let last = fb.clone();
+        fb.set(1, 1, StyledChar::new('z').with_attributes(Attributes::BOLD));
+        
+        // Lo stesso buffer di output riusato tra due frame
+        let mut out = String::with_capacity(256);
+        fb.write_to(&mut out).unwrap();
+        assert_eq!(out, fb.to_string());
+        let capacity = out.capacity();
+        out.clear();
+        fb.write_partial_to(&last, &mut out).unwrap();
+        assert_eq!(out, fb.render_partial(&last));
+        assert!(out.starts_with("\x1b[2;1H"));
+        assert_eq!(out.capacity(), capacity);
+        
+        out.clear();
+        fb.write_partial_to(&StyledFrameBuffer::new(1, 1), &mut out).unwrap();
+        assert_eq!(out, fb.to_string());
     }
 
-    pub fn wait_for_next_frame(&mut self) {
-        let elapsed = self.last_frame.elapsed();
+    #[test]
+    fn test_pack_braille_luma() {
+        // 3x4 pixel: la colonna oltre la larghezza pari finisce in una seconda cella
+        let pixels = [
+            255, 0, 255,
+            0, 0, 0,
+            0, 0, 0,
+            0, 255, 0,
+        ];
+        let fb = pack_braille_luma(&pixels, 3, 4, 128);
+        assert_eq!((fb.width, fb.height), (2, 1));
+        assert_eq!(fb.get(0, 0), braille_from_dots([true, false, false, false, false, false, false, true]));
+        assert_eq!(fb.get(1, 0), braille_from_dots([true, false, false, false, false, false, false, false]));
+        
+        // Stesso risultato del percorso con `image`
+        let img = GrayImage::from_raw(3, 4, pixels.to_vec()).unwrap();
+        assert_eq!(pack_braille(&img, 128).data, fb.data);
+    }
+
+    #[test]
+    fn test_braille_char() {
+        assert_eq!(braille_char(0), '\u{2800}');
+        assert_eq!(braille_char(0xFF), '\u{28FF}');
         
-        if elapsed < self.frame_duration {
-            let sleep_time = self.frame_duration - elapsed;
-            std::thread::sleep(sleep_time);
+        // Coerente con braille_from_dots/braille_to_dots per tutti i 256 pattern
+        for bits in 0..=255u8 {
+            let ch = braille_char(bits);
+            let dots = braille_to_dots(ch);
+            assert_eq!(braille_from_dots(dots), ch);
+            assert_eq!(dots.iter().filter(|&&lit| lit).count() as u32, bits.count_ones());
         }
         
-        self.last_frame = Instant::now();
-        self.frame_count += 1;
+        // L'unione dei punti resta un glifo Braille anche con bit fuori maschera
+        let mut fb = FrameBuffer::new(1, 1);
+        fb.set(0, 0, braille_char(0b0000_0001));
+        let mut top = FrameBuffer::new(1, 1);
+        top.set(0, 0, braille_char(0b1000_0000));
+        fb.or_braille(&top, 0, 0);
+        assert_eq!(fb.get(0, 0), braille_char(0b1000_0001));
     }
 
-    pub fn get_fps(&self) -> f32 {
-        let elapsed = self.last_frame.elapsed();
-        if elapsed.as_secs_f32() > 0.001 {
-            (1.0 / elapsed.as_secs_f32()).min(self.target_fps as f32)
-        } else {
-            self.target_fps as f32
+    #[test]
+    fn test_terminal_position_checked() {
+        use crate::renderer::SmartRenderer;
+        
+        assert_eq!(SmartRenderer::terminal_position((2, 1), (80, 24), 0, 0), Some((2, 1)));
+        assert_eq!(SmartRenderer::terminal_position((2, 1), (80, 24), 77, 22), Some((79, 23)));
+        // Fuori dal terminale: la cella va saltata, non troncata
+        assert_eq!(SmartRenderer::terminal_position((2, 1), (80, 24), 78, 0), None);
+        assert_eq!(SmartRenderer::terminal_position((2, 1), (80, 24), 0, 23), None);
+        assert_eq!(SmartRenderer::terminal_position((0, 0), (u16::MAX, u16::MAX), 65_536 + 5, 0), None);
+        assert_eq!(SmartRenderer::terminal_position((usize::MAX, 0), (80, 24), 1, 0), None);
+    }
+
+    /// Output condiviso con il renderer, per ispezionare i byte inviati al terminale
+    #[derive(Clone, Default)]
+    struct SharedOutput(std::sync::Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
         }
     }
 
-    pub fn get_target_fps(&self) -> u32 {
-        self.target_fps
+    impl SharedOutput {
+        /// Byte ricevuti finora (svuotando l'output)
+        fn take(&self) -> String {
+            String::from_utf8_lossy(&std::mem::take(&mut *self.0.lock())).into_owned()
+        }
     }
 
-    pub fn get_frame_count(&self) -> u64 {
-        self.frame_count
+    /// Renderer a schermo intero su un terminale finto di `width`x`height` celle
+    fn test_renderer(width: u16, height: u16) -> (crate::renderer::SmartRenderer, SharedOutput) {
+        use crate::renderer::{LayoutMode, SmartRenderer};
+        
+        let out = SharedOutput::default();
+        let renderer = SmartRenderer::with_terminal_size(LayoutMode::Fullscreen, (width, height))
+            .with_output(Box::new(out.clone()));
+        (renderer, out)
     }
-}
 
-// Global buffer pools for memory reuse
-static BUFFER_POOL: Lazy<Mutex<Vec<Vec<char>>>> = Lazy::new(|| Mutex::new(Vec::new()));
-static STYLED_BUFFER_POOL: Lazy<Mutex<Vec<Vec<StyledChar>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+    /// Output che restituisce errore finché `fail` è attivo
+    #[derive(Clone, Default)]
+    struct FailingOutput {
+        out: SharedOutput,
+        fail: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use image::DynamicImage;
+    impl std::io::Write for FailingOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.fail.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "output chiuso"));
+            }
+            self.out.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            if self.fail.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "output chiuso"));
+            }
+            Ok(())
+        }
+    }
 
     #[test]
-    fn test_framebuffer_set_get() {
-        let mut fb = FrameBuffer::new(4, 2);
-        fb.set(1, 1, 'A');
-        assert_eq!(fb.get(1, 1), 'A');
-        assert_eq!(fb.get(0, 0), ' ');
+    fn test_present_keeps_dirty_on_error() {
+        use crate::renderer::{LayoutMode, SmartRenderer};
+        use std::sync::atomic::Ordering;
+        
+        let output = FailingOutput::default();
+        let mut renderer = SmartRenderer::with_terminal_size(LayoutMode::Fullscreen, (10, 4))
+            .with_output(Box::new(output.clone()));
+        renderer.present().unwrap();
+        output.out.take();
+        
+        // Scrittura fallita: il frame non risulta presentato
+        renderer.back_buffer_mut().draw_text(0, 1, "abc", None, None);
+        output.fail.store(true, Ordering::SeqCst);
+        assert!(renderer.present().is_err());
+        assert!(!renderer.back_buffer_mut().get_dirty_regions().is_empty());
+        
+        // Al tentativo successivo le celle mancanti vengono inviate
+        output.fail.store(false, Ordering::SeqCst);
+        renderer.present().unwrap();
+        assert!(output.out.take().contains("abc"));
+        assert!(renderer.back_buffer_mut().get_dirty_regions().is_empty());
+        assert_eq!(renderer.back_buffer_mut().get(1, 1).ch, 'b');
     }
 
     #[test]
-    fn test_framebuffer_clear() {
-        let mut fb = FrameBuffer::new(2, 2);
-        fb.set(0, 0, 'X');
-        fb.clear();
-        assert_eq!(fb.get(0, 0), ' ');
+    fn test_cursor_blink_follows_frames() {
+        let (mut renderer, out) = test_renderer(10, 4);
+        let mut fb = StyledFrameBuffer::new(10, 4);
+        fb.draw_text(0, 1, "abc", None, None);
+        
+        // 10 fps e periodo di 200 ms: due frame visibili, due nascosti
+        renderer.set_frame_rate(10);
+        renderer.set_cursor_blink(Some((1, 1, Duration::from_millis(200))));
+        let mut shown = Vec::new();
+        for _ in 0..5 {
+            renderer.render(&fb).unwrap();
+            shown.push(out.take().contains('\u{2588}'));
+        }
+        assert_eq!(shown, [true, true, false, false, true]);
+        
+        // Anche render_rect disegna il cursore e conta come frame
+        renderer.render_rect(&fb, Rect::new(5, 3, 2, 1)).unwrap();
+        assert!(out.take().contains('\u{2588}'));
+        renderer.render_rect(&fb, Rect::new(5, 3, 2, 1)).unwrap();
+        let hidden = out.take();
+        assert!(!hidden.contains('\u{2588}'));
+        // Nella fase nascosta la cella torna al suo contenuto
+        assert!(hidden.contains('b'));
     }
 
     #[test]
-    fn test_framebuffer_to_string() {
-        let mut fb = FrameBuffer::new(2, 2);
-        fb.set(0, 0, 'A');
-        fb.set(1, 1, 'B');
-        let result = fb.to_string();
-        assert_eq!(result, "A \n B");
+    fn test_coalesced_flush_writes_last_frame() {
+        use crate::renderer::FlushPolicy;
+        
+        let (mut renderer, out) = test_renderer(10, 2);
+        renderer.set_flush_policy(FlushPolicy::Coalesced { max_delay: Duration::from_millis(50) });
+        renderer.flush().unwrap();
+        let mut fb = StyledFrameBuffer::new(10, 2);
+        
+        // Entro max_delay il frame resta in attesa
+        fb.draw_text(0, 0, "uno", None, None);
+        renderer.render(&fb).unwrap();
+        assert!(out.take().is_empty());
+        assert!(renderer.has_pending_output());
+        
+        // Scaduto max_delay, il rendering successivo invia prima il frame in attesa
+        std::thread::sleep(Duration::from_millis(60));
+        fb.draw_text(0, 0, "due", None, None);
+        renderer.mark_dirty(Rect::new(0, 0, 3, 1));
+        renderer.render(&fb).unwrap();
+        let sent = out.take();
+        assert!(sent.contains('u') && !sent.contains('d'));
+        assert!(renderer.has_pending_output());
+        
+        // L'ultimo frame della raffica arriva con il flush esplicito
+        renderer.flush().unwrap();
+        assert!(out.take().contains("due"));
+        assert!(!renderer.has_pending_output());
     }
 
     #[test]
-    fn test_image_to_braille_fb() {
-        let img = DynamicImage::new_luma8(4, 8);
-        let fb = image_to_braille_fb(&img, 2, 2).unwrap();
-        assert_eq!(fb.width, 2);
-        assert_eq!(fb.height, 2);
+    fn test_dirty_sampling_full_catches_single_cell() {
+        use crate::renderer::DirtySampling;
+        
+        // Due pagine 8x8: (3,3) non è tra i punti campionati
+        let (mut renderer, out) = test_renderer(16, 8);
+        renderer.set_page_size(8);
+        let mut fb = StyledFrameBuffer::new(16, 8);
+        renderer.render_paged(&fb).unwrap();
+        out.take();
+        
+        // Confronto completo (default): la pagina viene ridisegnata
+        fb.set(3, 3, StyledChar::new('X'));
+        renderer.render_paged(&fb).unwrap();
+        assert_eq!(renderer.last_stats().regions, 1);
+        assert!(out.take().contains('X'));
+        
+        // Con il campionamento la stessa modifica va persa
+        renderer.set_dirty_sampling(DirtySampling::Sampled);
+        fb.set(3, 3, StyledChar::new('Y'));
+        renderer.render_paged(&fb).unwrap();
+        assert_eq!(renderer.last_stats().regions, 0);
+        assert!(!out.take().contains('Y'));
     }
 
     #[test]
-    fn test_invalid_dimensions() {
-        let img = DynamicImage::new_luma8(4, 8);
-        assert!(image_to_braille_fb(&img, 0, 2).is_err());
+    fn test_incremental_render_skips_redundant_move() {
+        // Sequenze di posizionamento assoluto (CSI riga;colonna H) nell'output
+        let moves = |output: &str| {
+            output
+                .split("\x1b[")
+                .filter(|seq| seq.split_once('H').is_some_and(|(args, _)| {
+                    !args.is_empty() && args.chars().all(|c| c.is_ascii_digit() || c == ';')
+                }))
+                .count()
+        };
+        
+        // Primo frame completo, poi le regioni indicate su una riga cambiata
+        let render_regions = |regions: &[Rect]| {
+            let (mut renderer, out) = test_renderer(20, 3);
+            let mut fb = StyledFrameBuffer::new(20, 3);
+            renderer.render(&fb).unwrap();
+            out.take();
+            fb.draw_text(2, 1, "abcdef", None, None);
+            for &region in regions {
+                renderer.mark_dirty(region);
+            }
+            renderer.render(&fb).unwrap();
+            assert_eq!(renderer.last_stats().regions, regions.len());
+            out.take()
+        };
+        
+        // Due regioni contigue sulla stessa riga: un solo MoveTo, scrittura continua
+        let contiguous = render_regions(&[Rect::new(2, 1, 3, 1), Rect::new(5, 1, 3, 1)]);
+        assert_eq!(moves(&contiguous), 1);
+        assert!(contiguous.contains("abcdef"));
+        
+        // Con un buco tra le regioni il secondo MoveTo serve
+        let apart = render_regions(&[Rect::new(2, 1, 3, 1), Rect::new(6, 1, 2, 1)]);
+        assert_eq!(moves(&apart), 2);
+        assert!(apart.len() > contiguous.len());
     }
 
     #[test]
-    fn test_blit() {
-        let mut src = FrameBuffer::new(2, 2);
-        src.set(0, 0, 'A');
-        src.set(1, 1, 'B');
+    fn test_skip_unchanged_compares_cells() {
+        let (mut renderer, out) = test_renderer(10, 3);
+        renderer.set_skip_unchanged(true);
+        let mut fb = StyledFrameBuffer::new(10, 3);
+        fb.draw_text(0, 0, "ciao", None, None);
+        renderer.render(&fb).unwrap();
+        out.take();
         
-        let mut dst = FrameBuffer::new(4, 4);
-        dst.blit(&src, 0, 0, 1, 1, 2, 2);
+        // Frame identico: le regioni segnate vengono scartate senza scrivere celle
+        renderer.mark_dirty(Rect::new(0, 0, 10, 3));
+        renderer.render(&fb).unwrap();
+        assert_eq!(renderer.last_stats().cells_written, 0);
+        assert!(!out.take().contains('c'));
         
-        assert_eq!(dst.get(1, 1), 'A');
-        assert_eq!(dst.get(2, 2), 'B');
+        // Una sola cella diversa basta per ridisegnare
+        fb.set(9, 2, StyledChar::new('!'));
+        renderer.mark_dirty(Rect::new(9, 2, 1, 1));
+        renderer.render(&fb).unwrap();
+        assert_eq!(renderer.last_stats().cells_written, 1);
+        assert!(out.take().contains('!'));
     }
 
     #[test]
-    fn test_styled_char() {
-        let styled = StyledChar::new('A').with_fg(Color::Red).with_bg(Color::Blue);
-        let output = styled.to_string();
-        assert!(output.contains('A'));
-        assert!(output.contains("\x1b[31m")); // Red foreground
-        assert!(output.contains("\x1b[44m")); // Blue background
+    fn test_resize_between_frames_redraws_everything() {
+        let (mut renderer, out) = test_renderer(20, 6);
+        let mut fb = StyledFrameBuffer::new(20, 6);
+        renderer.render(&fb).unwrap();
+        fb.draw_text(0, 1, "abcdef", None, None);
+        renderer.mark_dirty(Rect::new(0, 1, 6, 1));
+        renderer.render(&fb).unwrap();
+        assert!(!renderer.last_stats().full_refresh);
+        out.take();
+        
+        // Dopo il ridimensionamento il frame successivo è un refresh completo,
+        // qualunque sia il percorso di rendering
+        renderer.update_terminal_size((10, 4)).unwrap();
+        assert_eq!(renderer.get_workspace_size(), (10, 4));
+        let mut small = StyledFrameBuffer::new(10, 4);
+        small.draw_text(0, 1, "abcdef", None, None);
+        renderer.render_using_dirty(&mut small).unwrap();
+        assert!(renderer.last_stats().full_refresh);
+        let redraw = out.take();
+        assert!(redraw.contains("\x1b[2J"));
+        assert!(['a', 'b', 'c', 'd', 'e', 'f'].iter().all(|&ch| redraw.contains(ch)));
+        
+        // Le regioni del frame prima del resize non vengono più ridisegnate
+        small.draw_text(0, 1, "zzzzzz", None, None);
+        small.set(0, 3, StyledChar::new('#'));
+        renderer.mark_dirty(Rect::new(0, 3, 1, 1));
+        renderer.render(&small).unwrap();
+        let stats = renderer.last_stats();
+        assert!(!stats.full_refresh);
+        assert_eq!((stats.regions, stats.cells_written), (1, 1));
+        assert!(!out.take().contains('z'));
     }
 
     #[test]
-    fn test_rect_contains() {
-        let rect = Rect::new(5, 5, 10, 10);
-        assert!(rect.contains(10, 10));
-        assert!(!rect.contains(0, 0));
-        assert!(!rect.contains(20, 20));
+    fn test_braille_fb_from_luma_exact() {
+        // 4x8 pixel = 2x2 celle, nessun ridimensionamento
+        let img = GrayImage::from_fn(4, 8, |x, y| if x == y { image::Luma([255]) } else { image::Luma([0]) });
+        let fb = braille_fb_from_luma_exact(&img);
+        assert_eq!((fb.width, fb.height), (2, 2));
+        assert_eq!(fb.get(0, 0), braille_from_dots([true, false, false, false, true, false, false, false]));
+        assert_eq!(fb.get(1, 0), braille_from_dots([false, false, true, false, false, false, false, true]));
+        assert_eq!(fb.get(0, 1), braille_char(0));
+        assert_eq!(fb.get(1, 1), braille_char(0));
+        
+        // Stessi punti della conversione con ridimensionamento alla stessa misura
+        let resized = image_to_braille_fb(&DynamicImage::ImageLuma8(img), 2, 2).unwrap();
+        assert_eq!(resized.data, fb.data);
     }
 
     #[test]
-    fn test_styled_framebuffer() {
-        let mut fb = StyledFrameBuffer::new(10, 10);
-        let styled_char = StyledChar::new('X').with_fg(Color::Red);
-        fb.set(5, 5, styled_char);
-        assert_eq!(fb.get(5, 5).ch, 'X');
-        assert_eq!(fb.get(5, 5).fg_color, Some(Color::Red));
+    fn test_draw_border_colors() {
+        let mut sfb = StyledFrameBuffer::new(6, 4);
+        sfb.draw_border(Rect::new(0, 0, 6, 4), Some(Color::Red), Some(Color::Blue));
+        
+        // Sfondo richiesto su tutte le celle del bordo, interno intatto
+        for (x, y) in [(0, 0), (3, 0), (5, 0), (0, 2), (5, 2), (0, 3), (2, 3), (5, 3)] {
+            let cell = sfb.get(x, y);
+            assert_eq!(cell.bg_color, Some(Color::Blue), "cella ({}, {})", x, y);
+            assert_eq!(cell.fg_color, Some(Color::Red));
+        }
+        assert_eq!(sfb.get(0, 0).ch, '+');
+        assert_eq!(sfb.get(2, 1), StyledChar::default());
+        
+        // Angoli con colore d'accento, lati con quello del bordo
+        let options = BorderOptions { style: BorderStyle::Single, corner_color: Some(Color::Yellow) };
+        sfb.draw_border_with(Rect::new(0, 0, 6, 4), Some(Color::Red), Some(Color::Blue), options);
+        assert_eq!(sfb.get(0, 0), StyledChar::styled('┌', Color::Yellow, Color::Blue));
+        assert_eq!(sfb.get(5, 3), StyledChar::styled('┘', Color::Yellow, Color::Blue));
+        assert_eq!(sfb.get(2, 0), StyledChar::styled('─', Color::Red, Color::Blue));
+        assert_eq!(sfb.get(0, 1), StyledChar::styled('│', Color::Red, Color::Blue));
+    }
+
+    #[test]
+    fn test_transparent_color() {
+        use crate::compositor::{Compositor, Layer};
+        
+        let clear = StyledChar { bg_color: Some(Color::Transparent), ..StyledChar::new(' ') };
+        let glyph = StyledChar { ch: 'x', fg_color: Some(Color::Red), ..clear };
+        assert!(clear.is_transparent());
+        assert!(!glyph.is_transparent());
+        // Nessuna sequenza per lo sfondo trasparente
+        assert_eq!(clear.to_string(), " ");
+        assert_eq!(glyph.to_string(), "\x1b[31mx\x1b[0m");
+        
+        // blit salta le celle trasparenti e tiene lo sfondo di destinazione
+        let mut src = StyledFrameBuffer::new(2, 1);
+        src.set(0, 0, clear);
+        src.set(1, 0, glyph);
+        let mut dst = StyledFrameBuffer::new(2, 1);
+        dst.clear_with(StyledChar::styled('.', Color::White, Color::Blue));
+        dst.blit(&src, Rect::new(0, 0, 2, 1), 0, 0);
+        assert_eq!(dst.get(0, 0), StyledChar::styled('.', Color::White, Color::Blue));
+        assert_eq!(dst.get(1, 0), StyledChar::styled('x', Color::Red, Color::Blue));
+        
+        // Stesso risultato nella composizione dei layer
+        let mut compositor = Compositor::new(2, 1);
+        let mut background = Layer::new(2, 1, 0, 0);
+        background.buffer.clear_with(StyledChar::styled('.', Color::White, Color::Blue));
+        compositor.add_layer(background);
+        let mut overlay = Layer::new(2, 1, 0, 0);
+        overlay.buffer.copy_rect(&src, Rect::new(0, 0, 2, 1), 0, 0);
+        compositor.add_layer(overlay);
+        assert_eq!(compositor.compose().data, dst.data);
+        
+        // Dopo uno sfondo blu, il trasparente torna allo sfondo di default
+        let mut row = StyledFrameBuffer::new(2, 1);
+        row.set(0, 0, StyledChar::styled('a', Color::White, Color::Blue));
+        row.set(1, 0, StyledChar { bg_color: Some(Color::Transparent), ..StyledChar::new('b') });
+        assert!(row.to_string().contains("a\x1b[39m\x1b[49mb"));
+        
+        // Il formato binario conserva il colore
+        let decoded = StyledFrameBuffer::decode(&src.encode()).unwrap();
+        assert_eq!(decoded.data, src.data);
+    }
+
+    #[test]
+    fn test_text_buffer_reflow() {
+        use crate::ui::TextBuffer;
+        
+        let mut text = TextBuffer::new(10);
+        text.set_text("uno due tre quattro\n\ncinque");
+        assert_eq!(text.wrapped_lines(), ["uno due ", "tre ", "quattro", "", "cinque"]);
+        
+        // Allargando la finestra il testo si ridistribuisce, le righe logiche restano
+        let mut fb = StyledFrameBuffer::new(20, 4);
+        assert_eq!(text.render_into(&mut fb, Rect::new(0, 0, 15, 4)), 4);
+        assert_eq!(text.width(), 15);
+        assert_eq!(text.lines().len(), 3);
+        let row = |fb: &StyledFrameBuffer, y: usize, width: usize| (0..width).map(|x| fb.get(x, y).ch).collect::<String>();
+        assert_eq!(row(&fb, 0, 15).trim_end(), "uno due tre");
+        assert_eq!(row(&fb, 1, 15).trim_end(), "quattro");
+        
+        // Restringendo, dentro il rect le righe vecchie più lunghe vengono cancellate
+        assert_eq!(text.render_into(&mut fb, Rect::new(0, 0, 8, 4)), 4);
+        assert_eq!(row(&fb, 0, 8), "uno due ");
+        assert_eq!(row(&fb, 1, 8), "tre     ");
+        
+        // Le righe aggiunte estendono la vista già calcolata
+        text.push_line("sei sette");
+        assert_eq!(text.wrapped_lines().last().map(String::as_str), Some("sette"));
+        assert_eq!(text.wrapped_lines().len(), 7);
+        
+        // Lo scroll viene limitato all'ultima pagina
+        text.set_scroll(100);
+        assert_eq!(text.render_into(&mut fb, Rect::new(0, 0, 8, 4)), 4);
+        assert_eq!(text.scroll(), 3);
+        assert_eq!(row(&fb, 3, 8).trim_end(), "sette");
+    }
+
+    #[test]
+    fn test_text_buffer_rewrap_keeps_text() {
+        use crate::ui::TextBuffer;
+        
+        let source = "    fn main() {\n        let  x = 1;    // commento\n\n\tfine  ";
+        let mut text = TextBuffer::new(40);
+        text.set_text(source);
+        let logical: String = text.lines().concat();
+        
+        // A ogni larghezza le righe riunite ridanno il testo originale, spazi compresi
+        for width in [40, 12, 7, 3, 1, 40] {
+            text.set_width(width);
+            let rows = text.wrapped_lines();
+            assert_eq!(rows.concat(), logical, "larghezza {}", width);
+            for row in rows {
+                assert!(row.trim_end().chars().count() <= width, "larghezza {}: {:?}", width, row);
+            }
+        }
+        
+        // Nessuna riga fantasma: parole lunghe quanto la riga e spazi al punto di a capo
+        text.set_text("abcd efgh");
+        text.set_width(4);
+        assert_eq!(text.wrapped_lines(), ["abcd ", "efgh"]);
+        text.set_width(9);
+        assert_eq!(text.wrapped_lines(), ["abcd efgh"]);
+        
+        // Gli spazi appesi oltre la larghezza non escono dal rect
+        let mut fb = StyledFrameBuffer::new(6, 2);
+        fb.set(4, 0, StyledChar::new('#'));
+        text.render_into(&mut fb, Rect::new(0, 0, 4, 2));
+        assert_eq!(fb.get(4, 0).ch, '#');
     }
 
     #[test]
@@ -1000,4 +5451,56 @@ mod tests {
         assert_eq!(timer.target_fps, 60);
         // Non testiamo wait_for_next_frame per evitare rallentamenti nei test
     }
+
+    #[test]
+    fn test_frame_timer_high_precision() {
+        let mut timer = FrameTimer::new(120);
+        assert_eq!(timer.precision(), Precision::PowerSave);
+        timer.set_precision(Precision::HighPrecision);
+        
+        // Lo spin-wait finale non anticipa mai la scadenza
+        let start = Instant::now();
+        timer.wait_for_next_tick();
+        timer.wait_for_next_tick();
+        assert!(start.elapsed() >= Duration::from_nanos(1_000_000_000 / 120));
+        assert_eq!(timer.get_frame_count(), 2);
+    }
+
+    #[test]
+    fn test_frame_timer_catch_up() {
+        let mut timer = FrameTimer::new(120);
+        timer.set_target_fps(0);
+        assert_eq!(timer.get_target_fps(), 1);
+        timer.set_target_fps(100);
+        assert_eq!(timer.frame_duration, Duration::from_millis(10));
+        
+        // Simula un frame durato ~3 frame oltre la scadenza
+        timer.next_frame = Instant::now() - Duration::from_millis(35);
+        assert!(timer.wait_for_next_tick() >= 4);
+        assert_eq!(timer.get_dropped_frames(), 0);
+        
+        timer.set_skip_if_behind(true);
+        timer.next_frame = Instant::now() - Duration::from_millis(35);
+        assert_eq!(timer.wait_for_next_tick(), 1);
+        assert!(timer.get_dropped_frames() >= 3);
+        assert_eq!(timer.get_frame_count(), 2);
+    }
+
+    #[test]
+    fn test_frame_timer_rolling_fps() {
+        let mut timer = FrameTimer::new(60);
+        assert_eq!(timer.get_fps(), 0.0);
+        
+        // Frame lenti: gli FPS non vengono più limitati al target
+        for _ in 0..FPS_SAMPLE_WINDOW {
+            timer.record_frame(Duration::from_millis(50));
+        }
+        assert!((timer.get_fps() - 20.0).abs() < 0.01);
+        
+        // Solo gli ultimi FPS_SAMPLE_WINDOW campioni contano
+        for _ in 0..FPS_SAMPLE_WINDOW {
+            timer.record_frame(Duration::from_millis(5));
+        }
+        assert!((timer.get_fps() - 200.0).abs() < 0.1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+//! Session recording: capture rendered frames to a file and play them back
+
+use crate::{write_varint, ByteReader, ConversionError, Rect, StyledFrameBuffer};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Header of a recording file (format version 1)
+const RECORDING_MAGIC: &[u8; 5] = b"STGR1";
+
+/// Frame stored whole, used for the first frame and after a resize
+const KEYFRAME: u8 = 0;
+/// Frame stored as the regions changed since the previous one
+const DIFF: u8 = 1;
+
+/// Block size for `get_changed_regions_with`: small blocks keep diffs tight
+const DIFF_BLOCK_SIZE: usize = 8;
+
+/// Accumulates frames in memory and writes them to `path` on `finish`.
+/// Only the regions that changed since the previous frame are stored.
+pub struct Recorder {
+    path: PathBuf,
+    data: Vec<u8>,
+    previous: Option<StyledFrameBuffer>,
+    frames: usize,
+}
+
+impl Recorder {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            data: RECORDING_MAGIC.to_vec(),
+            previous: None,
+            frames: 0,
+        }
+    }
+
+    /// Add a frame shown at `timestamp` (from the start of the recording)
+    pub fn record(&mut self, frame: &StyledFrameBuffer, timestamp: Duration) {
+        let mut payload = Vec::new();
+        let kind = match &mut self.previous {
+            Some(previous) if previous.width == frame.width && previous.height == frame.height => {
+                let regions = frame.get_changed_regions_with(previous, DIFF_BLOCK_SIZE);
+                write_varint(&mut payload, regions.len() as u64);
+                for region in regions {
+                    let mut patch = StyledFrameBuffer::new(region.width, region.height);
+                    patch.copy_rect(frame, region, 0, 0);
+                    let encoded = patch.encode();
+                    write_varint(&mut payload, region.x as u64);
+                    write_varint(&mut payload, region.y as u64);
+                    write_varint(&mut payload, encoded.len() as u64);
+                    payload.extend_from_slice(&encoded);
+                }
+                frame.clone_into(previous);
+                DIFF
+            }
+            _ => {
+                payload = frame.encode();
+                self.previous = Some(frame.clone());
+                KEYFRAME
+            }
+        };
+        
+        write_varint(&mut self.data, timestamp.as_micros() as u64);
+        self.data.push(kind);
+        write_varint(&mut self.data, payload.len() as u64);
+        self.data.extend_from_slice(&payload);
+        self.frames += 1;
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames
+    }
+
+    /// Recorded bytes so far, in the file format
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Write the recording to its file
+    pub fn finish(self) -> crate::Result<()> {
+        std::fs::write(&self.path, &self.data)?;
+        Ok(())
+    }
+}
+
+/// Reads a recording and yields its frames in order, rebuilding diffs on top
+/// of the previous frame. A corrupted frame yields an error and ends playback.
+pub struct Player {
+    data: Vec<u8>,
+    pos: usize,
+    current: Option<StyledFrameBuffer>,
+    failed: bool,
+}
+
+impl Player {
+    pub fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Ok(Self::from_bytes(std::fs::read(path)?)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, ConversionError> {
+        if !data.starts_with(RECORDING_MAGIC) {
+            return Err(ConversionError::DecodeFailed("not a recording".to_string()));
+        }
+        Ok(Self {
+            data,
+            pos: RECORDING_MAGIC.len(),
+            current: None,
+            failed: false,
+        })
+    }
+
+    fn next_frame(&mut self) -> Result<(Duration, StyledFrameBuffer), ConversionError> {
+        let mut reader = ByteReader { bytes: &self.data, pos: self.pos };
+        let timestamp = Duration::from_micros(reader.varint()?);
+        let kind = reader.byte()?;
+        let len = reader.varint()? as usize;
+        let payload = self.data.get(reader.pos..reader.pos.saturating_add(len))
+            .ok_or_else(|| ConversionError::DecodeFailed("truncated frame".to_string()))?;
+        self.pos = reader.pos + len;
+        
+        let frame = match (kind, self.current.take()) {
+            (KEYFRAME, _) => StyledFrameBuffer::decode(payload)?,
+            (DIFF, Some(mut frame)) => {
+                let mut reader = ByteReader { bytes: payload, pos: 0 };
+                for _ in 0..reader.varint()? {
+                    let x = reader.varint()? as usize;
+                    let y = reader.varint()? as usize;
+                    let len = reader.varint()? as usize;
+                    let encoded = payload.get(reader.pos..reader.pos.saturating_add(len))
+                        .ok_or_else(|| ConversionError::DecodeFailed("truncated region".to_string()))?;
+                    reader.pos += len;
+                    let patch = StyledFrameBuffer::decode(encoded)?;
+                    frame.copy_rect(&patch, Rect::new(0, 0, patch.width, patch.height), x, y);
+                }
+                frame
+            }
+            (DIFF, None) => return Err(ConversionError::DecodeFailed("diff without keyframe".to_string())),
+            (kind, _) => return Err(ConversionError::DecodeFailed(format!("unknown frame kind {}", kind))),
+        };
+        self.current = Some(frame.clone());
+        Ok((timestamp, frame))
+    }
+}
+
+impl Iterator for Player {
+    type Item = Result<(Duration, StyledFrameBuffer), ConversionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos >= self.data.len() {
+            return None;
+        }
+        let frame = self.next_frame();
+        self.failed = frame.is_err();
+        Some(frame)
+    }
+}
@@ -1,8 +1,8 @@
 //! Sistema di rendering intelligente con gestione ottimizzata del framebuffer
 
-use crate::{StyledFrameBuffer, Rect, StyledChar};
+use crate::{Result, StyledFrameBuffer, Rect, StyledChar};
 use std::io::{self, Write, stdout};
-use crossterm::{cursor, terminal, ExecutableCommand};
+use crossterm::{cursor, terminal, ExecutableCommand, QueueableCommand};
 use rayon::prelude::*;
 use parking_lot::RwLock;
 use std::sync::Arc;
@@ -38,17 +38,143 @@ impl StyledChar {
         let mut codes = String::new();
         
         if let Some(fg) = self.fg_color {
-            codes.push_str(fg.to_ansi_fg());
+            codes.push_str(&fg.to_ansi_fg());
         }
         
         if let Some(bg) = self.bg_color {
-            codes.push_str(bg.to_ansi_bg());
+            codes.push_str(&bg.to_ansi_bg());
         }
         
+        codes.push_str(&self.attributes.to_ansi());
+        
         codes
     }
 }
 
+/// Oltre questo numero di regioni segnate tra due rendering, `mark_dirty` le accorpa
+const MAX_DIRTY_REGIONS: usize = 64;
+
+/// Lavoro svolto dall'ultimo rendering, per profilare le regioni dirty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderStats {
+    /// Celle riscritte sul terminale
+    pub cells_written: usize,
+    /// Regioni (o pagine) effettivamente emesse
+    pub regions: usize,
+    /// Byte inviati all'output, sequenze di escape comprese
+    pub bytes_emitted: usize,
+    /// Il frame è stato un refresh completo
+    pub full_refresh: bool,
+}
+
+/// Output che conta i byte scritti, per `RenderStats::bytes_emitted`. I byte
+/// restano in `pending` fino al `flush`, così la `FlushPolicy` decide quando
+/// arrivano al terminale.
+struct CountingWriter {
+    inner: Box<dyn Write + Send>,
+    bytes: usize,
+    pending: Vec<u8>,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.bytes += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            self.inner.write_all(&self.pending)?;
+            self.pending.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+/// Quando l'output dei frame viene inviato al terminale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Un flush alla fine di ogni rendering
+    #[default]
+    PerFrame,
+    /// I rendering entro `max_delay` dall'ultimo flush si accumulano; l'output
+    /// parte all'inizio del primo rendering dopo la scadenza, o con `flush`
+    /// (da chiamare prima che l'app resti inattiva)
+    Coalesced { max_delay: Duration },
+}
+
+/// Forma del cursore reale del terminale (DECSCUSR, varianti lampeggianti)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorStyle {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+}
+
+impl CursorStyle {
+    /// Sequenza DECSCUSR per impostare la forma del cursore
+    pub fn to_escape(&self) -> &'static str {
+        match self {
+            CursorStyle::Block => "\x1b[1 q",
+            CursorStyle::Underline => "\x1b[3 q",
+            CursorStyle::Bar => "\x1b[5 q",
+        }
+    }
+}
+
+/// Come `render_paged` decide se una pagina è cambiata rispetto al frame precedente
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DirtySampling {
+    /// Confronto di ogni cella della pagina: nessuna modifica va persa
+    #[default]
+    Full,
+    /// Confronto di soli 4 punti per pagina: più veloce su pagine grandi, ma una
+    /// modifica lontana dai punti campionati lascia a schermo contenuto vecchio
+    Sampled,
+}
+
+/// Disposizione del workspace nel terminale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Workspace centrato con bordo; `margin` celle riservate per asse (metà per lato),
+    /// con dimensione minima 40x20
+    Centered { margin: usize },
+    /// Workspace grande quanto il terminale, senza bordo, offset (0, 0)
+    Fullscreen,
+    /// Tutto il terminale, di qualunque dimensione: una cella di margine in alto e a
+    /// sinistra per il bordo (se il terminale ha almeno 2 righe/colonne), e il
+    /// workspace arriva fino all'ultima riga e colonna (dimensione + offset = terminale)
+    FitExact,
+}
+
+impl Default for LayoutMode {
+    fn default() -> Self {
+        LayoutMode::Centered { margin: 4 }
+    }
+}
+
+impl LayoutMode {
+    /// Calcola (dimensioni, offset) del workspace per un terminale di queste dimensioni
+    pub fn workspace_layout(&self, terminal_size: (u16, u16)) -> ((usize, usize), (usize, usize)) {
+        let (term_w, term_h) = (terminal_size.0 as usize, terminal_size.1 as usize);
+        match *self {
+            LayoutMode::Centered { margin } => {
+                let width = term_w.saturating_sub(margin).max(40);
+                let height = term_h.saturating_sub(margin).max(20);
+                let offset = (term_w.saturating_sub(width) / 2, term_h.saturating_sub(height) / 2);
+                ((width, height), offset)
+            }
+            LayoutMode::Fullscreen => ((term_w, term_h), (0, 0)),
+            LayoutMode::FitExact => {
+                let offset = (usize::from(term_w > 1), usize::from(term_h > 1));
+                ((term_w - offset.0, term_h - offset.1), offset)
+            }
+        }
+    }
+}
+
 /// Gestore rendering con ottimizzazioni intelligenti e paging
 pub struct SmartRenderer {
     /// Dimensioni del terminale reale
@@ -59,8 +185,13 @@ pub struct SmartRenderer {
     workspace_offset: (usize, usize),
     /// Buffer precedente per confronto
     last_buffer: StyledFrameBuffer,
+    /// Buffer in cui l'app disegna direttamente, presentato con `present`
+    back_buffer: StyledFrameBuffer,
     /// Regioni dirty ottimizzate
     dirty_regions: Vec<Rect>,
+    /// Regioni dirty del frame precedente: ridisegnate anche nel frame successivo
+    /// così il contenuto spostato non lascia "fantasmi" nella posizione lasciata
+    previous_regions: Vec<Rect>,
     /// Modalità rendering (completo o parziale)
     force_full_refresh: bool,
     /// Sistema di paging per grandi framebuffer
@@ -73,65 +204,375 @@ pub struct SmartRenderer {
     /// Coda di regioni da renderizzare
     #[allow(dead_code)]
     render_queue: Arc<RwLock<VecDeque<Rect>>>,
+    /// Il terminale supporta gli attributi di riga DEC (doppia larghezza/altezza)
+    line_attributes: bool,
+    /// Posizione (workspace) e forma del cursore di testo reale, `None` = nascosto
+    text_cursor: Option<(usize, usize)>,
+    cursor_style: CursorStyle,
+    layout: LayoutMode,
+    /// Cursore lampeggiante disegnato dal renderer: (x, y, periodo) e frame di attivazione
+    cursor_blink: Option<(usize, usize, Duration)>,
+    blink_start: u64,
+    /// Frame renderizzati finora: scandiscono il lampeggio al posto dell'orologio
+    frame_count: u64,
+    /// Durata nominale di un frame, per convertire il periodo del lampeggio in frame
+    frame_duration: Duration,
+    /// Cella lasciata dal cursore lampeggiante, da ridisegnare al prossimo frame
+    blink_restore: Option<(usize, usize)>,
+    /// Destinazione dell'output (stdout di default)
+    out: CountingWriter,
+    /// Statistiche dell'ultimo frame
+    stats: RenderStats,
+    /// Salta i frame identici all'ultimo renderizzato da `render`
+    skip_unchanged: bool,
+    /// `last_buffer` contiene per intero l'ultimo frame di `render`/`present`,
+    /// quindi un confronto diretto può riconoscere un frame identico
+    last_frame_valid: bool,
+    /// Output sincronizzato (modo DEC 2026) richiesto con `set_sync_output`
+    sync_output: bool,
+    /// Supporto del modo 2026, `None` finché non è stato verificato
+    sync_supported: Option<bool>,
+    flush_policy: FlushPolicy,
+    last_flush: Instant,
+    dirty_sampling: DirtySampling,
 }
 
 impl SmartRenderer {
-    pub fn new() -> io::Result<Self> {
-        let terminal_size = terminal::size()?;
-        
-        // Calcola workspace ottimale (lascia margini)
-        let workspace_width = (terminal_size.0 as usize).saturating_sub(4).max(40);
-        let workspace_height = (terminal_size.1 as usize).saturating_sub(4).max(20);
-        
-        let workspace_offset = (
-            (terminal_size.0 as usize - workspace_width) / 2,
-            (terminal_size.1 as usize - workspace_height) / 2
-        );
+    pub fn new() -> Result<Self> {
+        Self::with_layout(LayoutMode::default())
+    }
+    
+    /// Crea il renderer con la disposizione del workspace indicata
+    pub fn with_layout(layout: LayoutMode) -> Result<Self> {
+        Ok(Self::with_terminal_size(layout, terminal::size()?))
+    }
+    
+    /// Crea il renderer per un terminale di dimensioni note, senza interrogarlo
+    pub(crate) fn with_terminal_size(layout: LayoutMode, terminal_size: (u16, u16)) -> Self {
+        let (workspace_size, workspace_offset) = layout.workspace_layout(terminal_size);
         
-        let last_buffer = StyledFrameBuffer::new_pooled(workspace_width, workspace_height);
+        let last_buffer = StyledFrameBuffer::new_pooled(workspace_size.0, workspace_size.1);
+        let back_buffer = StyledFrameBuffer::new_pooled(workspace_size.0, workspace_size.1);
         
-        Ok(Self {
+        Self {
             terminal_size,
-            workspace_size: (workspace_width, workspace_height),
+            workspace_size,
             workspace_offset,
             last_buffer,
+            back_buffer,
             dirty_regions: Vec::new(),
+            previous_regions: Vec::new(),
             force_full_refresh: true,
             page_cache: Arc::new(RwLock::new(std::collections::HashMap::new())),
             page_size: 64, // 64x64 pixel pages
             max_cached_pages: 16,
             output_buffer: Arc::new(RwLock::new(String::with_capacity(32768))),
             render_queue: Arc::new(RwLock::new(VecDeque::new())),
-        })
+            line_attributes: false,
+            text_cursor: None,
+            cursor_style: CursorStyle::default(),
+            layout,
+            cursor_blink: None,
+            blink_start: 0,
+            frame_count: 0,
+            frame_duration: Duration::from_nanos(1_000_000_000 / 60),
+            blink_restore: None,
+            out: CountingWriter { inner: Box::new(stdout()), bytes: 0, pending: Vec::with_capacity(32768) },
+            stats: RenderStats::default(),
+            skip_unchanged: false,
+            last_frame_valid: false,
+            sync_output: false,
+            sync_supported: None,
+            flush_policy: FlushPolicy::default(),
+            last_flush: Instant::now(),
+            dirty_sampling: DirtySampling::default(),
+        }
+    }
+    
+    /// Invia tutto l'output di rendering a `out` invece che a stdout
+    /// (es. `/dev/tty` aperto in scrittura quando stdout è rediretto)
+    pub fn with_output(mut self, out: Box<dyn Write + Send>) -> Self {
+        self.out.inner = out;
+        self
     }
     
     /// Aggiorna dimensioni quando il terminale viene ridimensionato
-    pub fn update_terminal_size(&mut self, new_size: (u16, u16)) -> io::Result<()> {
+    pub fn update_terminal_size(&mut self, new_size: (u16, u16)) -> Result<()> {
         self.terminal_size = new_size;
         
-        // Ricalcola workspace
-        let new_width = (new_size.0 as usize).saturating_sub(4).max(40);
-        let new_height = (new_size.1 as usize).saturating_sub(4).max(20);
-        
-        self.workspace_offset = (
-            (new_size.0 as usize - new_width) / 2,
-            (new_size.1 as usize - new_height) / 2
-        );
+        // Ricalcola workspace secondo la disposizione scelta
+        let ((new_width, new_height), offset) = self.layout.workspace_layout(new_size);
+        self.workspace_offset = offset;
         
         // Ridimensiona buffer se necessario
         if (new_width, new_height) != self.workspace_size {
             self.workspace_size = (new_width, new_height);
-            self.last_buffer.resize(new_width, new_height);
-            self.force_full_refresh = true;
+            self.back_buffer.resize(new_width, new_height);
         }
         
+        // Il terminale viene pulito: il buffer di confronto riparte vuoto invece di
+        // conservare il frame precedente (magari ritagliato da un rimpicciolimento),
+        // e le regioni dirty in coordinate vecchie non servono più
+        let blank = StyledFrameBuffer::new_pooled(new_width, new_height);
+        std::mem::replace(&mut self.last_buffer, blank).release_to_pool();
+        self.force_full_refresh = true;
+        self.dirty_regions.clear();
+        self.previous_regions.clear();
+        self.page_cache.write().clear();
+        
         // Pulisci terminale completamente
-        stdout().execute(terminal::Clear(terminal::ClearType::All))?;
-        stdout().execute(cursor::MoveTo(0, 0))?;
+        self.last_frame_valid = false;
+        self.out.execute(terminal::Clear(terminal::ClearType::All))?;
+        self.out.execute(cursor::MoveTo(0, 0))?;
         
         Ok(())
     }
     
+    /// Cambia la disposizione del workspace a runtime
+    pub fn set_layout(&mut self, layout: LayoutMode) -> Result<()> {
+        self.layout = layout;
+        self.update_terminal_size(self.terminal_size)?;
+        self.force_full_refresh = true;
+        Ok(())
+    }
+    
+    pub fn get_layout(&self) -> LayoutMode {
+        self.layout
+    }
+    
+    /// Abilita l'emissione degli attributi di riga DEC (`RowScale`).
+    /// Da abilitare solo su terminali che li supportano: altrimenti le righe
+    /// vengono renderizzate normalmente ignorando il loro `RowScale`.
+    pub fn set_line_attributes_supported(&mut self, supported: bool) {
+        if self.line_attributes != supported {
+            self.line_attributes = supported;
+            self.force_full_refresh = true;
+        }
+    }
+    
+    /// Indica se gli attributi di riga DEC vengono emessi
+    pub fn line_attributes_supported(&self) -> bool {
+        self.line_attributes
+    }
+    
+    /// Lato (in celle) delle pagine usate da `render_paged`. Si assume una potenza
+    /// di due; cambiare dimensione invalida la cache delle pagine.
+    pub fn set_page_size(&mut self, page_size: usize) {
+        debug_assert!(page_size.is_power_of_two(), "page_size deve essere una potenza di due");
+        let page_size = page_size.max(1);
+        if self.page_size != page_size {
+            self.page_size = page_size;
+            self.page_cache.write().clear();
+            self.force_full_refresh = true;
+        }
+    }
+    
+    pub fn get_page_size(&self) -> usize {
+        self.page_size
+    }
+    
+    /// Sceglie come `render_paged` rileva le pagine cambiate: `Full` (default) è
+    /// sempre corretto, `Sampled` costa meno ma può perdere modifiche puntuali
+    pub fn set_dirty_sampling(&mut self, sampling: DirtySampling) {
+        self.dirty_sampling = sampling;
+    }
+    
+    pub fn dirty_sampling(&self) -> DirtySampling {
+        self.dirty_sampling
+    }
+    
+    /// Se attivo, `render` confronta il buffer cella per cella con l'ultimo frame
+    /// (si ferma alla prima differenza) e, se coincidono, non esegue il diff e
+    /// scarta le regioni dirty segnate.
+    /// Cursore lampeggiante e cursore di testo vengono comunque aggiornati.
+    pub fn set_skip_unchanged(&mut self, skip: bool) {
+        self.skip_unchanged = skip;
+        self.last_frame_valid = false;
+    }
+    
+    pub fn skip_unchanged(&self) -> bool {
+        self.skip_unchanged
+    }
+    
+    /// Racchiude ogni `render` tra inizio e fine dell'output sincronizzato (modo DEC
+    /// 2026), così il terminale presenta il frame in un colpo solo invece di mostrarlo
+    /// a metà aggiornamento. Alla prima attivazione il supporto viene verificato con
+    /// DECRQM (il terminale deve già essere in raw mode): la chiamata attende la
+    /// risposta al massimo 200 ms e non legge altro input dopo essere tornata. Se il
+    /// terminale non riconosce il modo o non risponde, l'opzione resta senza effetto.
+    pub fn set_sync_output(&mut self, enabled: bool) {
+        self.sync_output = enabled;
+        if enabled && self.sync_supported.is_none() {
+            #[cfg(unix)]
+            let supported = crate::input::query_mode(&mut self.out, 2026) == Some(true);
+            #[cfg(not(unix))]
+            let supported = false;
+            self.sync_supported = Some(supported);
+        }
+    }
+    
+    /// Indica se i frame vengono effettivamente racchiusi nell'output sincronizzato
+    pub fn sync_output(&self) -> bool {
+        self.sync_output && self.sync_supported == Some(true)
+    }
+    
+    /// Imposta il supporto del modo 2026 senza interrogare il terminale
+    pub fn set_sync_output_supported(&mut self, supported: bool) {
+        self.sync_supported = Some(supported);
+    }
+    
+    /// Supporto del modo 2026, `None` se non ancora verificato
+    pub fn sync_output_supported(&self) -> Option<bool> {
+        self.sync_supported
+    }
+    
+    /// Sceglie quando inviare al terminale l'output dei rendering. Con
+    /// `FlushPolicy::Coalesced` l'ultimo frame di una raffica resta in attesa
+    /// fino al rendering successivo: chiamare `flush` quando non ne seguiranno.
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.flush_policy = policy;
+    }
+    
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.flush_policy
+    }
+    
+    /// Invia subito al terminale l'output in attesa. Con `FlushPolicy::Coalesced`
+    /// l'app deve chiamarlo prima di restare inattiva (es. in attesa di input),
+    /// altrimenti l'ultimo frame non compare finché non ne viene renderizzato un altro.
+    pub fn flush(&mut self) -> Result<()> {
+        self.out.flush()?;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+    
+    /// Indica se c'è output di rendering non ancora inviato al terminale
+    pub fn has_pending_output(&self) -> bool {
+        !self.out.pending.is_empty()
+    }
+    
+    /// Fine di un rendering: flush secondo la `FlushPolicy`
+    fn end_frame(&mut self) -> Result<()> {
+        match self.flush_policy {
+            FlushPolicy::PerFrame => self.flush(),
+            FlushPolicy::Coalesced { .. } => self.flush_if_due(),
+        }
+    }
+    
+    /// Con `FlushPolicy::Coalesced`, invia l'output in attesa da almeno `max_delay`
+    fn flush_if_due(&mut self) -> Result<()> {
+        match self.flush_policy {
+            FlushPolicy::Coalesced { max_delay } if self.has_pending_output() && self.last_flush.elapsed() >= max_delay => self.flush(),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Mostra il cursore reale del terminale nella cella `position` del workspace
+    /// con la forma indicata, o lo nasconde con `None`. Applicato dopo ogni frame.
+    pub fn set_text_cursor(&mut self, position: Option<(usize, usize)>, style: CursorStyle) {
+        self.text_cursor = position;
+        self.cursor_style = style;
+    }
+    
+    /// Cursore a blocco lampeggiante nella cella (x, y) del workspace: visibile per
+    /// `period`, poi nascosto per `period`, senza che l'app tenga traccia della fase.
+    /// La fase segue il numero di frame renderizzati (ogni chiamata a `render`,
+    /// `present`, `render_paged`, `render_using_dirty` o `render_rect` è un frame),
+    /// convertendo `period` con la frequenza di `set_frame_rate`; `None` lo disattiva.
+    pub fn set_cursor_blink(&mut self, blink: Option<(usize, usize, Duration)>) {
+        let previous = self.cursor_blink.map(|(x, y, _)| (x, y));
+        if blink.map(|(x, y, _)| (x, y)) != previous {
+            // Ricomincia visibile quando il cursore si sposta
+            self.blink_start = self.frame_count;
+            // La cella sotto il vecchio cursore va ridisegnata
+            self.blink_restore = previous;
+        }
+        self.cursor_blink = blink;
+    }
+    
+    /// Frequenza (limitata a 1..=120 come `FrameTimer`) con cui l'app renderizza:
+    /// determina quanti frame dura ogni fase del cursore lampeggiante. Default 60.
+    pub fn set_frame_rate(&mut self, target_fps: u32) {
+        self.frame_duration = Duration::from_nanos(1_000_000_000 / target_fps.clamp(1, 120) as u64);
+    }
+    
+    /// Indica se il cursore lampeggiante è nella fase visibile nel frame corrente
+    fn cursor_blink_visible(&self) -> bool {
+        match self.cursor_blink {
+            Some((_, _, period)) if !period.is_zero() => {
+                // Almeno un frame per fase, anche con periodi più brevi di un frame
+                let phase_frames = (period.as_nanos() / self.frame_duration.as_nanos()).max(1) as u64;
+                ((self.frame_count - self.blink_start) / phase_frames).is_multiple_of(2)
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+    
+    /// Disegna sopra il frame la cella del cursore lampeggiante nella fase corrente,
+    /// poi passa al frame successivo
+    fn draw_cursor_blink(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
+        let result = self.write_cursor_blink(buffer);
+        self.frame_count += 1;
+        result
+    }
+    
+    fn write_cursor_blink(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
+        if let Some((x, y)) = self.blink_restore.take() {
+            if x < buffer.width && y < buffer.height {
+                if let Some((term_x, term_y)) = self.workspace_to_terminal(x, y) {
+                    self.out.queue(cursor::MoveTo(term_x, term_y))?;
+                    write!(self.out, "{}", buffer.get(x, y))?;
+                }
+            }
+        }
+        
+        let Some((x, y, _)) = self.cursor_blink else {
+            return Ok(());
+        };
+        if x >= buffer.width || y >= buffer.height {
+            return Ok(());
+        }
+        let Some((term_x, term_y)) = self.workspace_to_terminal(x, y) else {
+            return Ok(());
+        };
+        
+        let cell = buffer.get(x, y);
+        let shown = if self.cursor_blink_visible() {
+            StyledChar {
+                ch: '█',
+                fg_color: Some(cell.fg_color.unwrap_or(crate::Color::White)),
+                bg_color: cell.bg_color,
+                combining: None,
+                attributes: cell.attributes,
+            }
+        } else {
+            cell
+        };
+        
+        self.out.queue(cursor::MoveTo(term_x, term_y))?;
+        write!(self.out, "{}", shown)?;
+        Ok(())
+    }
+    
+    /// Posiziona (o nasconde) il cursore reale dopo l'output del frame
+    fn place_text_cursor(&mut self) -> Result<()> {
+        let position = self.text_cursor
+            .filter(|&(x, y)| x < self.workspace_size.0 && y < self.workspace_size.1)
+            .and_then(|(x, y)| self.workspace_to_terminal(x, y));
+        match position {
+            Some((term_x, term_y)) => {
+                write!(self.out, "{}", self.cursor_style.to_escape())?;
+                self.out.queue(cursor::MoveTo(term_x, term_y))?;
+                self.out.queue(cursor::Show)?;
+            }
+            None => {
+                self.out.queue(cursor::Hide)?;
+            }
+        }
+        Ok(())
+    }
+    
     /// Ottieni dimensioni del workspace
     pub fn get_workspace_size(&self) -> (usize, usize) {
         self.workspace_size
@@ -159,15 +600,30 @@ impl SmartRenderer {
         None
     }
     
-    /// Converti coordinate workspace in coordinate terminale
-    pub fn workspace_to_terminal(&self, x: usize, y: usize) -> (u16, u16) {
-        (
-            (x + self.workspace_offset.0) as u16,
-            (y + self.workspace_offset.1) as u16
-        )
+    /// Converti coordinate workspace in coordinate terminale. `None` se la cella
+    /// cade fuori dal terminale (buffer più grande del workspace, offset enormi):
+    /// va saltata invece di essere disegnata in una posizione troncata a u16
+    pub fn workspace_to_terminal(&self, x: usize, y: usize) -> Option<(u16, u16)> {
+        Self::terminal_position(self.workspace_offset, self.terminal_size, x, y)
     }
     
-    /// Aggiungi regione dirty
+    /// Versione statica di `workspace_to_terminal`, usabile nel rendering parallelo
+    pub(crate) fn terminal_position(workspace_offset: (usize, usize), terminal_size: (u16, u16), x: usize, y: usize) -> Option<(u16, u16)> {
+        let term_x = x.checked_add(workspace_offset.0)?;
+        let term_y = y.checked_add(workspace_offset.1)?;
+        if term_x >= terminal_size.0 as usize || term_y >= terminal_size.1 as usize {
+            return None;
+        }
+        Some((term_x as u16, term_y as u16))
+    }
+    
+    /// Prima colonna del workspace che non entra più nel terminale
+    fn visible_columns(workspace_offset: (usize, usize), terminal_size: (u16, u16)) -> usize {
+        (terminal_size.0 as usize).saturating_sub(workspace_offset.0)
+    }
+    
+    /// Aggiungi regione dirty. Se tra due rendering se ne accumulano più di
+    /// `MAX_DIRTY_REGIONS`, vengono subito accorpate così la lista resta limitata.
     pub fn mark_dirty(&mut self, rect: Rect) {
         // Clamp il rect ai bounds del workspace
         let clamped_rect = Rect::new(
@@ -179,9 +635,31 @@ impl SmartRenderer {
         
         if clamped_rect.width > 0 && clamped_rect.height > 0 {
             self.dirty_regions.push(clamped_rect);
+            if self.dirty_regions.len() > MAX_DIRTY_REGIONS {
+                self.coalesce_dirty_regions();
+            }
         }
     }
     
+    /// Unisce le regioni dirty vicine; se ne restano ancora troppe le sostituisce
+    /// con l'intero workspace
+    fn coalesce_dirty_regions(&mut self) {
+        let regions = std::mem::take(&mut self.dirty_regions);
+        let mut merged: Vec<Rect> = Vec::with_capacity(regions.len());
+        for region in regions {
+            match merged.iter().position(|&cluster| self.should_merge_regions(cluster, region)) {
+                Some(index) => merged[index] = self.merge_regions(merged[index], region),
+                None => merged.push(region),
+            }
+        }
+        
+        if merged.len() > MAX_DIRTY_REGIONS / 2 {
+            merged.clear();
+            merged.push(Rect::new(0, 0, self.workspace_size.0, self.workspace_size.1));
+        }
+        self.dirty_regions = merged;
+    }
+    
     /// Forza refresh completo
     pub fn force_full_refresh(&mut self) {
         self.force_full_refresh = true;
@@ -190,38 +668,116 @@ impl SmartRenderer {
     }
     
     /// Rendering intelligente con ottimizzazioni
-    pub fn render(&mut self, buffer: &StyledFrameBuffer) -> io::Result<()> {
+    pub fn render(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
+        self.render_frame(buffer, true)
+    }
+    
+    /// Back buffer posseduto dal renderer, grande quanto il workspace: l'app ci
+    /// disegna direttamente e poi chiama `present`, senza buffer esterni né copie
+    /// dell'intero frame
+    pub fn back_buffer_mut(&mut self) -> &mut StyledFrameBuffer {
+        &mut self.back_buffer
+    }
+    
+    /// Presenta il back buffer: come `render` (regioni segnate con `mark_dirty` più
+    /// quelle registrate dal back buffer stesso), poi scambia back buffer e
+    /// `last_buffer` invece di copiare il frame. Il nuovo back buffer viene
+    /// riallineato solo sulle regioni cambiate, così contiene di nuovo il frame
+    /// appena presentato e si può continuare a disegnare in modo incrementale.
+    pub fn present(&mut self) -> Result<()> {
+        let mut back = std::mem::replace(&mut self.back_buffer, StyledFrameBuffer::new(0, 0));
+        for &region in back.get_dirty_regions() {
+            self.mark_dirty(region);
+        }
+        let result = self.render_frame(&back, false);
+        if result.is_ok() {
+            std::mem::swap(&mut self.last_buffer, &mut back);
+            if self.stats.full_refresh {
+                self.last_buffer.clone_into(&mut back);
+            } else {
+                for &region in &self.previous_regions {
+                    back.copy_rect(&self.last_buffer, region, region.x, region.y);
+                }
+            }
+            self.last_buffer.clear_dirty();
+            back.clear_dirty();
+        }
+        // Se l'output fallisce le regioni restano dirty e il frame verrà ripresentato
+        self.back_buffer = back;
+        result
+    }
+    
+    /// Corpo di `render` e `present`: con `copy_last` il frame viene copiato in
+    /// `last_buffer`, altrimenti è il chiamante ad aggiornarlo
+    fn render_frame(&mut self, buffer: &StyledFrameBuffer, copy_last: bool) -> Result<()> {
         if buffer.width != self.workspace_size.0 || buffer.height != self.workspace_size.1 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Buffer size mismatch with workspace"
-            ));
+            ).into());
+        }
+        
+        let bytes_before = self.begin_frame()?;
+        let sync = self.sync_output();
+        if sync {
+            self.out.write_all(b"\x1b[?2026h")?;
+        }
+        if self.skip_unchanged && self.last_frame_valid && !self.force_full_refresh
+            && buffer.same_content(&self.last_buffer)
+        {
+            // Frame identico all'ultimo: niente diff, e le regioni segnate non
+            // hanno nulla da ridisegnare
+            self.dirty_regions.clear();
+            self.draw_cursor_blink(buffer)?;
+            self.place_text_cursor()?;
+            if sync {
+                self.out.write_all(b"\x1b[?2026l")?;
+            }
+            self.end_frame()?;
+            self.stats.bytes_emitted = self.out.bytes - bytes_before;
+            return Ok(());
         }
         
         if self.force_full_refresh {
             self.render_full(buffer)?;
             self.force_full_refresh = false;
         } else {
+            // Include le regioni occupate nel frame precedente (es. finestra spostata)
+            let marked = self.dirty_regions.len();
+            self.dirty_regions.extend_from_slice(&self.previous_regions);
             self.render_incremental(buffer)?;
+            self.dirty_regions.truncate(marked);
         }
         
-        // Aggiorna buffer di confronto
-        self.last_buffer = buffer.clone();
+        // Aggiorna buffer di confronto riusando l'allocazione esistente
+        if copy_last {
+            buffer.clone_into(&mut self.last_buffer);
+        }
+        self.last_frame_valid = true;
+        std::mem::swap(&mut self.previous_regions, &mut self.dirty_regions);
         self.dirty_regions.clear();
         
-        stdout().flush()?;
+        self.draw_cursor_blink(buffer)?;
+        self.place_text_cursor()?;
+        if sync {
+            self.out.write_all(b"\x1b[?2026l")?;
+        }
+        self.end_frame()?;
+        self.stats.bytes_emitted = self.out.bytes - bytes_before;
         Ok(())
     }
     
     /// Rendering con sistema di paging
-    pub fn render_paged(&mut self, buffer: &StyledFrameBuffer) -> io::Result<()> {
+    pub fn render_paged(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
         if buffer.width != self.workspace_size.0 || buffer.height != self.workspace_size.1 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Buffer size mismatch with workspace"
-            ));
+            ).into());
         }
 
+        let bytes_before = self.begin_frame()?;
+        self.last_frame_valid = false;
         if self.force_full_refresh {
             self.render_full_paged(buffer)?;
             self.force_full_refresh = false;
@@ -229,43 +785,224 @@ impl SmartRenderer {
             self.render_incremental_paged(buffer)?;
         }
 
-        self.last_buffer = buffer.clone();
+        buffer.clone_into(&mut self.last_buffer);
+        self.dirty_regions.clear();
+        
+        self.draw_cursor_blink(buffer)?;
+        self.place_text_cursor()?;
+        self.end_frame()?;
+        self.stats.bytes_emitted = self.out.bytes - bytes_before;
+        Ok(())
+    }
+
+    /// Rendering basato sulle regioni dirty registrate dal buffer stesso (più quelle
+    /// segnate con `mark_dirty`), senza confronto con il frame precedente: per app
+    /// che tracciano con precisione le proprie modifiche, incluse le aree lasciate
+    /// libere da contenuti spostati. Le regioni del buffer vengono azzerate dopo
+    /// il rendering; `last_buffer` viene aggiornato solo su quelle regioni.
+    pub fn render_using_dirty(&mut self, buffer: &mut StyledFrameBuffer) -> Result<()> {
+        if buffer.width != self.workspace_size.0 || buffer.height != self.workspace_size.1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Buffer size mismatch with workspace"
+            ).into());
+        }
+        
+        let bytes_before = self.begin_frame()?;
+        self.last_frame_valid = false;
+        if self.force_full_refresh {
+            self.render_full(buffer)?;
+            self.force_full_refresh = false;
+            buffer.clone_into(&mut self.last_buffer);
+        } else {
+            for &region in buffer.get_dirty_regions() {
+                self.mark_dirty(region);
+            }
+            let regions = std::mem::take(&mut self.dirty_regions);
+            let mut cursor_at = None;
+            self.stats.regions = regions.len();
+            for &region in &regions {
+                for y in region.y..region.y + region.height {
+                    self.write_row_span(buffer, region, y, &mut cursor_at)?;
+                }
+                self.last_buffer.copy_rect(buffer, region, region.x, region.y);
+            }
+            self.last_buffer.clear_dirty();
+            self.dirty_regions = regions;
+        }
+        
+        buffer.clear_dirty();
         self.dirty_regions.clear();
         
-        stdout().flush()?;
+        self.draw_cursor_blink(buffer)?;
+        self.place_text_cursor()?;
+        self.end_frame()?;
+        self.stats.bytes_emitted = self.out.bytes - bytes_before;
+        Ok(())
+    }
+
+    /// Renderizza solo `rect` (clippato al workspace) confrontandolo con `last_buffer`,
+    /// senza passare dalle regioni dirty. Aggiorna `last_buffer` solo per le celle del rect.
+    pub fn render_rect(&mut self, buffer: &StyledFrameBuffer, rect: Rect) -> Result<()> {
+        if buffer.width != self.workspace_size.0 || buffer.height != self.workspace_size.1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Buffer size mismatch with workspace"
+            ).into());
+        }
+        
+        let bytes_before = self.begin_frame()?;
+        self.last_frame_valid = false;
+        let start_x = rect.x.min(buffer.width);
+        let start_y = rect.y.min(buffer.height);
+        let end_x = (rect.x + rect.width).min(buffer.width);
+        let end_y = (rect.y + rect.height).min(buffer.height);
+        
+        let mut output = String::new();
+        for y in start_y..end_y {
+            // Limita l'output al tratto della riga effettivamente cambiato
+            let changed = |x: &usize| buffer.get(*x, y) != self.last_buffer.get(*x, y);
+            let first = (start_x..end_x).find(changed);
+            let last = (start_x..end_x).rev().find(changed);
+            
+            if let (Some(first), Some(last)) = (first, last) {
+                let run = Rect::new(first, y, last + 1 - first, 1);
+                self.stats.regions += 1;
+                self.stats.cells_written += run.width;
+                output.push_str(&Self::render_page_region_static(buffer, run, self.workspace_offset, self.terminal_size, self.line_attributes));
+                
+                let row = y * buffer.width;
+                self.last_buffer.data[row + first..=row + last]
+                    .copy_from_slice(&buffer.data[row + first..=row + last]);
+            }
+        }
+        
+        write!(self.out, "{}", output)?;
+        self.draw_cursor_blink(buffer)?;
+        if self.out.bytes > bytes_before {
+            self.place_text_cursor()?;
+            self.end_frame()?;
+        }
+        
+        self.stats.bytes_emitted = self.out.bytes - bytes_before;
         Ok(())
     }
 
+    /// Inizio di un frame: invia l'output dei frame precedenti se la scadenza di
+    /// `FlushPolicy::Coalesced` è passata, poi azzera le statistiche e restituisce
+    /// i byte già scritti
+    fn begin_frame(&mut self) -> Result<usize> {
+        self.flush_if_due()?;
+        self.stats = RenderStats::default();
+        Ok(self.out.bytes)
+    }
+    
+    /// Statistiche dell'ultimo `render`, `render_paged`, `render_using_dirty` o `render_rect`
+    pub fn last_stats(&self) -> RenderStats {
+        self.stats
+    }
+
     /// Rendering completo
-    fn render_full(&mut self, buffer: &StyledFrameBuffer) -> io::Result<()> {
+    fn render_full(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
+        self.stats.full_refresh = true;
+        self.stats.regions = 1;
+        let mut frame = Vec::with_capacity(buffer.width * buffer.height * 8);
+        self.stats.cells_written = self.write_full_frame(&mut frame, buffer)?;
+        self.out.write_all(&frame)?;
+        Ok(())
+    }
+    
+    /// Sequenza completa di un refresh totale: pulizia, bordo e ogni cella
+    /// con posizionamento assoluto. Le celle vuote con lo sfondo più diffuso
+    /// vengono dipinte riga per riga con un'unica cancellazione (ECH) invece
+    /// che una a una; restituisce il numero di celle scritte singolarmente.
+    fn write_full_frame<W: Write>(&self, out: &mut W, buffer: &StyledFrameBuffer) -> Result<usize> {
         // Pulisci terminale
-        stdout().execute(terminal::Clear(terminal::ClearType::All))?;
+        out.queue(terminal::Clear(terminal::ClearType::All))?;
         
         // Disegna bordo workspace
-        self.draw_workspace_border()?;
+        self.write_workspace_border(out)?;
+        
+        let background = Self::dominant_background(buffer);
+        let background_sgr = background.map(|bg| bg.to_ansi_bg());
+        let mut written = 0;
         
         // Renderizza tutto il buffer
+        // Le colonne oltre il terminale non vengono disegnate
+        let width = buffer.width.min(Self::visible_columns(self.workspace_offset, self.terminal_size));
         for y in 0..buffer.height {
-            for x in 0..buffer.width {
+            let Some((row_x, row_y)) = self.workspace_to_terminal(0, y) else {
+                break;
+            };
+            out.queue(cursor::MoveTo(row_x, row_y))?;
+            if self.line_attributes {
+                write!(out, "{}", buffer.get_row_scale(y).to_escape())?;
+            }
+            // Erase Character con lo sfondo attivo: limitato al workspace, a
+            // differenza di erase-in-line che arriverebbe al bordo del terminale
+            if let Some(sgr) = &background_sgr {
+                write!(out, "{}\x1b[{}X\x1b[0m", sgr, width)?;
+            }
+            
+            for x in 0..width {
                 let styled_char = buffer.get(x, y);
-                let (term_x, term_y) = self.workspace_to_terminal(x, y);
+                // Già disegnata dalla pulizia dello schermo o dalla cancellazione di riga
+                let blank = styled_char.ch == ' ' && styled_char.combining.is_none() && styled_char.attributes.is_empty();
+                let painted = match background {
+                    Some(bg) => blank && styled_char.bg_color == Some(bg),
+                    None => styled_char == StyledChar::default(),
+                };
+                if painted {
+                    continue;
+                }
                 
-                stdout().execute(cursor::MoveTo(term_x, term_y))?;
-                print!("{}", styled_char.to_string());
+                if let Some((term_x, term_y)) = self.workspace_to_terminal(x, y) {
+                    out.queue(cursor::MoveTo(term_x, term_y))?;
+                    write!(out, "{}", styled_char)?;
+                    written += 1;
+                }
             }
         }
         
-        Ok(())
+        Ok(written)
+    }
+    
+    /// Sfondo delle celle vuote se ne copre almeno metà del buffer: conviene
+    /// dipingerlo con una cancellazione per riga
+    fn dominant_background(buffer: &StyledFrameBuffer) -> Option<crate::Color> {
+        let mut counts: std::collections::HashMap<crate::Color, usize> = std::collections::HashMap::new();
+        for cell in &buffer.data {
+            if cell.ch == ' ' && cell.combining.is_none() && cell.attributes.is_empty() {
+                if let Some(bg) = cell.bg_color.filter(|&bg| bg != crate::Color::Reset && bg != crate::Color::Transparent) {
+                    *counts.entry(bg).or_default() += 1;
+                }
+            }
+        }
+        counts
+            .into_iter()
+            .max_by_key(|&(_, count)| count)
+            .filter(|&(_, count)| count * 2 >= buffer.data.len())
+            .map(|(bg, _)| bg)
+    }
+    
+    /// Restituisce esattamente i byte che un refresh completo di `buffer` invierebbe
+    /// al terminale (bordo del workspace incluso), senza scrivere nulla: utile per
+    /// allegare una riproduzione a una segnalazione invece di uno screenshot
+    pub fn compose_to_string(&self, buffer: &StyledFrameBuffer) -> String {
+        let mut frame = Vec::with_capacity(buffer.width * buffer.height * 8);
+        // La scrittura su un Vec non può fallire
+        let _ = self.write_full_frame(&mut frame, buffer);
+        String::from_utf8_lossy(&frame).into_owned()
     }
     
     /// Rendering completo con paging
-    fn render_full_paged(&mut self, buffer: &StyledFrameBuffer) -> io::Result<()> {
-        stdout().execute(terminal::Clear(terminal::ClearType::All))?;
+    fn render_full_paged(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
+        self.out.queue(terminal::Clear(terminal::ClearType::All))?;
         self.draw_workspace_border()?;
 
         // Suddividi il buffer in pagine
-        let pages_x = (buffer.width + self.page_size - 1) / self.page_size;
-        let pages_y = (buffer.height + self.page_size - 1) / self.page_size;
+        let pages_x = buffer.width.div_ceil(self.page_size);
+        let pages_y = buffer.height.div_ceil(self.page_size);
 
         // Crea lista di regioni da renderizzare
         let mut page_regions = Vec::new();
@@ -280,37 +1017,49 @@ impl SmartRenderer {
             }
         }
 
+        self.stats.full_refresh = true;
+        self.stats.regions = page_regions.len();
+        self.stats.cells_written = buffer.width * buffer.height;
+        
         // Rendering parallelo delle pagine
         let workspace_offset = self.workspace_offset;
+        let terminal_size = self.terminal_size;
+        let line_attributes = self.line_attributes;
         let page_outputs: Vec<String> = page_regions
             .into_par_iter()
             .map(|page_rect| {
-                SmartRenderer::render_page_region_static(buffer, page_rect, workspace_offset)
+                SmartRenderer::render_page_region_static(buffer, page_rect, workspace_offset, terminal_size, line_attributes)
             })
             .collect();
 
         // Output sequenziale dei risultati
         for page_output in page_outputs {
-            print!("{}", page_output);
+            write!(self.out, "{}", page_output)?;
         }
 
         Ok(())
     }
     
     /// Rendering incrementale (solo regioni cambiate)
-    fn render_incremental(&mut self, buffer: &StyledFrameBuffer) -> io::Result<()> {
+    fn render_incremental(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
         // Ottimizza regioni dirty (merge regioni adiacenti)
         let optimized_regions = self.optimize_dirty_regions();
         
+        // Cursore virtuale: posizione reale del cursore dopo l'ultima scrittura
+        let mut cursor_at = None;
         for region in optimized_regions {
-            self.render_region(buffer, region)?;
+            let cells_before = self.stats.cells_written;
+            self.render_region(buffer, region, &mut cursor_at)?;
+            if self.stats.cells_written > cells_before {
+                self.stats.regions += 1;
+            }
         }
         
         Ok(())
     }
     
     /// Rendering incrementale con paging
-    fn render_incremental_paged(&mut self, buffer: &StyledFrameBuffer) -> io::Result<()> {
+    fn render_incremental_paged(&mut self, buffer: &StyledFrameBuffer) -> Result<()> {
         // Identifica pagine dirty
         let dirty_pages = self.identify_dirty_pages(buffer);
         
@@ -318,25 +1067,31 @@ impl SmartRenderer {
             return Ok(());
         }
 
+        self.stats.regions = dirty_pages.len();
+        self.stats.cells_written = dirty_pages.iter().map(|page| page.width * page.height).sum();
+        
         // Rendering parallelo solo delle pagine dirty
         let workspace_offset = self.workspace_offset;
+        let terminal_size = self.terminal_size;
+        let line_attributes = self.line_attributes;
         let page_outputs: Vec<String> = dirty_pages
             .into_par_iter()
-            .map(|page_rect| Self::render_page_region_static(buffer, page_rect, workspace_offset))
+            .map(|page_rect| Self::render_page_region_static(buffer, page_rect, workspace_offset, terminal_size, line_attributes))
             .collect();
 
         // Output sequenziale
         for page_output in page_outputs {
-            print!("{}", page_output);
+            write!(self.out, "{}", page_output)?;
         }
 
         Ok(())
     }
     
     /// Renderizza una specifica regione
-    fn render_region(&mut self, buffer: &StyledFrameBuffer, region: Rect) -> io::Result<()> {
+    fn render_region(&mut self, buffer: &StyledFrameBuffer, region: Rect, cursor_at: &mut Option<(u16, u16)>) -> Result<()> {
         for y in region.y..(region.y + region.height).min(buffer.height) {
-            let mut line_changed = false;
+            let mut line_changed = self.line_attributes &&
+                buffer.get_row_scale(y) != self.last_buffer.get_row_scale(y);
             
             // Verifica se la riga è cambiata
             for x in region.x..(region.x + region.width).min(buffer.width) {
@@ -348,28 +1103,50 @@ impl SmartRenderer {
             
             if line_changed {
                 // Renderizza l'intera riga per performance
-                let (start_term_x, term_y) = self.workspace_to_terminal(region.x, y);
-                stdout().execute(cursor::MoveTo(start_term_x, term_y))?;
-                
-                // Ottimizzazione: costruisci stringa completa per la riga
-                let mut line_string = String::new();
-                for x in region.x..(region.x + region.width).min(buffer.width) {
-                    let styled_char = buffer.get(x, y);
-                    line_string.push_str(&styled_char.to_string());
-                }
-                
-                print!("{}", line_string);
+                self.write_row_span(buffer, region, y, cursor_at)?;
             }
         }
         
         Ok(())
     }
     
+    /// Scrive la riga `y` limitata alle colonne di `region`, senza confronti
+    fn write_row_span(&mut self, buffer: &StyledFrameBuffer, region: Rect, y: usize, cursor_at: &mut Option<(u16, u16)>) -> Result<()> {
+        let Some((start_term_x, term_y)) = self.workspace_to_terminal(region.x, y) else {
+            return Ok(());
+        };
+        // Scrittura contigua alla precedente: il cursore è già in posizione
+        if *cursor_at != Some((start_term_x, term_y)) {
+            self.out.queue(cursor::MoveTo(start_term_x, term_y))?;
+        }
+        if self.line_attributes {
+            write!(self.out, "{}", buffer.get_row_scale(y).to_escape())?;
+        }
+        
+        // Ottimizzazione: costruisci stringa completa per la riga
+        let mut line_string = String::new();
+        let end_x = (region.x + region.width)
+            .min(buffer.width)
+            .min(Self::visible_columns(self.workspace_offset, self.terminal_size));
+        for x in region.x..end_x {
+            let styled_char = buffer.get(x, y);
+            line_string.push_str(&styled_char.to_string());
+        }
+        
+        write!(self.out, "{}", line_string)?;
+        self.stats.cells_written += end_x.saturating_sub(region.x);
+        
+        // Sull'ultima colonna il terminale può posticipare l'a capo: posizione incerta
+        let end_term_x = start_term_x + end_x.saturating_sub(region.x) as u16;
+        *cursor_at = (end_term_x < self.terminal_size.0).then_some((end_term_x, term_y));
+        Ok(())
+    }
+    
     /// Identifica le pagine che sono cambiate
     fn identify_dirty_pages(&self, buffer: &StyledFrameBuffer) -> Vec<Rect> {
         let mut dirty_pages = Vec::new();
-        let pages_x = (buffer.width + self.page_size - 1) / self.page_size;
-        let pages_y = (buffer.height + self.page_size - 1) / self.page_size;
+        let pages_x = buffer.width.div_ceil(self.page_size);
+        let pages_y = buffer.height.div_ceil(self.page_size);
 
         for page_y in 0..pages_y {
             for page_x in 0..pages_x {
@@ -390,8 +1167,20 @@ impl SmartRenderer {
         dirty_pages
     }
 
-    /// Verifica se una pagina è dirty usando campionamento intelligente
+    /// Verifica se una pagina è dirty secondo la `DirtySampling` scelta
     fn is_page_dirty(&self, buffer: &StyledFrameBuffer, page_rect: Rect) -> bool {
+        if self.dirty_sampling == DirtySampling::Full {
+            if (buffer.width, buffer.height) != (self.last_buffer.width, self.last_buffer.height) {
+                return true;
+            }
+            // Confronto riga per riga sulle slice della pagina
+            return (page_rect.y..page_rect.y + page_rect.height).any(|y| {
+                let start = y * buffer.width + page_rect.x;
+                let end = start + page_rect.width;
+                buffer.data[start..end] != self.last_buffer.data[start..end]
+            });
+        }
+        
         // Campiona alcuni punti della pagina per performance
         let sample_points = [
             (0, 0),
@@ -405,10 +1194,9 @@ impl SmartRenderer {
             let y = page_rect.y + dy;
             
             if x < buffer.width && y < buffer.height &&
-               x < self.last_buffer.width && y < self.last_buffer.height {
-                if buffer.get(x, y) != self.last_buffer.get(x, y) {
-                    return true;
-                }
+               x < self.last_buffer.width && y < self.last_buffer.height &&
+               buffer.get(x, y) != self.last_buffer.get(x, y) {
+                return true;
             }
         }
         
@@ -416,22 +1204,30 @@ impl SmartRenderer {
     }
 
     /// Rendering ottimizzato di una regione/pagina (versione statica per parallelismo)
-    fn render_page_region_static(buffer: &StyledFrameBuffer, region: Rect, workspace_offset: (usize, usize)) -> String {
+    fn render_page_region_static(buffer: &StyledFrameBuffer, region: Rect, workspace_offset: (usize, usize), terminal_size: (u16, u16), line_attributes: bool) -> String {
         let mut output = String::with_capacity(region.width * region.height * 15);
+        let end_x = (region.x + region.width)
+            .min(buffer.width)
+            .min(Self::visible_columns(workspace_offset, terminal_size));
         
         // Rendering ottimizzato con batching degli stili
         for y in region.y..(region.y + region.height).min(buffer.height) {
-            let term_x = (region.x + workspace_offset.0) as u16;
-            let term_y = (y + workspace_offset.1) as u16;
+            // Righe o regioni fuori dal terminale: niente da disegnare
+            let Some((term_x, term_y)) = Self::terminal_position(workspace_offset, terminal_size, region.x, y) else {
+                break;
+            };
             output.push_str(&format!("\x1b[{};{}H", term_y + 1, term_x + 1));
+            if line_attributes {
+                output.push_str(buffer.get_row_scale(y).to_escape());
+            }
             
             // Batch caratteri con stesso stile
             let mut current_style = None;
             let mut style_batch = String::new();
             
-            for x in region.x..(region.x + region.width).min(buffer.width) {
+            for x in region.x..end_x {
                 let styled_char = buffer.get(x, y);
-                let char_style = (styled_char.fg_color, styled_char.bg_color);
+                let char_style = (styled_char.fg_color, styled_char.bg_color, styled_char.attributes);
                 
                 if current_style != Some(char_style) {
                     // Flush batch precedente
@@ -440,12 +1236,16 @@ impl SmartRenderer {
                         style_batch.clear();
                     }
                     
-                    // Nuovo stile
+                    // Nuovo stile, partendo da zero: colori e attributi assenti non
+                    // vengono emessi e resterebbero quelli della cella precedente
+                    if current_style.is_some() {
+                        output.push_str("\x1b[0m");
+                    }
                     output.push_str(&styled_char.get_style_codes());
                     current_style = Some(char_style);
                 }
                 
-                style_batch.push(styled_char.ch);
+                let _ = styled_char.write_glyph(&mut style_batch);
             }
             
             // Flush finale
@@ -462,7 +1262,7 @@ impl SmartRenderer {
     /// Rendering ottimizzato di una regione/pagina
     #[allow(dead_code)]
     fn render_page_region(&self, buffer: &StyledFrameBuffer, region: Rect) -> String {
-        Self::render_page_region_static(buffer, region, self.workspace_offset)
+        Self::render_page_region_static(buffer, region, self.workspace_offset, self.terminal_size, self.line_attributes)
     }
 
     /// Ottimizza regioni dirty unendo quelle adiacenti
@@ -510,13 +1310,11 @@ impl SmartRenderer {
             while found_adjacent {
                 found_adjacent = false;
                 
-                for j in 0..self.dirty_regions.len() {
+                for (j, &other) in self.dirty_regions.iter().enumerate() {
                     if processed[j] {
                         continue;
                     }
                     
-                    let other = self.dirty_regions[j];
-                    
                     // Verifica adiacenza e convenineza del merge
                     if self.should_merge_regions(cluster, other) {
                         cluster = self.merge_regions(cluster, other);
@@ -533,7 +1331,6 @@ impl SmartRenderer {
     }
 
     /// Verifica se due regioni dovrebbero essere unite
-    #[allow(dead_code)]
     fn should_merge_regions(&self, a: Rect, b: Rect) -> bool {
         // Calcola l'area del bounding box che conterrebbe entrambe
         let merged = self.merge_regions(a, b);
@@ -545,7 +1342,6 @@ impl SmartRenderer {
     }
 
     /// Unisce due regioni in un bounding box
-    #[allow(dead_code)]
     fn merge_regions(&self, a: Rect, b: Rect) -> Rect {
         let min_x = a.x.min(b.x);
         let min_y = a.y.min(b.y);
@@ -586,22 +1382,49 @@ impl SmartRenderer {
     }
     
     /// Disegna bordo del workspace
-    fn draw_workspace_border(&self) -> io::Result<()> {
+    fn draw_workspace_border(&mut self) -> Result<()> {
+        let mut border = Vec::new();
+        self.write_workspace_border(&mut border)?;
+        self.out.write_all(&border)?;
+        Ok(())
+    }
+    
+    fn write_workspace_border<W: Write>(&self, out: &mut W) -> Result<()> {
+        if self.layout == LayoutMode::Fullscreen {
+            return Ok(());
+        }
+        
         let border_color = "\x1b[36m"; // Cyan
         let reset_color = "\x1b[0m";
         
+        if self.layout == LayoutMode::FitExact {
+            // Solo bordo superiore e sinistro: destra e basso sono occupati dal workspace
+            if self.workspace_offset.1 > 0 {
+                out.queue(cursor::MoveTo(0, 0))?;
+                let corner = if self.workspace_offset.0 > 0 { "┌" } else { "" };
+                write!(out, "{}{}{}{}", border_color, corner, "─".repeat(self.workspace_size.0), reset_color)?;
+            }
+            if self.workspace_offset.0 > 0 {
+                for y in 0..self.workspace_size.1 {
+                    out.queue(cursor::MoveTo(0, (self.workspace_offset.1 + y) as u16))?;
+                    write!(out, "{}│{}", border_color, reset_color)?;
+                }
+            }
+            return Ok(());
+        }
+        
         // Bordo superiore
         let top_y = self.workspace_offset.1.saturating_sub(1);
         if top_y < self.terminal_size.1 as usize {
-            stdout().execute(cursor::MoveTo(
+            out.queue(cursor::MoveTo(
                 self.workspace_offset.0.saturating_sub(1) as u16,
                 top_y as u16
             ))?;
-            print!("{}┌{}┐{}", 
+            write!(out, "{}┌{}┐{}", 
                 border_color,
                 "─".repeat(self.workspace_size.0),
                 reset_color
-            );
+            )?;
         }
         
         // Bordi laterali
@@ -610,47 +1433,59 @@ impl SmartRenderer {
             
             // Bordo sinistro
             if self.workspace_offset.0 > 0 {
-                stdout().execute(cursor::MoveTo(
+                out.queue(cursor::MoveTo(
                     self.workspace_offset.0.saturating_sub(1) as u16,
                     term_y
                 ))?;
-                print!("{}│{}", border_color, reset_color);
+                write!(out, "{}│{}", border_color, reset_color)?;
             }
             
             // Bordo destro
             let right_x = (self.workspace_offset.0 + self.workspace_size.0) as u16;
             if right_x < self.terminal_size.0 {
-                stdout().execute(cursor::MoveTo(right_x, term_y))?;
-                print!("{}│{}", border_color, reset_color);
+                out.queue(cursor::MoveTo(right_x, term_y))?;
+                write!(out, "{}│{}", border_color, reset_color)?;
             }
         }
         
         // Bordo inferiore
         let bottom_y = (self.workspace_offset.1 + self.workspace_size.1) as u16;
         if bottom_y < self.terminal_size.1 {
-            stdout().execute(cursor::MoveTo(
+            out.queue(cursor::MoveTo(
                 self.workspace_offset.0.saturating_sub(1) as u16,
                 bottom_y
             ))?;
-            print!("{}└{}┘{}", 
+            write!(out, "{}└{}┘{}", 
                 border_color,
                 "─".repeat(self.workspace_size.0),
                 reset_color
-            );
+            )?;
         }
         
         Ok(())
     }
     
     /// Nascondi cursore
-    pub fn hide_cursor(&self) -> io::Result<()> {
-        stdout().execute(cursor::Hide)?;
+    pub fn hide_cursor(&mut self) -> Result<()> {
+        self.out.execute(cursor::Hide)?;
         Ok(())
     }
     
     /// Mostra cursore
-    pub fn show_cursor(&self) -> io::Result<()> {
-        stdout().execute(cursor::Show)?;
+    pub fn show_cursor(&mut self) -> Result<()> {
+        self.out.execute(cursor::Show)?;
         Ok(())
     }
 }
+
+impl Drop for SmartRenderer {
+    fn drop(&mut self) {
+        // Restituisce al pool i buffer creati con `new_pooled`
+        let last_buffer = std::mem::replace(&mut self.last_buffer, StyledFrameBuffer::new(0, 0));
+        last_buffer.release_to_pool();
+        let back_buffer = std::mem::replace(&mut self.back_buffer, StyledFrameBuffer::new(0, 0));
+        back_buffer.release_to_pool();
+        // Output accumulato da `FlushPolicy::Coalesced` non ancora inviato
+        let _ = self.out.flush();
+    }
+}
@@ -0,0 +1,132 @@
+//! Sprites and tile maps for simple games built on `StyledFrameBuffer`
+
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::{StyledFrameBuffer, StyledChar, Rect};
+
+/// Small styled image with an optional transparent key character
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    pub buffer: StyledFrameBuffer,
+    /// Cells whose character matches this key are skipped when drawing
+    pub transparent: Option<char>,
+}
+
+impl Sprite {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::from_buffer(StyledFrameBuffer::new(width, height))
+    }
+
+    pub fn from_buffer(buffer: StyledFrameBuffer) -> Self {
+        Self {
+            buffer,
+            transparent: None,
+        }
+    }
+
+    /// Build a sprite from text rows, one cell per char
+    pub fn from_lines(lines: &[&str]) -> Self {
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let mut buffer = StyledFrameBuffer::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                buffer.set(x, y, StyledChar::new(ch));
+            }
+        }
+        Self::from_buffer(buffer)
+    }
+
+    pub fn with_transparent(mut self, key: char) -> Self {
+        self.transparent = Some(key);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.buffer.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.buffer.height
+    }
+}
+
+impl StyledFrameBuffer {
+    /// Draw a sprite at a signed position, skipping transparent cells and
+    /// clipping whatever falls outside the buffer
+    pub fn draw_sprite(&mut self, sprite: &Sprite, x: i32, y: i32) {
+        for sy in 0..sprite.height() {
+            let dy = y as i64 + sy as i64;
+            if dy < 0 || dy >= self.height as i64 {
+                continue;
+            }
+            for sx in 0..sprite.width() {
+                let dx = x as i64 + sx as i64;
+                if dx < 0 || dx >= self.width as i64 {
+                    continue;
+                }
+                let cell = sprite.buffer.get(sx, sy);
+                if sprite.transparent == Some(cell.ch) {
+                    continue;
+                }
+                self.set(dx as usize, dy as usize, cell);
+            }
+        }
+    }
+}
+
+/// Grid of tile indices rendered through a palette of styled chars
+#[derive(Debug, Clone)]
+pub struct TileMap {
+    pub width: usize,
+    pub height: usize,
+    tiles: Vec<usize>,
+    palette: Vec<StyledChar>,
+}
+
+impl TileMap {
+    /// Create a map filled with tile 0
+    pub fn new(width: usize, height: usize, palette: Vec<StyledChar>) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![0; width * height],
+            palette,
+        }
+    }
+
+    pub fn set_tile(&mut self, x: usize, y: usize, tile: usize) {
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x] = tile;
+        }
+    }
+
+    pub fn get_tile(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(self.tiles[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn palette(&self) -> &[StyledChar] {
+        &self.palette
+    }
+
+    pub fn palette_mut(&mut self) -> &mut Vec<StyledChar> {
+        &mut self.palette
+    }
+
+    /// Render the part of the map seen from `camera` (top-left map cell) into
+    /// `viewport` of the target buffer. Cells outside the map or with an
+    /// index missing from the palette are left untouched.
+    pub fn render(&self, target: &mut StyledFrameBuffer, viewport: Rect, camera: (usize, usize)) {
+        for vy in 0..viewport.height {
+            for vx in 0..viewport.width {
+                let tile = self.get_tile(camera.0 + vx, camera.1 + vy);
+                if let Some(&cell) = tile.and_then(|index| self.palette.get(index)) {
+                    target.set(viewport.x + vx, viewport.y + vy, cell);
+                }
+            }
+        }
+    }
+}
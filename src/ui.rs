@@ -1,6 +1,7 @@
 //! User interface components and widgets
 
 use crate::{StyledFrameBuffer, Rect, Color};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Base trait for UI widgets
 pub trait Widget {
@@ -45,10 +46,13 @@ impl Widget for Button {
         buffer.draw_rect(self.rect, ' ', Some(Color::White), Some(bg_color));
         buffer.draw_border(self.rect, Some(Color::White), None);
         
-        if self.rect.width > 2 && self.rect.height > 0 {
-            let text_x = self.rect.x + (self.rect.width - self.text.len().min(self.rect.width - 2)) / 2;
+        // Label centered between the borders, truncated to fit; skipped if there is no room
+        let inner_width = self.rect.width.saturating_sub(2);
+        if inner_width > 0 && self.rect.height > 0 {
+            let label = crate::truncate_to_width(&self.text, inner_width);
+            let text_x = self.rect.x + 1 + (inner_width - crate::text_display_width(label)) / 2;
             let text_y = self.rect.y + self.rect.height / 2;
-            buffer.draw_text(text_x, text_y, &self.text, Some(Color::White), Some(bg_color));
+            buffer.draw_text(text_x, text_y, label, Some(Color::White), Some(bg_color));
         }
     }
 
@@ -61,6 +65,929 @@ impl Widget for Button {
     }
 }
 
+/// Scrollbar direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Vertical,
+    Horizontal,
+}
+
+/// Scrollbar with a thumb proportional to the visible part of the content
+pub struct Scrollbar {
+    rect: Rect,
+    orientation: Orientation,
+    content_len: usize,
+    viewport_len: usize,
+    offset: usize,
+    dragging: bool,
+}
+
+impl Scrollbar {
+    pub fn new(rect: Rect, orientation: Orientation) -> Self {
+        Self {
+            rect,
+            orientation,
+            content_len: 0,
+            viewport_len: 0,
+            offset: 0,
+            dragging: false,
+        }
+    }
+
+    /// Update content and viewport sizes, re-clamping the current offset
+    pub fn set_content(&mut self, content_len: usize, viewport_len: usize) {
+        self.content_len = content_len;
+        self.viewport_len = viewport_len;
+        self.set_offset(self.offset);
+    }
+
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset.min(self.max_offset());
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn max_offset(&self) -> usize {
+        self.content_len.saturating_sub(self.viewport_len)
+    }
+
+    fn track_len(&self) -> usize {
+        match self.orientation {
+            Orientation::Vertical => self.rect.height,
+            Orientation::Horizontal => self.rect.width,
+        }
+    }
+
+    /// Thumb position and length in track cells
+    pub fn thumb(&self) -> (usize, usize) {
+        let track = self.track_len();
+        if track == 0 || self.content_len <= self.viewport_len {
+            return (0, track);
+        }
+        let len = (track * self.viewport_len / self.content_len).clamp(1, track);
+        let start = (self.offset * (track - len) + self.max_offset() / 2) / self.max_offset();
+        (start, len)
+    }
+
+    fn offset_at(&self, pos: usize) -> usize {
+        let (_, len) = self.thumb();
+        let free = self.track_len().saturating_sub(len);
+        if free == 0 {
+            return 0;
+        }
+        // Center the thumb on the clicked position
+        let start = pos.saturating_sub(len / 2).min(free);
+        (start * self.max_offset() + free / 2) / free
+    }
+
+    /// Handle a mouse or wheel event, returning the new offset if it changed
+    pub fn handle_event(&mut self, event: &crate::input::InputEvent) -> Option<usize> {
+        use crossterm::event::{MouseButton, MouseEventKind};
+
+        let (x, y, kind) = match event {
+            crate::input::InputEvent::Mouse { x, y, kind } => (*x as usize, *y as usize, kind),
+            crate::input::InputEvent::Scroll { x, y, delta } => {
+                if !self.rect.contains(*x as usize, *y as usize) {
+                    return None;
+                }
+                let old_offset = self.offset;
+                self.set_offset(self.offset.saturating_add_signed(-*delta as isize));
+                return (self.offset != old_offset).then_some(self.offset);
+            }
+            _ => return None,
+        };
+        let pos = match self.orientation {
+            Orientation::Vertical => y.saturating_sub(self.rect.y),
+            Orientation::Horizontal => x.saturating_sub(self.rect.x),
+        };
+
+        let new_offset = match kind {
+            MouseEventKind::Down(MouseButton::Left) if self.rect.contains(x, y) => {
+                self.dragging = true;
+                self.offset_at(pos)
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging => self.offset_at(pos),
+            MouseEventKind::Up(_) => {
+                self.dragging = false;
+                return None;
+            }
+            _ => return None,
+        };
+
+        let old_offset = self.offset;
+        self.set_offset(new_offset);
+        (self.offset != old_offset).then_some(self.offset)
+    }
+}
+
+impl Widget for Scrollbar {
+    fn render(&self, buffer: &mut StyledFrameBuffer) {
+        let (start, len) = self.thumb();
+        for dy in 0..self.rect.height {
+            for dx in 0..self.rect.width {
+                let pos = match self.orientation {
+                    Orientation::Vertical => dy,
+                    Orientation::Horizontal => dx,
+                };
+                let (ch, color) = if pos >= start && pos < start + len {
+                    ('█', Color::White)
+                } else {
+                    ('░', Color::Gray)
+                };
+                buffer.set(
+                    self.rect.x + dx,
+                    self.rect.y + dy,
+                    crate::StyledChar::new(ch).with_fg(color),
+                );
+            }
+        }
+    }
+
+    fn get_rect(&self) -> Rect {
+        self.rect
+    }
+
+    fn handle_input(&mut self, event: &crate::input::InputEvent) -> bool {
+        self.handle_event(event).is_some()
+    }
+}
+
+/// Outcome of a key press handled by a `Menu`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Selected(usize),
+    Cancelled,
+}
+
+/// Popup menu with keyboard navigation. Esc cancels it, so unbind Esc from the
+/// `InputManager` quit keys while a menu is open or it arrives as `Quit`.
+pub struct Menu {
+    items: Vec<String>,
+    highlighted: usize,
+    anchor: (usize, usize),
+    /// Buffer size of the last `render`, so `get_rect` matches what was drawn
+    bounds: std::cell::Cell<(usize, usize)>,
+}
+
+impl Menu {
+    pub fn new(items: Vec<String>, anchor: (usize, usize)) -> Self {
+        Self {
+            items,
+            highlighted: 0,
+            anchor,
+            bounds: std::cell::Cell::new((usize::MAX, usize::MAX)),
+        }
+    }
+
+    pub fn set_anchor(&mut self, x: usize, y: usize) {
+        self.anchor = (x, y);
+    }
+
+    pub fn highlighted(&self) -> usize {
+        self.highlighted
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let longest = self.items.iter().map(|item| crate::text_display_width(item)).max().unwrap_or(0);
+        // Border plus one cell of padding on each side
+        (longest + 4, self.items.len() + 2)
+    }
+
+    /// Popup rect inside a buffer of the given size: flipped above the anchor
+    /// when it would overflow the bottom, shifted left when it would overflow the right
+    pub fn popup_rect(&self, buffer_width: usize, buffer_height: usize) -> Rect {
+        let (width, height) = self.size();
+        let (mut x, mut y) = self.anchor;
+        if y + height > buffer_height && y >= height {
+            y -= height;
+        }
+        if x + width > buffer_width {
+            x = buffer_width.saturating_sub(width);
+        }
+        Rect::new(x, y, width, height)
+    }
+
+    /// Handle Up/Down/Enter/Esc, returning the outcome once the menu closes
+    pub fn handle_event(&mut self, event: &crate::input::InputEvent) -> Option<MenuAction> {
+        use crossterm::event::KeyCode;
+
+        let crate::input::InputEvent::Key(code) = event else {
+            return None;
+        };
+        if self.items.is_empty() {
+            return matches!(code, KeyCode::Esc).then_some(MenuAction::Cancelled);
+        }
+
+        match code {
+            KeyCode::Up => {
+                self.highlighted = self.highlighted.checked_sub(1).unwrap_or(self.items.len() - 1);
+                None
+            }
+            KeyCode::Down => {
+                self.highlighted = (self.highlighted + 1) % self.items.len();
+                None
+            }
+            KeyCode::Enter => Some(MenuAction::Selected(self.highlighted)),
+            KeyCode::Esc => Some(MenuAction::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+impl Widget for Menu {
+    fn render(&self, buffer: &mut StyledFrameBuffer) {
+        self.bounds.set((buffer.width, buffer.height));
+        let rect = self.popup_rect(buffer.width, buffer.height);
+        buffer.draw_rect(rect, ' ', Some(Color::White), Some(Color::Gray));
+        buffer.draw_border(rect, Some(Color::White), Some(Color::Gray));
+
+        for (i, item) in self.items.iter().enumerate() {
+            let y = rect.y + 1 + i;
+            let (fg, bg) = if i == self.highlighted {
+                (Color::Black, Color::White)
+            } else {
+                (Color::White, Color::Gray)
+            };
+            let row = Rect::new(rect.x + 1, y, rect.width.saturating_sub(2), 1);
+            buffer.draw_rect(row, ' ', Some(fg), Some(bg));
+            buffer.draw_text(rect.x + 2, y, item, Some(fg), Some(bg));
+        }
+    }
+
+    /// Where the popup was last drawn (flipped and shifted to stay on screen);
+    /// before the first `render` the unclamped rect at the anchor
+    fn get_rect(&self) -> Rect {
+        let (width, height) = self.bounds.get();
+        self.popup_rect(width, height)
+    }
+
+    fn handle_input(&mut self, event: &crate::input::InputEvent) -> bool {
+        // A popup menu swallows every key while open
+        if matches!(event, crate::input::InputEvent::Key(_)) {
+            self.handle_event(event);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Table with fixed-width columns, a header and a scrollable row selection
+pub struct Table {
+    rect: Rect,
+    columns: Vec<(String, usize)>,
+    rows: Vec<Vec<String>>,
+    selected: usize,
+    scroll: usize,
+}
+
+impl Table {
+    /// `columns` holds each header title with its width in cells
+    pub fn new(rect: Rect, columns: Vec<(String, usize)>, rows: Vec<Vec<String>>) -> Self {
+        Self {
+            rect,
+            columns,
+            rows,
+            selected: 0,
+            scroll: 0,
+        }
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<Vec<String>>) {
+        self.rows = rows;
+        self.select(self.selected);
+    }
+
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    /// Index of the selected row, `None` when the table is empty
+    pub fn selected(&self) -> Option<usize> {
+        (!self.rows.is_empty()).then_some(self.selected)
+    }
+
+    /// Select a row (clamped) and scroll so it stays visible
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().saturating_sub(1));
+        let page = self.page_len().max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + page {
+            self.scroll = self.selected + 1 - page;
+        }
+    }
+
+    /// First visible row
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll
+    }
+
+    /// Number of body rows that fit below the header and its rule
+    fn page_len(&self) -> usize {
+        self.rect.height.saturating_sub(2)
+    }
+
+    fn draw_row(&self, buffer: &mut StyledFrameBuffer, y: usize, cells: &[String], fg: Color, bg: Color) {
+        let right = self.rect.x + self.rect.width;
+        buffer.draw_rect(Rect::new(self.rect.x, y, self.rect.width, 1), ' ', Some(fg), Some(bg));
+
+        let mut x = self.rect.x;
+        for (i, (_, width)) in self.columns.iter().enumerate() {
+            if x >= right {
+                break;
+            }
+            if i > 0 {
+                buffer.set(x, y, crate::StyledChar::new('|').with_fg(fg).with_bg(bg));
+                x += 1;
+            }
+            let width = (*width).min(right.saturating_sub(x));
+            let text = cells.get(i).map(|cell| crate::truncate_to_width(cell, width)).unwrap_or_default();
+            buffer.draw_text(x, y, text, Some(fg), Some(bg));
+            x += width;
+        }
+    }
+}
+
+impl Widget for Table {
+    fn render(&self, buffer: &mut StyledFrameBuffer) {
+        if self.rect.height == 0 {
+            return;
+        }
+
+        let headers: Vec<String> = self.columns.iter().map(|(title, _)| title.clone()).collect();
+        self.draw_row(buffer, self.rect.y, &headers, Color::Yellow, Color::Blue);
+        if self.rect.height > 1 {
+            buffer.draw_rect(Rect::new(self.rect.x, self.rect.y + 1, self.rect.width, 1), '-', Some(Color::Yellow), Some(Color::Blue));
+        }
+
+        let visible = self.rows.iter().enumerate().skip(self.scroll).take(self.page_len());
+        for (line, (index, row)) in visible.enumerate() {
+            // Selected row uses reversed colors
+            let (fg, bg) = if index == self.selected {
+                (Color::Black, Color::White)
+            } else {
+                (Color::White, Color::Black)
+            };
+            self.draw_row(buffer, self.rect.y + 2 + line, row, fg, bg);
+        }
+    }
+
+    fn get_rect(&self) -> Rect {
+        self.rect
+    }
+
+    fn handle_input(&mut self, event: &crate::input::InputEvent) -> bool {
+        use crossterm::event::KeyCode;
+
+        let page = self.page_len().max(1);
+        let target = match event {
+            crate::input::InputEvent::Key(code) => match code {
+                KeyCode::Up => self.selected.saturating_sub(1),
+                KeyCode::Down => self.selected + 1,
+                KeyCode::PageUp => self.selected.saturating_sub(page),
+                KeyCode::PageDown => self.selected + page,
+                KeyCode::Home => 0,
+                KeyCode::End => self.rows.len(),
+                _ => return false,
+            },
+            // Wheel up moves the selection towards the first row
+            crate::input::InputEvent::Scroll { x, y, delta } if self.rect.contains(*x as usize, *y as usize) => {
+                self.selected.saturating_add_signed(-*delta as isize)
+            }
+            _ => return false,
+        };
+        self.select(target);
+        true
+    }
+}
+
+/// Direction for `next_word_boundary`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordDirection {
+    Backward,
+    Forward,
+}
+
+/// Char class used to group runs: 0 whitespace, 1 word chars, 2 punctuation
+fn char_class(ch: char) -> u8 {
+    if ch.is_whitespace() {
+        0
+    } else if ch.is_alphanumeric() || ch == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+/// Char index reached from `pos` (a char index in `s`) moving by one word:
+/// whitespace is skipped, then a run of word chars (or of punctuation)
+pub fn next_word_boundary(s: &str, pos: usize, dir: WordDirection) -> usize {
+    let classes: Vec<u8> = s.chars().map(char_class).collect();
+    let mut pos = pos.min(classes.len());
+    match dir {
+        WordDirection::Forward => {
+            while pos < classes.len() && classes[pos] == 0 {
+                pos += 1;
+            }
+            if let Some(&class) = classes.get(pos) {
+                while pos < classes.len() && classes[pos] == class {
+                    pos += 1;
+                }
+            }
+        }
+        WordDirection::Backward => {
+            while pos > 0 && classes[pos - 1] == 0 {
+                pos -= 1;
+            }
+            if pos > 0 {
+                let class = classes[pos - 1];
+                while pos > 0 && classes[pos - 1] == class {
+                    pos -= 1;
+                }
+            }
+        }
+    }
+    pos
+}
+
+/// Multi-line text editor with a 2D cursor and a shift+arrows selection. Typing
+/// q (or Esc) needs those keys unbound from the `InputManager` quit keys.
+#[derive(Debug, Clone)]
+pub struct TextArea {
+    rect: Rect,
+    lines: Vec<String>,
+    /// Cursor as (row, col), col counted in chars
+    cursor: (usize, usize),
+    /// Fixed end of the selection; the cursor is the moving end
+    anchor: Option<(usize, usize)>,
+    /// First visible row and column
+    scroll: (usize, usize),
+}
+
+impl TextArea {
+    pub fn new(rect: Rect) -> Self {
+        Self {
+            rect,
+            lines: vec![String::new()],
+            cursor: (0, 0),
+            anchor: None,
+            scroll: (0, 0),
+        }
+    }
+
+    /// Whole content, lines joined with `\n`
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replace the content, moving the cursor to the start and dropping the selection
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.split('\n').map(|line| line.trim_end_matches('\r').to_string()).collect();
+        self.cursor = (0, 0);
+        self.anchor = None;
+        self.scroll = (0, 0);
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Move or resize the area, scrolling so the cursor stays visible
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.move_to(self.cursor.0, self.cursor.1);
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Move the cursor (clamped to the text) and drop the selection
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        self.anchor = None;
+        self.move_to(row, col);
+    }
+
+    /// Selected range as ordered (start, end) positions, end excluded
+    pub fn selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        let anchor = self.anchor.filter(|&anchor| anchor != self.cursor)?;
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        let mut text = String::new();
+        for row in start.0..=end.0 {
+            let line = &self.lines[row];
+            let from = if row == start.0 { start.1 } else { 0 };
+            let to = if row == end.0 { end.1 } else { self.line_len(row) };
+            text.push_str(&line[Self::byte_index(line, from)..Self::byte_index(line, to)]);
+            if row != end.0 {
+                text.push('\n');
+            }
+        }
+        Some(text)
+    }
+
+    /// Cursor position in buffer cells, `None` when scrolled out of the rect
+    pub fn cursor_screen_position(&self) -> Option<(usize, usize)> {
+        let (row, col) = self.cursor;
+        if row < self.scroll.0 || col < self.scroll.1 {
+            return None;
+        }
+        let (x, y) = (self.rect.x + col - self.scroll.1, self.rect.y + row - self.scroll.0);
+        self.rect.contains(x, y).then_some((x, y))
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].chars().count()
+    }
+
+    fn byte_index(line: &str, col: usize) -> usize {
+        line.char_indices().nth(col).map_or(line.len(), |(index, _)| index)
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) {
+        let row = row.min(self.lines.len() - 1);
+        self.cursor = (row, col.min(self.line_len(row)));
+
+        // Keep the cursor inside the visible rect
+        let (height, width) = (self.rect.height.max(1), self.rect.width.max(1));
+        if self.cursor.0 < self.scroll.0 {
+            self.scroll.0 = self.cursor.0;
+        } else if self.cursor.0 >= self.scroll.0 + height {
+            self.scroll.0 = self.cursor.0 + 1 - height;
+        }
+        if self.cursor.1 < self.scroll.1 {
+            self.scroll.1 = self.cursor.1;
+        } else if self.cursor.1 >= self.scroll.1 + width {
+            self.scroll.1 = self.cursor.1 + 1 - width;
+        }
+    }
+
+    /// Cursor target for a navigation key, `None` for other keys
+    fn navigate(&self, code: crossterm::event::KeyCode) -> Option<(usize, usize)> {
+        use crossterm::event::KeyCode;
+
+        let (row, col) = self.cursor;
+        let page = self.rect.height.max(1);
+        Some(match code {
+            KeyCode::Left if col > 0 => (row, col - 1),
+            KeyCode::Left if row > 0 => (row - 1, self.line_len(row - 1)),
+            KeyCode::Right if col < self.line_len(row) => (row, col + 1),
+            KeyCode::Right if row + 1 < self.lines.len() => (row + 1, 0),
+            KeyCode::Left | KeyCode::Right => (row, col),
+            KeyCode::Up => (row.saturating_sub(1), col),
+            KeyCode::Down => (row + 1, col),
+            KeyCode::PageUp => (row.saturating_sub(page), col),
+            KeyCode::PageDown => (row + page, col),
+            KeyCode::Home => (row, 0),
+            KeyCode::End => (row, usize::MAX),
+            _ => return None,
+        })
+    }
+
+    /// Cursor target one word away, wrapping to the neighbouring line at the edges
+    fn word_target(&self, dir: WordDirection) -> (usize, usize) {
+        let (row, col) = self.cursor;
+        match dir {
+            WordDirection::Backward if col == 0 && row > 0 => (row - 1, self.line_len(row - 1)),
+            WordDirection::Forward if col == self.line_len(row) && row + 1 < self.lines.len() => (row + 1, 0),
+            _ => (row, next_word_boundary(&self.lines[row], col, dir)),
+        }
+    }
+
+    /// Remove the selected text, leaving the cursor at its start
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        let tail = {
+            let line = &self.lines[end.0];
+            line[Self::byte_index(line, end.1)..].to_string()
+        };
+        let line = &mut self.lines[start.0];
+        line.truncate(Self::byte_index(line, start.1));
+        line.push_str(&tail);
+        self.lines.drain(start.0 + 1..=end.0);
+        self.anchor = None;
+        self.move_to(start.0, start.1);
+        true
+    }
+
+    fn insert_char(&mut self, ch: char) {
+        self.delete_selection();
+        let (row, col) = self.cursor;
+        let line = &mut self.lines[row];
+        line.insert(Self::byte_index(line, col), ch);
+        self.move_to(row, col + 1);
+    }
+
+    fn insert_newline(&mut self) {
+        self.delete_selection();
+        let (row, col) = self.cursor;
+        let line = &mut self.lines[row];
+        let tail = line.split_off(Self::byte_index(line, col));
+        self.lines.insert(row + 1, tail);
+        self.move_to(row + 1, 0);
+    }
+
+    fn backspace(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let (row, col) = self.cursor;
+        if col > 0 {
+            let line = &mut self.lines[row];
+            line.remove(Self::byte_index(line, col - 1));
+            self.move_to(row, col - 1);
+        } else if row > 0 {
+            // Join with the previous line
+            let line = self.lines.remove(row);
+            let join_col = self.line_len(row - 1);
+            self.lines[row - 1].push_str(&line);
+            self.move_to(row - 1, join_col);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let (row, col) = self.cursor;
+        if col < self.line_len(row) {
+            let line = &mut self.lines[row];
+            line.remove(Self::byte_index(line, col));
+        } else if row + 1 < self.lines.len() {
+            let next = self.lines.remove(row + 1);
+            self.lines[row].push_str(&next);
+        }
+    }
+}
+
+impl Widget for TextArea {
+    fn render(&self, buffer: &mut StyledFrameBuffer) {
+        buffer.draw_rect(self.rect, ' ', Some(Color::White), Some(Color::Black));
+
+        let selection = self.selection();
+        let visible = self.lines.iter().enumerate().skip(self.scroll.0).take(self.rect.height);
+        for (y, (row, line)) in visible.enumerate() {
+            let chars = line.chars().enumerate().skip(self.scroll.1).take(self.rect.width);
+            for (x, (col, ch)) in chars.enumerate() {
+                // Selected text uses reversed colors
+                let selected = selection.is_some_and(|(start, end)| (row, col) >= start && (row, col) < end);
+                let (fg, bg) = if selected { (Color::Black, Color::White) } else { (Color::White, Color::Black) };
+                buffer.set(self.rect.x + x, self.rect.y + y, crate::StyledChar::styled(ch, fg, bg));
+            }
+        }
+
+        if let Some((x, y)) = self.cursor_screen_position() {
+            let mut cell = buffer.get(x, y);
+            cell.fg_color = Some(Color::Black);
+            cell.bg_color = Some(Color::Yellow);
+            buffer.set(x, y, cell);
+        }
+    }
+
+    fn get_rect(&self) -> Rect {
+        self.rect
+    }
+
+    fn handle_input(&mut self, event: &crate::input::InputEvent) -> bool {
+        use crossterm::event::KeyCode;
+
+        match event {
+            crate::input::InputEvent::Shifted(code) => {
+                let Some((row, col)) = self.navigate(*code) else {
+                    return false;
+                };
+                self.anchor.get_or_insert(self.cursor);
+                self.move_to(row, col);
+            }
+            crate::input::InputEvent::Ctrl(code) => {
+                let dir = match code {
+                    KeyCode::Left | KeyCode::Backspace => WordDirection::Backward,
+                    KeyCode::Right | KeyCode::Delete => WordDirection::Forward,
+                    _ => return false,
+                };
+                let target = self.word_target(dir);
+                if matches!(code, KeyCode::Left | KeyCode::Right) {
+                    self.set_cursor(target.0, target.1);
+                } else {
+                    // Delete by word: select up to the boundary, then drop it
+                    if self.selection().is_none() {
+                        self.anchor = Some(target);
+                    }
+                    self.delete_selection();
+                }
+            }
+            crate::input::InputEvent::Key(code) => {
+                if let Some((row, col)) = self.navigate(*code) {
+                    self.set_cursor(row, col);
+                    return true;
+                }
+                match code {
+                    KeyCode::Char(ch) => self.insert_char(*ch),
+                    KeyCode::Enter => self.insert_newline(),
+                    KeyCode::Backspace => self.backspace(),
+                    KeyCode::Delete => self.delete(),
+                    _ => return false,
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Text kept as logical lines and wrapped to the display width on demand, so a
+/// log or editor pane reflows when its window is resized instead of truncating
+#[derive(Debug, Clone, Default)]
+pub struct TextBuffer {
+    lines: Vec<String>,
+    width: usize,
+    /// Wrapped rows for `width`, rebuilt lazily after the content or width changes
+    wrapped: Option<Vec<String>>,
+    /// First wrapped row drawn by `render_into`
+    scroll: usize,
+    pub fg_color: Option<Color>,
+    pub bg_color: Option<Color>,
+}
+
+impl TextBuffer {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    /// Replace the content; `\n` separates logical lines
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.split('\n').map(str::to_string).collect();
+        self.wrapped = None;
+    }
+
+    /// Append logical lines (split on `\n`). An already wrapped view is extended
+    /// instead of rebuilt, so appending to a long log stays cheap.
+    pub fn push_line(&mut self, line: &str) {
+        for line in line.split('\n') {
+            if let Some(wrapped) = &mut self.wrapped {
+                wrapped.extend(Self::wrap_line(line, self.width));
+            }
+            self.lines.push(line.to_string());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.wrapped = None;
+        self.scroll = 0;
+    }
+
+    /// Logical lines, independent of the width
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Change the wrap width; the text is re-wrapped the next time it's needed
+    pub fn set_width(&mut self, width: usize) {
+        if width != self.width {
+            self.width = width;
+            self.wrapped = None;
+        }
+    }
+
+    /// Rows of the wrapped view at the current width
+    pub fn wrapped_lines(&mut self) -> &[String] {
+        let (lines, width) = (&self.lines, self.width);
+        self.wrapped.get_or_insert_with(|| {
+            lines.iter().flat_map(|line| Self::wrap_line(line, width)).collect()
+        })
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// First wrapped row to draw (clamped when rendering)
+    pub fn set_scroll(&mut self, row: usize) {
+        self.scroll = row;
+    }
+
+    /// Wrap to `rect.width` and draw the rows from `scroll` inside `rect`, clearing
+    /// the rest of it. Returns the number of rows drawn.
+    pub fn render_into(&mut self, buffer: &mut StyledFrameBuffer, rect: Rect) -> usize {
+        self.set_width(rect.width);
+        let (fg_color, bg_color) = (self.fg_color, self.bg_color);
+        buffer.draw_rect(rect, ' ', fg_color, bg_color);
+
+        let rows = self.wrapped_lines().len();
+        self.scroll = self.scroll.min(rows.saturating_sub(rect.height));
+        let scroll = self.scroll;
+        let visible = self.wrapped_lines().iter().skip(scroll).take(rect.height);
+        let mut drawn = 0;
+        for (y, row) in visible.enumerate() {
+            let end = row.grapheme_indices(true).nth(rect.width).map_or(row.len(), |(index, _)| index);
+            buffer.draw_text(rect.x, rect.y + y, &row[..end], fg_color, bg_color);
+            drawn += 1;
+        }
+        drawn
+    }
+
+    /// Rows of one logical line, wrapped like `draw_text_wrapped` but keeping
+    /// whitespace and indentation (see `wrap_text_preserving`), so joining the
+    /// rows gives back the line. An empty line still takes a row.
+    fn wrap_line(line: &str, width: usize) -> Vec<String> {
+        if width > 0 && line.is_empty() {
+            return vec![String::new()];
+        }
+        crate::wrap_text_preserving(line, width)
+    }
+}
+
+/// Severity of a toast, which picks its colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl ToastLevel {
+    /// Foreground and background colors of the banner
+    pub fn colors(&self) -> (Color, Color) {
+        match self {
+            ToastLevel::Info => (Color::White, Color::Blue),
+            ToastLevel::Warn => (Color::Black, Color::Yellow),
+            ToastLevel::Error => (Color::White, Color::Red),
+        }
+    }
+}
+
+/// Screen edge a toast is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastPosition {
+    Top,
+    Bottom,
+}
+
+/// Draw a one-line colored banner spanning the whole buffer width
+pub fn show_toast(buffer: &mut StyledFrameBuffer, text: &str, level: ToastLevel, position: ToastPosition) {
+    if buffer.height == 0 {
+        return;
+    }
+    let y = match position {
+        ToastPosition::Top => 0,
+        ToastPosition::Bottom => buffer.height - 1,
+    };
+    let (fg, bg) = level.colors();
+    buffer.set_span(0, y, buffer.width, crate::StyledChar::new(' ').with_fg(fg).with_bg(bg));
+    buffer.draw_text(1, y, text, Some(fg), Some(bg));
+}
+
+/// Toast that disappears on its own once added to an `AnimationManager`
+pub struct Toast {
+    text: String,
+    level: ToastLevel,
+    position: ToastPosition,
+    remaining: std::time::Duration,
+}
+
+impl Toast {
+    pub fn new(text: String, level: ToastLevel, position: ToastPosition, duration: std::time::Duration) -> Self {
+        Self {
+            text,
+            level,
+            position,
+            remaining: duration,
+        }
+    }
+}
+
+impl crate::animation::Animation for Toast {
+    fn update(&mut self, delta_time: std::time::Duration) -> bool {
+        self.remaining = self.remaining.saturating_sub(delta_time);
+        self.remaining.is_zero()
+    }
+
+    fn apply(&self, buffer: &mut StyledFrameBuffer) {
+        show_toast(buffer, &self.text, self.level, self.position);
+    }
+}
+
 /// UI manager for handling multiple widgets
 pub struct UIManager {
     widgets: Vec<Box<dyn Widget>>,
@@ -88,3 +1015,63 @@ impl UIManager {
         }
     }
 }
+
+impl Default for UIManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps screen regions to opaque ids for mouse hit-testing.
+/// Regions with a higher z win; on equal z the most recently added wins,
+/// so populating the map in draw order gives the expected result.
+#[derive(Debug, Clone)]
+pub struct HitMap<Id: Copy> {
+    regions: Vec<(Rect, i32, Id)>,
+}
+
+impl<Id: Copy> HitMap<Id> {
+    pub fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, rect: Rect, id: Id) {
+        self.add_with_z(rect, id, 0);
+    }
+
+    pub fn add_with_z(&mut self, rect: Rect, id: Id, z_order: i32) {
+        self.regions.push((rect, z_order, id));
+    }
+
+    /// Topmost region containing the point, if any
+    pub fn hit(&self, x: usize, y: usize) -> Option<Id> {
+        let mut best: Option<(i32, Id)> = None;
+        for &(rect, z_order, id) in self.regions.iter().rev() {
+            if rect.contains(x, y) && best.is_none_or(|(z, _)| z_order > z) {
+                best = Some((z_order, id));
+            }
+        }
+        best.map(|(_, id)| id)
+    }
+
+    /// Remove all regions, typically at the start of each frame
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+impl<Id: Copy> Default for HitMap<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
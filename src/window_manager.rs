@@ -0,0 +1,353 @@
+//! Window manager: decorated, movable and resizable windows on top of the compositor
+
+use crate::compositor::{Compositor, Layer, LayerId};
+use crate::input::InputEvent;
+use crate::{BorderStyle, Color, Rect, StyledChar, StyledFrameBuffer};
+use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
+
+/// Smallest window the manager allows: the border plus one client cell
+const MIN_WINDOW_SIZE: (usize, usize) = (3, 3);
+
+/// Window settings; its surface lives in the backing compositor layer
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub title: String,
+    pub border: BorderStyle,
+    /// Size limits applied to every move/resize (clamped to at least 3x3)
+    pub min_size: (usize, usize),
+    pub max_size: (usize, usize),
+}
+
+impl Window {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            border: BorderStyle::Single,
+            min_size: MIN_WINDOW_SIZE,
+            max_size: (usize::MAX, usize::MAX),
+        }
+    }
+
+    pub fn with_min_size(mut self, width: usize, height: usize) -> Self {
+        self.min_size = (width, height);
+        self
+    }
+
+    pub fn with_max_size(mut self, width: usize, height: usize) -> Self {
+        self.max_size = (width, height);
+        self
+    }
+
+    /// Size clamped to the window limits
+    fn constrain(&self, width: usize, height: usize) -> (usize, usize) {
+        let min_w = self.min_size.0.max(MIN_WINDOW_SIZE.0);
+        let min_h = self.min_size.1.max(MIN_WINDOW_SIZE.1);
+        (
+            width.min(self.max_size.0).max(min_w),
+            height.min(self.max_size.1).max(min_h),
+        )
+    }
+}
+
+/// What a mouse or key event did to the windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowEvent {
+    Focused(LayerId),
+    Moved(LayerId),
+    /// The surface was resized: its client area needs redrawing
+    Resized(LayerId),
+    /// The close button was clicked; the window stays open until `close`
+    CloseRequested(LayerId),
+}
+
+/// Drag in progress, started by a press on a title bar or on the resize edges
+#[derive(Debug, Clone, Copy)]
+enum Drag {
+    /// `grab` is the pressed cell relative to the window origin
+    Move { id: LayerId, grab: (usize, usize) },
+    /// Which edges follow the mouse: right, bottom
+    Resize { id: LayerId, right: bool, bottom: bool },
+}
+
+/// Manages windows backed by compositor layers: title bars with a close button,
+/// move by dragging the title bar, resize by dragging the right/bottom edges,
+/// click to focus and raise. Windows stay inside the manager bounds.
+pub struct WindowManager {
+    compositor: Compositor,
+    windows: Vec<(LayerId, Window)>,
+    bounds: Rect,
+    focused: Option<LayerId>,
+    drag: Option<Drag>,
+}
+
+impl WindowManager {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            compositor: Compositor::new(width, height),
+            windows: Vec::new(),
+            bounds: Rect::new(0, 0, width, height),
+            focused: None,
+            drag: None,
+        }
+    }
+
+    /// Change the area windows are kept inside, moving them back in if needed
+    pub fn set_bounds(&mut self, width: usize, height: usize) {
+        self.bounds = Rect::new(0, 0, width, height);
+        for id in self.window_ids() {
+            if let Some(rect) = self.rect(id) {
+                self.set_rect(id, rect);
+            }
+        }
+    }
+
+    /// Open a window at `rect` (size constrained, kept inside the bounds) and focus it
+    pub fn open(&mut self, window: Window, rect: Rect) -> LayerId {
+        let (width, height) = window.constrain(rect.width, rect.height);
+        let id = self.compositor.add_layer(Layer::new(width, height, rect.x, rect.y));
+        self.windows.push((id, window));
+        self.set_rect(id, rect);
+        self.focus(id);
+        id
+    }
+
+    /// Close a window, focusing the topmost remaining one
+    pub fn close(&mut self, id: LayerId) -> Option<Window> {
+        let index = self.windows.iter().position(|(window_id, _)| *window_id == id)?;
+        self.compositor.remove_layer(id);
+        if matches!(self.drag, Some(Drag::Move { id: drag_id, .. } | Drag::Resize { id: drag_id, .. }) if drag_id == id) {
+            self.drag = None;
+        }
+        if self.focused == Some(id) {
+            self.focused = self.shown_ids().last().copied();
+        }
+        Some(self.windows.remove(index).1)
+    }
+
+    pub fn window(&self, id: LayerId) -> Option<&Window> {
+        self.windows.iter().find(|(window_id, _)| *window_id == id).map(|(_, window)| window)
+    }
+
+    pub fn window_mut(&mut self, id: LayerId) -> Option<&mut Window> {
+        self.windows.iter_mut().find(|(window_id, _)| *window_id == id).map(|(_, window)| window)
+    }
+
+    /// Window ids from bottom to top
+    pub fn window_ids(&self) -> Vec<LayerId> {
+        self.compositor.layer_ids()
+    }
+
+    pub fn focused(&self) -> Option<LayerId> {
+        self.focused
+    }
+
+    /// Ids of the windows that aren't minimized, from bottom to top
+    fn shown_ids(&self) -> Vec<LayerId> {
+        self.compositor.layer_ids().into_iter().filter(|&id| self.compositor.is_visible(id)).collect()
+    }
+
+    /// Minimize (hide) or restore a window. Minimizing the focused window focuses
+    /// the topmost remaining one; restoring focuses and raises the window.
+    /// Returns false if there's no window with that id.
+    pub fn set_minimized(&mut self, id: LayerId, minimized: bool) -> bool {
+        if self.window(id).is_none() {
+            return false;
+        }
+        self.compositor.set_visible(id, !minimized);
+        if !minimized {
+            self.focus(id);
+        } else if self.focused == Some(id) {
+            if matches!(self.drag, Some(Drag::Move { id: drag_id, .. } | Drag::Resize { id: drag_id, .. }) if drag_id == id) {
+                self.drag = None;
+            }
+            self.focused = self.shown_ids().last().copied();
+        }
+        true
+    }
+
+    pub fn is_minimized(&self, id: LayerId) -> bool {
+        self.window(id).is_some() && !self.compositor.is_visible(id)
+    }
+
+    /// Alt+Tab style cycling: focus the bottom window that isn't minimized and raise
+    /// it, so repeated calls visit every window in z-order and wrap around.
+    /// Returns the newly focused window.
+    pub fn focus_next(&mut self) -> Option<LayerId> {
+        let next = *self.shown_ids().first()?;
+        self.focus(next);
+        self.focused
+    }
+
+    /// Reverse of `focus_next`: send the top window to the back and focus the one
+    /// that was below it
+    pub fn focus_prev(&mut self) -> Option<LayerId> {
+        let shown = self.shown_ids();
+        let (&top, rest) = shown.split_last()?;
+        if let Some(&below) = rest.last() {
+            self.compositor.send_to_back(top);
+            self.focus(below);
+        } else {
+            self.focus(top);
+        }
+        self.focused
+    }
+
+    /// Focus a window and raise it above the others
+    pub fn focus(&mut self, id: LayerId) {
+        if self.window(id).is_some() {
+            self.compositor.bring_to_front(id);
+            self.focused = Some(id);
+        }
+    }
+
+    /// Outer rect of a window, decorations included
+    pub fn rect(&self, id: LayerId) -> Option<Rect> {
+        self.compositor.layer(id).map(Layer::rect)
+    }
+
+    /// Move/resize a window; the size is constrained and the rect kept inside the
+    /// bounds. Returns the rect applied.
+    pub fn set_rect(&mut self, id: LayerId, rect: Rect) -> Option<Rect> {
+        // Never larger than the bounds, unless the minimum size requires it
+        let (width, height) = self.window(id)?
+            .constrain(rect.width.min(self.bounds.width), rect.height.min(self.bounds.height));
+        let rect = Rect::new(rect.x, rect.y, width, height).moved_within(0, 0, self.bounds);
+
+        let layer = self.compositor.layer_mut(id)?;
+        if (layer.buffer.width, layer.buffer.height) != (width, height) {
+            layer.buffer.resize(width, height);
+        }
+        layer.position = (rect.x, rect.y);
+        Some(rect)
+    }
+
+    /// Client area inside the border, in surface coordinates
+    pub fn client_rect(&self, id: LayerId) -> Option<Rect> {
+        let rect = self.rect(id)?;
+        Some(Rect::new(1, 1, rect.width.saturating_sub(2), rect.height.saturating_sub(2)))
+    }
+
+    /// Whole window surface: draw the content inside `client_rect`, the border
+    /// and title bar are redrawn by `render_into`
+    pub fn surface_mut(&mut self, id: LayerId) -> Option<&mut StyledFrameBuffer> {
+        self.compositor.layer_mut(id).map(|layer| &mut layer.buffer)
+    }
+
+    /// Topmost window at (x, y)
+    pub fn window_at(&self, x: usize, y: usize) -> Option<LayerId> {
+        self.compositor.hit_top_layer(x, y)
+    }
+
+    /// Press: focus, then start a move on the title bar or a resize on the
+    /// right/bottom edge. Drag: follow the mouse. Release: end the drag.
+    pub fn handle_mouse(&mut self, x: usize, y: usize, kind: MouseEventKind) -> Option<WindowEvent> {
+        match kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let id = self.window_at(x, y)?;
+                let rect = self.rect(id)?;
+                let (local_x, local_y) = (x - rect.x, y - rect.y);
+                let right = local_x == rect.width - 1;
+                let bottom = local_y == rect.height - 1;
+
+                if local_y == 0 && local_x == rect.width.saturating_sub(3) {
+                    return Some(WindowEvent::CloseRequested(id));
+                }
+                if right || bottom {
+                    self.drag = Some(Drag::Resize { id, right, bottom });
+                } else if local_y == 0 {
+                    self.drag = Some(Drag::Move { id, grab: (local_x, local_y) });
+                }
+                let was_focused = self.focused == Some(id);
+                self.focus(id);
+                (!was_focused).then_some(WindowEvent::Focused(id))
+            }
+            MouseEventKind::Drag(MouseButton::Left) => match self.drag? {
+                Drag::Move { id, grab } => {
+                    let rect = self.rect(id)?;
+                    let target = Rect::new(x.saturating_sub(grab.0), y.saturating_sub(grab.1), rect.width, rect.height);
+                    (self.set_rect(id, target)? != rect).then_some(WindowEvent::Moved(id))
+                }
+                Drag::Resize { id, right, bottom } => {
+                    let rect = self.rect(id)?;
+                    let width = if right { (x + 1).saturating_sub(rect.x) } else { rect.width };
+                    let height = if bottom { (y + 1).saturating_sub(rect.y) } else { rect.height };
+                    let target = Rect::new(rect.x, rect.y, width, height);
+                    (self.set_rect(id, target)? != rect).then_some(WindowEvent::Resized(id))
+                }
+            },
+            MouseEventKind::Up(_) => {
+                self.drag = None;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Keyboard control of the focused window: Shift+arrows move it by one cell,
+    /// Ctrl+arrows resize it
+    pub fn handle_key(&mut self, event: &InputEvent) -> Option<WindowEvent> {
+        let id = self.focused?;
+        let rect = self.rect(id)?;
+        let step = |code: &KeyCode| match code {
+            KeyCode::Left => Some((-1, 0)),
+            KeyCode::Right => Some((1, 0)),
+            KeyCode::Up => Some((0, -1)),
+            KeyCode::Down => Some((0, 1)),
+            _ => None,
+        };
+        match event {
+            InputEvent::Shifted(code) => {
+                let (dx, dy) = step(code)?;
+                let target = rect.moved_within(dx, dy, self.bounds);
+                (self.set_rect(id, target)? != rect).then_some(WindowEvent::Moved(id))
+            }
+            InputEvent::Ctrl(code) => {
+                let (dw, dh) = step(code)?;
+                let target = Rect::new(
+                    rect.x,
+                    rect.y,
+                    rect.width.saturating_add_signed(dw),
+                    rect.height.saturating_add_signed(dh),
+                );
+                (self.set_rect(id, target)? != rect).then_some(WindowEvent::Resized(id))
+            }
+            _ => None,
+        }
+    }
+
+    /// Redraw the decorations and draw every window into `dst`, bottom first
+    pub fn render_into(&mut self, dst: &mut StyledFrameBuffer) {
+        for (id, window) in &self.windows {
+            let focused = self.focused == Some(*id);
+            if let Some(layer) = self.compositor.layer_mut(*id) {
+                Self::draw_decorations(&mut layer.buffer, window, focused);
+            }
+        }
+
+        for id in self.compositor.layer_ids() {
+            if let Some(layer) = self.compositor.layer(id).filter(|layer| layer.visible) {
+                let src = Rect::new(0, 0, layer.buffer.width, layer.buffer.height);
+                dst.blit(&layer.buffer, src, layer.position.0, layer.position.1);
+            }
+        }
+    }
+
+    /// Border, title and close button on the top border row
+    fn draw_decorations(surface: &mut StyledFrameBuffer, window: &Window, focused: bool) {
+        let (fg, bg) = if focused { (Color::Yellow, Color::Blue) } else { (Color::Gray, Color::Black) };
+        let (width, height) = (surface.width, surface.height);
+        if width < 2 || height < 2 {
+            return;
+        }
+        surface.draw_border_chars(0, 0, width - 1, height - 1, window.border, StyledChar::styled(' ', fg, bg));
+
+        // Title after the corner, close button before the right corner
+        let title = crate::truncate_to_width(&window.title, width.saturating_sub(6));
+        if !title.is_empty() {
+            surface.draw_text(2, 0, title, Some(Color::White), Some(bg));
+        }
+        if width >= 5 {
+            surface.set(width - 3, 0, StyledChar::styled('x', Color::Red, bg));
+        }
+    }
+}